@@ -0,0 +1,73 @@
+//! Compares reading one field of a record through its derived view against
+//! deserializing the whole record.
+//!
+//! Run with `cargo bench --bench view`.
+use std::time::Instant;
+
+use nautilus::*;
+
+#[derive(Table)]
+pub struct Listing {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    pub seller: Pubkey,
+    pub price: u64,
+    pub title: String,
+    pub description: String,
+    pub images: Vec<String>,
+}
+
+const ITERATIONS: u32 = 100_000;
+
+fn main() {
+    let bytes = Listing {
+        id: 1,
+        seller: Pubkey::new_unique(),
+        price: 1_000,
+        title: "A".repeat(64),
+        description: "B".repeat(2_048),
+        images: vec!["C".repeat(128); 16],
+    }
+    .try_to_vec()
+    .unwrap();
+
+    // Every read feeds a checksum, printed at the end, so none are optimized
+    // away.
+    let mut checksum = 0u64;
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        checksum += Listing::try_from_slice(&bytes).unwrap().price;
+    }
+    let full = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        checksum += ListingView::new(&bytes).unwrap().price().unwrap();
+    }
+    let view = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        checksum += ListingView::new(&bytes)
+            .unwrap()
+            .description()
+            .unwrap()
+            .len() as u64;
+    }
+    let borrowed = start.elapsed();
+
+    println!(
+        "full deserialize, then read `price`: {:?}",
+        full / ITERATIONS
+    );
+    println!(
+        "view, read `price`:                  {:?}",
+        view / ITERATIONS
+    );
+    println!(
+        "view, borrow `description`:          {:?}",
+        borrowed / ITERATIONS
+    );
+    println!("checksum: {}", checksum);
+}
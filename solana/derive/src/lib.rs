@@ -24,7 +24,42 @@ pub fn nautilus(_: TokenStream, input: TokenStream) -> TokenStream {
 /// The derive macro to implement the required traits to allow for the annotated
 /// struct to serve as the data type for a Nautilus record - allowing it to be
 /// used as `T` inside of `Record<'_, T>`.
-#[proc_macro_derive(Table, attributes(default_instructions, primary_key, authority))]
+///
+/// Trailing fields added to an existing table can be marked
+/// `#[default_on_missing]`, so that accounts written before the fields existed
+/// still deserialize, with their defaults (see
+/// `nautilus::deserialize_or_default(..)`).
+///
+/// Sensitive fields can be marked `#[nautilus(audit)]`, which generates a
+/// `get_<field>(&self, accessor: &Pubkey)` getter that logs each access (see
+/// `nautilus::log_field_access(..)`).
+///
+/// Fields marked `#[authority]` must sign for the record:
+/// `check_authorities(..)` fails with `NautilusError::MissingAuthority` unless
+/// an account whose key each of them holds is among the signers. A `Pubkey`
+/// field marked `#[nautilus(authority_is_payer)]` is an authority stamped with
+/// the key of whoever pays to create the record - `create(..)`'s fee payer or
+/// `create_with_payer(..)`'s payer - rather than passed in, for records owned
+/// by their creator. When a relayer pays on a user's behalf, create the record
+/// with `create_with_authority(..)` instead, which stamps the signing user as
+/// the authority and has the relayer pay: otherwise the relayer, not the
+/// record's logical owner, would be the one authorized to write it.
+///
+/// A `Pubkey` field marked `#[nautilus(last_writer)]` records whoever last
+/// wrote the record, for audit (see `nautilus::LastWriter`). It's stamped like
+/// an `authority_is_payer` field on create - with the authority, through
+/// `create_with_authority(..)` - and with the updating signer by
+/// `Record::update_as(..)`, but never checked.
+#[proc_macro_derive(
+    Table,
+    attributes(
+        default_instructions,
+        primary_key,
+        authority,
+        default_on_missing,
+        nautilus
+    )
+)]
 pub fn nautilus_table(input: TokenStream) -> TokenStream {
     let item_struct = parse_macro_input!(input as ItemStruct);
     NautilusObject::from_item_struct(
@@ -38,7 +73,10 @@ pub fn nautilus_table(input: TokenStream) -> TokenStream {
 /// The derive macro to implement the required traits to allow for the annotated
 /// struct to serve as the data type for a Nautilus account - allowing it to be
 /// used as `T` inside of `Account<'_, T>`.
-#[proc_macro_derive(State, attributes(seeds, authority))]
+///
+/// As with `#[derive(nautilus::Table)]`, trailing fields can be marked
+/// `#[default_on_missing]`, and sensitive fields `#[nautilus(audit)]`.
+#[proc_macro_derive(State, attributes(seeds, authority, default_on_missing, nautilus))]
 pub fn nautilus_account(input: TokenStream) -> TokenStream {
     let item_struct = parse_macro_input!(input as ItemStruct);
     NautilusObject::from_item_struct(
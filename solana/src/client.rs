@@ -0,0 +1,38 @@
+//! Helpers for off-chain clients deriving the addresses of Nautilus records and
+//! estimating their costs, enabled by the `client` feature.
+use solana_program::{pubkey::Pubkey, rent::Rent};
+
+use crate::NautilusRecordData;
+
+pub use crate::{
+    find_child, nautilus_index_pda, next_pda, schema_hash, schema_registry_pda,
+    seeds::{
+        child_record_seeds, compose_key, decompose_key, normalize_table_name, record_seeds,
+        variant_table_name,
+    },
+    SchemaRegistry,
+};
+
+/// Returns the program-derived address and bump of the record with
+/// `primary_key` in table `table_name` - the same address the program derives
+/// with `NautilusRecordData::pda(..)`.
+pub fn record_pda(program_id: &Pubkey, table_name: &str, primary_key: &[u8]) -> (Pubkey, u8) {
+    let seeds_vec = record_seeds(table_name, primary_key);
+    let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+    Pubkey::find_program_address(&seeds, program_id)
+}
+
+/// Client-side estimates of the rent a record's account costs, so apps can show
+/// users the cost of creating a record before submitting the transaction.
+pub trait EstimateRent: NautilusRecordData {
+    /// The rent-exempt minimum balance for this record's account under the
+    /// default rent schedule, computed from the same span
+    /// (`NautilusRecordData::span()`) the program uses for `required_rent()`.
+    ///
+    /// Panics if the record fails to serialize.
+    fn estimate_rent(&self) -> u64 {
+        Rent::default().minimum_balance(self.span().expect("Failed to serialize record data"))
+    }
+}
+
+impl<T: NautilusRecordData> EstimateRent for T {}
@@ -4,7 +4,7 @@ use solana_program::{
     entrypoint::ProgramResult,
     program::{invoke, invoke_signed},
     pubkey::Pubkey,
-    system_instruction,
+    system_instruction, system_program,
 };
 
 use crate::{NautilusAccountInfo, NautilusMut, NautilusSigner};
@@ -64,10 +64,40 @@ pub fn create_pda<'a, T: BorshSerialize>(
         &[*payer.account_info(), *new_account.account_info()],
         &[&signer_seeds],
     )?;
-    data.serialize(&mut &mut new_account.account_info().data.borrow_mut()[..])?;
+    data.serialize(&mut &mut new_account.account_info_ref().data.borrow_mut()[..])?;
     Ok(())
 }
 
+/// Cross-Program Invocation (CPI) to create a program-derived address account
+/// (PDA) owned by a program other than the one it's derived from.
+///
+/// The account is first created as a system account with space for its data,
+/// then assigned to `owner`, both signed with the PDA's seeds. Its data is left
+/// zeroed for `owner` to initialize.
+pub fn create_pda_owned_by<'a>(
+    new_account: impl NautilusAccountInfo<'a>,
+    owner: &Pubkey,
+    payer: impl NautilusSigner<'a>,
+    signer_seeds: Vec<&[u8]>,
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key(),
+            new_account.key(),
+            new_account.required_rent()?,
+            new_account.size()?,
+            &system_program::ID,
+        ),
+        &[*payer.account_info(), *new_account.account_info()],
+        &[&signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(new_account.key(), owner),
+        &[*new_account.account_info()],
+        &[&signer_seeds],
+    )
+}
+
 /// Transfer lamports from an account owned by the system program.
 pub fn transfer<'a>(
     from: impl NautilusSigner<'a>,
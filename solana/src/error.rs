@@ -3,6 +3,7 @@ use num_traits::{FromPrimitive, ToPrimitive};
 use solana_program::{
     decode_error::DecodeError,
     program_error::{PrintProgramError, ProgramError},
+    pubkey::MAX_SEEDS,
 };
 use splogger::{error, Splog};
 use thiserror::Error;
@@ -34,6 +35,158 @@ pub enum NautilusError {
     /// The underlying account for a `Create<T>` declared object already exists.
     #[error("The underlying account for a `Create<T>` declared object already exists.")]
     AccountExists(String),
+    /// An account's data would grow by more than the runtime allows within a
+    /// single instruction.
+    #[error(
+        "An account's data would grow by more than the runtime allows within a single instruction."
+    )]
+    ReallocTooLarge(String, usize),
+    /// The program that owns a record's underlying account is not the program
+    /// its address was derived from.
+    #[error("The program that owns a record's underlying account is not the program its address was derived from.")]
+    OwnerMismatch(String, String, String),
+    /// An `InlineVec` already holds as many elements as its capacity allows.
+    #[error("An `InlineVec` already holds as many elements as its capacity allows.")]
+    InlineVecFull(usize),
+    /// A table could not be found in the Nautilus Index.
+    #[error("A table could not be found in the Nautilus Index.")]
+    TableNotFound(String),
+    /// A cascade's dependencies between records form a cycle, so there is no
+    /// safe order to delete them in.
+    #[error("A cascade's dependencies between records form a cycle, so there is no safe order to delete them in.")]
+    CascadeCycle(String, u32),
+    /// A Pubkey field that must be set still holds the default (all-zeros) key.
+    #[error("A Pubkey field that must be set still holds the default (all-zeros) key.")]
+    DefaultKey(String),
+    /// A Pubkey field that must hold a wallet address holds a key that is not on
+    /// the ed25519 curve, such as a program-derived address.
+    #[error("A Pubkey field that must hold a wallet address holds a key that is not on the ed25519 curve.")]
+    KeyNotOnCurve(String, String),
+    /// The Nautilus Index is locked by another session, whose lock has not yet
+    /// expired.
+    #[error("The Nautilus Index is locked by another session, whose lock has not yet expired.")]
+    IndexLocked(String, String, u64),
+    /// The lamport moves of a rebalance don't sum to zero, so lamports would be
+    /// created or destroyed.
+    #[error("The lamport moves of a rebalance don't sum to zero, so lamports would be created or destroyed.")]
+    RebalanceNotZeroSum(i128),
+    /// A rebalance would leave an account with a negative balance, or one that
+    /// overflows a u64.
+    #[error(
+        "A rebalance would leave an account with a negative balance, or one that overflows a u64."
+    )]
+    RebalanceOutOfRange(String, i128),
+    /// The account passed in is not the record expected for a given key, since its
+    /// address was not derived from that key.
+    #[error("The account passed in is not the record expected for a given key.")]
+    RecordNotFound(String, String, String),
+    /// A value of a table's unique field is already held by a different record.
+    #[error("A value of a table's unique field is already held by a different record.")]
+    UniqueConstraintViolation(String, String),
+    /// An account's data does not begin with the discriminator of the data type it
+    /// was expected to hold.
+    #[error("An account's data does not begin with the discriminator of the data type it was expected to hold.")]
+    DiscriminatorMismatch(String, String),
+    /// A program-derived address would be derived from more seeds than the runtime
+    /// allows.
+    #[error("A program-derived address would be derived from more seeds than the runtime allows.")]
+    TooManySeeds(usize),
+    /// A collection is too long for the width of its length prefix.
+    #[error("A collection is too long for the width of its length prefix.")]
+    LengthPrefixOverflow(usize, usize),
+    /// The index was last mutated longer ago than the caller will accept.
+    #[error("The index was last mutated longer ago than the caller will accept.")]
+    StaleIndex(String, u64, u64),
+    /// A string field is longer than its maximum length.
+    #[error("A string field is longer than its maximum length.")]
+    StringTooLong(String, usize, usize),
+    /// A string field holds characters it must not, such as control characters.
+    #[error("A string field holds characters it must not, such as control characters.")]
+    InvalidString(String),
+    /// A record's version changed between when it was read and written.
+    #[error("A record's version changed between when it was read and written.")]
+    VersionConflict(String, u64, u64),
+    /// An operation is gated on a record flag that isn't set.
+    #[error("An operation is gated on a record flag that isn't set.")]
+    FlagNotSet(u64),
+    /// A record id is outside the range of ids its table's count has assigned.
+    #[error("A record id is outside the range of ids its table's count has assigned.")]
+    IdOutOfRange(String, u32, u32),
+    /// The Nautilus Index is frozen by its admin, so it can't be mutated until
+    /// it's unfrozen.
+    #[error(
+        "The Nautilus Index is frozen by its admin, so it can't be mutated until it's unfrozen."
+    )]
+    IndexFrozen(String),
+    /// The signer is not the admin of the Nautilus Index, or the index has no
+    /// admin.
+    #[error("The signer is not the admin of the Nautilus Index, or the index has no admin.")]
+    NotIndexAdmin(String, String),
+    /// A voucher for the Nautilus Index wasn't signed by its voucher authority,
+    /// or doesn't authorize the record being added.
+    #[error("A voucher for the Nautilus Index wasn't signed by its voucher authority, or doesn't authorize the record being added.")]
+    InvalidVoucher(String),
+    /// A voucher for the Nautilus Index has a nonce no greater than the last
+    /// one redeemed, so it may already have been used.
+    #[error("A voucher for the Nautilus Index has a nonce no greater than the last one redeemed.")]
+    VoucherReplayed(u64, u64),
+    /// The overflow accounts passed for a chained record don't match the chain
+    /// its head account links to.
+    #[error("The overflow accounts passed for a chained record don't match the chain its head account links to.")]
+    OverflowChainMismatch(String),
+    /// Too few overflow accounts were passed to hold a chained record's data.
+    #[error("Too few overflow accounts were passed to hold a chained record's data.")]
+    OverflowChainTooShort(usize, usize),
+    /// The Nautilus Index was mutated between when a two-phase commit was prepared
+    /// and when it was committed.
+    #[error("The Nautilus Index was mutated between when a two-phase commit was prepared and committed.")]
+    StaleCommit(u64, u64),
+    /// A record type's schema hash doesn't match the one registered for its table
+    /// in the program's schema registry, or none is registered.
+    #[error("A record type's schema hash doesn't match the one registered for its table.")]
+    SchemaMismatch(String),
+    /// An authority of a record didn't sign the instruction.
+    #[error("An authority of a record didn't sign the instruction.")]
+    MissingAuthority(String),
+    /// A table's id stride is invalid - its start or stride is zero - or is being
+    /// set after the table has assigned ids.
+    #[error("A table's id stride is invalid, or the table has already assigned ids.")]
+    InvalidIdStride(String),
+    /// A table's next id would overflow a u32.
+    #[error("A table's next id would overflow a u32.")]
+    IdSpaceExhausted(String),
+    /// A record's underlying account is not at the program-derived address of its
+    /// seeds.
+    #[error("A record's underlying account is not at the program-derived address of its seeds.")]
+    AddressMismatch(String, String),
+    /// A checked arithmetic operation on a `Decimal` overflowed its mantissa.
+    #[error("A checked arithmetic operation on a `Decimal` overflowed its mantissa.")]
+    ArithmeticOverflow,
+    /// A string could not be parsed as a `Decimal`.
+    #[error("A string could not be parsed as a `Decimal`.")]
+    InvalidDecimal(String),
+    /// A payer created records in a rate-limited table faster than its rate limit
+    /// allows.
+    #[error("A payer created records in a rate-limited table faster than its rate limit allows.")]
+    RateLimited(String, u64),
+    /// A record whose table declares no authority was closed by burning its rent.
+    #[error("A record whose table declares no authority was closed by burning its rent.")]
+    BurnWithoutAuthority(String),
+    /// The field values measured for a record are missing one of its fields.
+    #[error("The field values measured for a record are missing one of its fields.")]
+    MissingFieldValue(String, String),
+    /// The field values measured for a record hold a field it doesn't have, or a
+    /// value that doesn't deserialize as its field's type.
+    #[error("The field values measured for a record hold a field it doesn't have, or a value that doesn't deserialize as its field's type.")]
+    InvalidFieldValue(String, String),
+    /// A record whose table declares no authority was closed with its rent
+    /// refunded to an account of the caller's choosing.
+    #[error("A record whose table declares no authority was closed with its rent refunded to an account of the caller's choosing.")]
+    CloseWithoutAuthority(String),
+    /// A copy of the Nautilus Index with mutations it hasn't written was
+    /// refreshed after another copy wrote the account.
+    #[error("A copy of the Nautilus Index with mutations it hasn't written was refreshed after another copy wrote the account.")]
+    IndexWriteConflict(String, u64, u64),
 }
 
 impl<T> DecodeError<T> for NautilusError {
@@ -54,6 +207,100 @@ impl FromPrimitive for NautilusError {
             203 => Some(Self::AccountNotMutable(String::default())),
             204 => Some(Self::AccountNotSigner(String::default())),
             205 => Some(Self::AccountExists(String::default())),
+            206 => Some(Self::ReallocTooLarge(String::default(), usize::default())),
+            207 => Some(Self::OwnerMismatch(
+                String::default(),
+                String::default(),
+                String::default(),
+            )),
+            208 => Some(Self::InlineVecFull(usize::default())),
+            209 => Some(Self::TableNotFound(String::default())),
+            210 => Some(Self::CascadeCycle(String::default(), u32::default())),
+            211 => Some(Self::DefaultKey(String::default())),
+            212 => Some(Self::KeyNotOnCurve(String::default(), String::default())),
+            213 => Some(Self::IndexLocked(
+                String::default(),
+                String::default(),
+                u64::default(),
+            )),
+            214 => Some(Self::RebalanceNotZeroSum(i128::default())),
+            215 => Some(Self::RebalanceOutOfRange(
+                String::default(),
+                i128::default(),
+            )),
+            216 => Some(Self::RecordNotFound(
+                String::default(),
+                String::default(),
+                String::default(),
+            )),
+            217 => Some(Self::UniqueConstraintViolation(
+                String::default(),
+                String::default(),
+            )),
+            218 => Some(Self::DiscriminatorMismatch(
+                String::default(),
+                String::default(),
+            )),
+            219 => Some(Self::TooManySeeds(usize::default())),
+            220 => Some(Self::LengthPrefixOverflow(
+                usize::default(),
+                usize::default(),
+            )),
+            221 => Some(Self::StaleIndex(
+                String::default(),
+                u64::default(),
+                u64::default(),
+            )),
+            222 => Some(Self::StringTooLong(
+                String::default(),
+                usize::default(),
+                usize::default(),
+            )),
+            223 => Some(Self::InvalidString(String::default())),
+            224 => Some(Self::VersionConflict(
+                String::default(),
+                u64::default(),
+                u64::default(),
+            )),
+            225 => Some(Self::FlagNotSet(u64::default())),
+            226 => Some(Self::IdOutOfRange(
+                String::default(),
+                u32::default(),
+                u32::default(),
+            )),
+            227 => Some(Self::IndexFrozen(String::default())),
+            228 => Some(Self::NotIndexAdmin(String::default(), String::default())),
+            229 => Some(Self::InvalidVoucher(String::default())),
+            230 => Some(Self::VoucherReplayed(u64::default(), u64::default())),
+            231 => Some(Self::OverflowChainMismatch(String::default())),
+            232 => Some(Self::OverflowChainTooShort(
+                usize::default(),
+                usize::default(),
+            )),
+            233 => Some(Self::StaleCommit(u64::default(), u64::default())),
+            234 => Some(Self::SchemaMismatch(String::default())),
+            235 => Some(Self::MissingAuthority(String::default())),
+            236 => Some(Self::InvalidIdStride(String::default())),
+            237 => Some(Self::IdSpaceExhausted(String::default())),
+            238 => Some(Self::AddressMismatch(String::default(), String::default())),
+            239 => Some(Self::ArithmeticOverflow),
+            240 => Some(Self::InvalidDecimal(String::default())),
+            241 => Some(Self::RateLimited(String::default(), u64::default())),
+            242 => Some(Self::BurnWithoutAuthority(String::default())),
+            243 => Some(Self::MissingFieldValue(
+                String::default(),
+                String::default(),
+            )),
+            244 => Some(Self::InvalidFieldValue(
+                String::default(),
+                String::default(),
+            )),
+            245 => Some(Self::CloseWithoutAuthority(String::default())),
+            246 => Some(Self::IndexWriteConflict(
+                String::default(),
+                u64::default(),
+                u64::default(),
+            )),
             _ => None,
         }
     }
@@ -72,6 +319,47 @@ impl ToPrimitive for NautilusError {
             Self::AccountNotMutable(..) => Some(203),
             Self::AccountNotSigner(..) => Some(204),
             Self::AccountExists(..) => Some(205),
+            Self::ReallocTooLarge(..) => Some(206),
+            Self::OwnerMismatch(..) => Some(207),
+            Self::InlineVecFull(..) => Some(208),
+            Self::TableNotFound(..) => Some(209),
+            Self::CascadeCycle(..) => Some(210),
+            Self::DefaultKey(..) => Some(211),
+            Self::KeyNotOnCurve(..) => Some(212),
+            Self::IndexLocked(..) => Some(213),
+            Self::RebalanceNotZeroSum(..) => Some(214),
+            Self::RebalanceOutOfRange(..) => Some(215),
+            Self::RecordNotFound(..) => Some(216),
+            Self::UniqueConstraintViolation(..) => Some(217),
+            Self::DiscriminatorMismatch(..) => Some(218),
+            Self::TooManySeeds(..) => Some(219),
+            Self::LengthPrefixOverflow(..) => Some(220),
+            Self::StaleIndex(..) => Some(221),
+            Self::StringTooLong(..) => Some(222),
+            Self::InvalidString(..) => Some(223),
+            Self::VersionConflict(..) => Some(224),
+            Self::FlagNotSet(..) => Some(225),
+            Self::IdOutOfRange(..) => Some(226),
+            Self::IndexFrozen(..) => Some(227),
+            Self::NotIndexAdmin(..) => Some(228),
+            Self::InvalidVoucher(..) => Some(229),
+            Self::VoucherReplayed(..) => Some(230),
+            Self::OverflowChainMismatch(..) => Some(231),
+            Self::OverflowChainTooShort(..) => Some(232),
+            Self::StaleCommit(..) => Some(233),
+            Self::SchemaMismatch(..) => Some(234),
+            Self::MissingAuthority(..) => Some(235),
+            Self::InvalidIdStride(..) => Some(236),
+            Self::IdSpaceExhausted(..) => Some(237),
+            Self::AddressMismatch(..) => Some(238),
+            Self::ArithmeticOverflow => Some(239),
+            Self::InvalidDecimal(..) => Some(240),
+            Self::RateLimited(..) => Some(241),
+            Self::BurnWithoutAuthority(..) => Some(242),
+            Self::MissingFieldValue(..) => Some(243),
+            Self::InvalidFieldValue(..) => Some(244),
+            Self::CloseWithoutAuthority(..) => Some(245),
+            Self::IndexWriteConflict(..) => Some(246),
         }
     }
 
@@ -123,6 +411,117 @@ impl PrintProgramError for NautilusError {
                 "This account was marked with `Create<T>` but it exists already: {}",
                 pubkey
             ),
+            Self::ReallocTooLarge(pubkey, increase) => error!(
+                "Growing this account by {} bytes exceeds the per-instruction realloc limit: {}",
+                increase, pubkey
+            ),
+            Self::OwnerMismatch(pubkey, owner, deriver) => error!(
+                "This account is owned by {} but its address is derived from {}: {}",
+                owner, deriver, pubkey
+            ),
+            Self::InlineVecFull(capacity) => {
+                error!("Cannot push to an `InlineVec` with capacity {}", capacity)
+            }
+            Self::TableNotFound(table_name) => {
+                error!("Table not found in the Nautilus Index: {}", table_name)
+            }
+            Self::CascadeCycle(table_name, id) => error!(
+                "Cascade dependencies form a cycle, so record {} can't be ordered for table: {}",
+                id, table_name
+            ),
+            Self::DefaultKey(field) => error!(
+                "This field must be set but holds the default key: {}",
+                field
+            ),
+            Self::KeyNotOnCurve(field, key) => error!(
+                "This field must hold an on-curve key but holds {}: {}",
+                key, field
+            ),
+            Self::IndexLocked(pubkey, holder, expires_at_slot) => error!(
+                "The index is locked by {} until slot {}: {}",
+                holder, expires_at_slot, pubkey
+            ),
+            Self::RebalanceNotZeroSum(net) => error!(
+                "Lamport moves of a rebalance sum to {} instead of zero",
+                net
+            ),
+            Self::RebalanceOutOfRange(pubkey, balance) => error!(
+                "A rebalance would leave this account with {} lamports: {}",
+                balance, pubkey
+            ),
+            Self::RecordNotFound(table_name, key, pubkey) => error!(
+                "This account is not the {} record expected for key {}: {}",
+                table_name, key, pubkey
+            ),
+            Self::UniqueConstraintViolation(table_name, field_name) => error!(
+                "This value of unique field {} is already held by another record in table: {}",
+                field_name, table_name
+            ),
+            Self::DiscriminatorMismatch(state_type, pubkey) => {
+                error!("This account does not hold {} data: {}", state_type, pubkey)
+            }
+            Self::TooManySeeds(count) => error!(
+                "Too many seeds for a program-derived address: {} including the bump, of at most {}",
+                count, MAX_SEEDS
+            ),
+            Self::LengthPrefixOverflow(len, max) => error!(
+                "Collection length {} exceeds the maximum of {} for its length prefix",
+                len, max
+            ),
+            Self::StaleIndex(pubkey, age, max_age) => error!(
+                "This index was last mutated {} slots ago, more than the {} accepted: {}",
+                age, max_age, pubkey
+            ),
+            Self::StringTooLong(field, len, max) => error!(
+                "String field {} is {} bytes long, more than the maximum of {}",
+                field, len, max
+            ),
+            Self::InvalidString(field) => error!("String field {} holds control characters", field),
+            Self::VersionConflict(pubkey, expected, actual) => error!(
+                "Expected record version {}, but found version {}: {}",
+                expected, actual, pubkey
+            ),
+            Self::FlagNotSet(flag) => {
+                error!("This operation requires flag {:#x}, which isn't set", flag)
+            }
+            Self::IdOutOfRange(table_name, id, count) => error!(
+                "Record id {} of table {} is outside the assigned range 1..={}",
+                id, table_name, count
+            ),
+            Self::IndexFrozen(pubkey) => error!("The Nautilus Index is frozen: {}", pubkey),
+            Self::NotIndexAdmin(pubkey, signer) => error!(
+                "The signer {} is not the admin of the Nautilus Index: {}",
+                signer, pubkey
+            ),
+            Self::InvalidVoucher(reason) => {
+                error!("Invalid voucher for the Nautilus Index: {}", reason)
+            }
+            Self::VoucherReplayed(nonce, last_nonce) => error!(
+                "Voucher nonce {} was already used: the last nonce redeemed is {}",
+                nonce, last_nonce
+            ),
+            Self::OverflowChainMismatch(pubkey) => error!(
+                "The overflow accounts passed don't match the chain of this head account: {}",
+                pubkey
+            ),
+            Self::OverflowChainTooShort(needed, given) => error!(
+                "Writing this chained record takes {} overflow accounts, but only {} were passed",
+                needed, given
+            ),
+            Self::StaleCommit(prepared, current) => error!("This commit was prepared at generation {} of the Nautilus Index, which is now at generation {}", prepared, current),
+            Self::SchemaMismatch(table_name) => error!("The schema registry holds no matching schema hash for table: {}", table_name),
+            Self::MissingAuthority(authority) => error!("This authority of the record did not sign the instruction: {}", authority),
+            Self::InvalidIdStride(table_name) => error!("Cannot set this id stride for table {}: its start and stride must be non-zero, and it must not have assigned any ids", table_name),
+            Self::IdSpaceExhausted(table_name) => error!("Table {} has no ids left to assign", table_name),
+            Self::AddressMismatch(pubkey, expected) => error!("This account is not at the address derived from its seeds, {}: {}", expected, pubkey),
+            Self::ArithmeticOverflow => error!("Decimal arithmetic overflowed"),
+            Self::InvalidDecimal(string) => error!("Cannot parse a decimal from: {}", string),
+            Self::RateLimited(table_name, retry_after) => error!("This payer may not create another record in table {} until slot {}", table_name, retry_after),
+            Self::BurnWithoutAuthority(table_name) => error!("Records of table {} declare no authority, so they cannot be burned", table_name),
+            Self::MissingFieldValue(table_name, field_name) => error!("The field values for a record of table {} are missing field {}", table_name, field_name),
+            Self::InvalidFieldValue(table_name, field_name) => error!("The field values for a record of table {} hold an invalid value for field {}", table_name, field_name),
+            Self::CloseWithoutAuthority(table_name) => error!("Records of table {} declare no authority, so they cannot be closed with a refund", table_name),
+            Self::IndexWriteConflict(pubkey, synced, on_chain) => error!("This copy of the index {} has unwritten mutations since generation {}, but the account has since been written at generation {}", pubkey, synced, on_chain),
         }
     }
 }
@@ -5,12 +5,17 @@
 // ----------------------------------------------------------------
 //
 //
+extern crate alloc;
 extern crate self as nautilus;
 
+#[cfg(feature = "client")]
+pub mod client;
 pub mod cpi;
 pub mod error;
 pub mod objects;
 pub mod properties;
+pub mod seeds;
+pub mod types;
 
 pub use mpl_token_metadata;
 pub use solana_program;
@@ -34,8 +39,15 @@ pub use solana_program::{
 
 pub use objects::{
     accounts::*,
-    records::{index::*, *},
+    records::{
+        aggregate::*, cascade::*, changelog::*, delta::*, event::*, index::*, lazy::*, lock::*,
+        merkle::*, overflow::*, prepare::*, rate_limit::*, receipt::*, schema::*, voucher::*, *,
+    },
     tokens::{associated_token::*, metadata::*, mint::*, nft::*, token::*, *},
     wallets::*,
 };
 pub use properties::{create::*, data::*, mutable::*, signer::*, *};
+pub use types::{
+    decimal::*, flags::*, inline_vec::*, max_span::*, rent_contributions::*, short_vec::*,
+    sorted_map::*, view::*, *,
+};
@@ -0,0 +1,138 @@
+//! Aggregate records kept in step with the lifecycle of another table's
+//! records, such as a "stats" account of counts and sums.
+use borsh::BorshSerialize;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError};
+
+use crate::{Create, Mut, NautilusRecordData, NautilusSigner, Record};
+
+/// The data of an aggregate record, updated by the lifecycle events of the
+/// records of table `T`.
+///
+/// An aggregate can track several tables by implementing this trait once for
+/// each.
+pub trait NautilusAggregate<T: NautilusRecordData>: NautilusRecordData {
+    /// Called when a record of `T` is created.
+    fn on_create(&mut self, record: &T);
+
+    /// Called when a record of `T` is updated from `old` to `new`.
+    ///
+    /// Defaults to deleting `old` and creating `new`.
+    fn on_update(&mut self, old: &T, new: &T) {
+        self.on_delete(old);
+        self.on_create(new);
+    }
+
+    /// Called when a record of `T` is deleted.
+    fn on_delete(&mut self, record: &T);
+}
+
+/// A record whose data aggregates other records, updated through its
+/// `NautilusAggregate` hooks and written back as each event is applied.
+///
+/// The aggregate account must be passed to every instruction that creates,
+/// updates or deletes the records it aggregates, and must be writable:
+/// `AggregateRecord::new(..)` fails with `NautilusError::AccountNotMutable`
+/// otherwise, so an instruction can't skip updating it by accident.
+///
+/// Updates to the aggregate are made in the same instruction as the operation
+/// that triggers them, so they're atomic with it: if either fails, the whole
+/// transaction fails and neither is applied.
+///
+/// The aggregate's data is rewritten in place, so it should be of a fixed size
+/// (such as counts and sums) - an update that grows it fails.
+#[derive(Clone)]
+pub struct AggregateRecord<'a, A>
+where
+    A: NautilusRecordData,
+{
+    pub record: Mut<Record<'a, A>>,
+}
+
+impl<'a, A> AggregateRecord<'a, A>
+where
+    A: NautilusRecordData,
+{
+    /// Wraps a loaded record as an aggregate, failing if its account isn't
+    /// writable.
+    pub fn new(record: Record<'a, A>) -> Result<Self, ProgramError> {
+        Ok(Self {
+            record: Mut::new(record)?,
+        })
+    }
+
+    /// The aggregate's current data.
+    pub fn data(&self) -> &A {
+        &self.record.self_account.data
+    }
+
+    /// Applies the creation of `record` to the aggregate.
+    pub fn on_create<T>(&mut self, record: &T) -> ProgramResult
+    where
+        T: NautilusRecordData,
+        A: NautilusAggregate<T>,
+    {
+        self.record.self_account.data.on_create(record);
+        self.write()
+    }
+
+    /// Applies the update of a record from `old` to `new` to the aggregate.
+    pub fn on_update<T>(&mut self, old: &T, new: &T) -> ProgramResult
+    where
+        T: NautilusRecordData,
+        A: NautilusAggregate<T>,
+    {
+        self.record.self_account.data.on_update(old, new);
+        self.write()
+    }
+
+    /// Applies the deletion of `record` to the aggregate.
+    pub fn on_delete<T>(&mut self, record: &T) -> ProgramResult
+    where
+        T: NautilusRecordData,
+        A: NautilusAggregate<T>,
+    {
+        self.record.self_account.data.on_delete(record);
+        self.write()
+    }
+
+    /// Serializes the aggregate's data into its account.
+    fn write(&mut self) -> ProgramResult {
+        let record = &self.record.self_account;
+        record
+            .data
+            .serialize(&mut &mut record.account_info.data.borrow_mut()[..])?;
+        Ok(())
+    }
+}
+
+impl<'a, T> Create<'a, Record<'a, T>>
+where
+    T: NautilusRecordData,
+{
+    /// Create a new record (see `create_record(&mut self)`), then apply its
+    /// creation to `aggregate`.
+    pub fn create_record_with_aggregate<A>(
+        &mut self,
+        aggregate: &mut AggregateRecord<'a, A>,
+    ) -> ProgramResult
+    where
+        A: NautilusAggregate<T>,
+    {
+        self.create_record()?;
+        aggregate.on_create(&*self.self_account.data)
+    }
+
+    /// This function is the same as `create_record_with_aggregate(&mut self,
+    /// ..)` but allows you to specify a rent payer.
+    pub fn create_record_with_payer_and_aggregate<A>(
+        &mut self,
+        payer: impl NautilusSigner<'a>,
+        aggregate: &mut AggregateRecord<'a, A>,
+    ) -> ProgramResult
+    where
+        A: NautilusAggregate<T>,
+    {
+        self.create_record_with_payer(payer)?;
+        aggregate.on_create(&*self.self_account.data)
+    }
+}
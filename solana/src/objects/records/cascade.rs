@@ -0,0 +1,121 @@
+//! The `CascadePlan` for deleting a set of dependent records in a safe order.
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError};
+
+use crate::{error::NautilusError, NautilusIndex, NautilusSigner};
+
+/// A plan for cascade-deleting a set of records that reference one another.
+///
+/// Each record in the plan is identified by its table name and id. A
+/// dependency declares that one record (the child) references another (the
+/// parent), so the child must be deleted first - otherwise it would be left
+/// referencing an already-deleted parent partway through the cascade.
+///
+/// `order(&self)` resolves the plan into a deletion order by topologically
+/// sorting these dependencies, and `execute(..)` resolves that order before
+/// deleting anything. A plan whose dependencies form a cycle therefore fails
+/// with `NautilusError::CascadeCycle` without a single record being deleted.
+#[derive(Clone, Debug, Default)]
+pub struct CascadePlan {
+    records: Vec<(String, u32)>,
+    dependencies: Vec<(usize, usize)>,
+}
+
+impl CascadePlan {
+    /// Instantiate a new, empty `CascadePlan`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a record to the plan. Adding the same record twice has no effect.
+    pub fn add(&mut self, table_name: &str, id: u32) -> &mut Self {
+        self.position_or_insert(table_name, id);
+        self
+    }
+
+    /// Declare that the `child` record references the `parent` record, so the
+    /// child must be deleted before the parent.
+    ///
+    /// Both records are added to the plan if they aren't in it already.
+    pub fn add_dependency(&mut self, child: (&str, u32), parent: (&str, u32)) -> &mut Self {
+        let child = self.position_or_insert(child.0, child.1);
+        let parent = self.position_or_insert(parent.0, parent.1);
+        self.dependencies.push((child, parent));
+        self
+    }
+
+    /// All records in the plan, in the order they were added.
+    pub fn records(&self) -> &[(String, u32)] {
+        &self.records
+    }
+
+    /// Resolves the order the records in the plan must be deleted in, with
+    /// every child ahead of its parents.
+    ///
+    /// Records with no dependency between them keep the order they were added
+    /// in, so the resulting order is deterministic.
+    pub fn order(&self) -> Result<Vec<(&str, u32)>, ProgramError> {
+        let count = self.records.len();
+        let mut pending_children = vec![0usize; count];
+        for (_, parent) in self.dependencies.iter() {
+            pending_children[*parent] += 1;
+        }
+        let mut deleted = vec![false; count];
+        let mut order = Vec::with_capacity(count);
+        while order.len() < count {
+            match (0..count).find(|i| !deleted[*i] && pending_children[*i] == 0) {
+                Some(next) => {
+                    deleted[next] = true;
+                    order.push(next);
+                    for (child, parent) in self.dependencies.iter() {
+                        if *child == next {
+                            pending_children[*parent] -= 1;
+                        }
+                    }
+                }
+                None => {
+                    let blocked = (0..count).find(|i| !deleted[*i]).unwrap();
+                    let (table_name, id) = &self.records[blocked];
+                    return Err(NautilusError::CascadeCycle(table_name.to_string(), *id).into());
+                }
+            }
+        }
+        Ok(order
+            .into_iter()
+            .map(|i| (self.records[i].0.as_str(), self.records[i].1))
+            .collect())
+    }
+
+    /// Executes the plan.
+    ///
+    /// The deletion order is resolved first, then `delete` is called for each
+    /// record in that order, followed by closing the record in the index (see
+    /// `NautilusIndex::close_record(..)`), so a table's count only drops for
+    /// records deleted from its tail. The fee payer funds any growth of the
+    /// index.
+    pub fn execute<'a>(
+        &self,
+        index: &mut NautilusIndex<'a>,
+        fee_payer: impl NautilusSigner<'a>,
+        mut delete: impl FnMut(&str, u32) -> ProgramResult,
+    ) -> ProgramResult {
+        for (table_name, id) in self.order()? {
+            delete(table_name, id)?;
+            index.close_record(table_name, id, fee_payer.clone())?;
+        }
+        Ok(())
+    }
+
+    fn position_or_insert(&mut self, table_name: &str, id: u32) -> usize {
+        match self
+            .records
+            .iter()
+            .position(|(t, i)| t == table_name && *i == id)
+        {
+            Some(position) => position,
+            None => {
+                self.records.push((table_name.to_string(), id));
+                self.records.len() - 1
+            }
+        }
+    }
+}
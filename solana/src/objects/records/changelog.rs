@@ -0,0 +1,409 @@
+//! The `IndexChangelog` Nautilus object: an on-chain ring buffer of the count
+//! changes made to the `NautilusIndex`.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEED_LEN},
+    sysvar::Sysvar,
+};
+
+use crate::{
+    cpi, error::NautilusError, Create, NautilusAccountInfo, NautilusRecord, NautilusRecordData,
+    NautilusSigner, Signer, Wallet,
+};
+
+/// A change to a table's count in the `NautilusIndex`: the slot it was made
+/// in, the table, and the count before and after.
+///
+/// The table name is kept zero-padded to `MAX_SEED_LEN` bytes, so every entry
+/// is of the same size. Table names are used as seeds, so they fit.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChangelogEntry {
+    pub slot: u64,
+    pub table: [u8; MAX_SEED_LEN],
+    pub old: u32,
+    pub new: u32,
+}
+
+impl ChangelogEntry {
+    /// A new entry, with `table_name` truncated to `MAX_SEED_LEN` bytes.
+    pub fn new(slot: u64, table_name: &str, old: u32, new: u32) -> Self {
+        let mut table = [0; MAX_SEED_LEN];
+        let len = table_name.len().min(MAX_SEED_LEN);
+        table[..len].copy_from_slice(&table_name.as_bytes()[..len]);
+        Self {
+            slot,
+            table,
+            old,
+            new,
+        }
+    }
+
+    /// The name of the table whose count changed.
+    pub fn table_name(&self) -> String {
+        let len = self
+            .table
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |last| last + 1);
+        String::from_utf8_lossy(&self.table[..len]).into_owned()
+    }
+}
+
+/// The data of the `IndexChangelog`: a ring buffer of `ChangelogEntry`s.
+///
+/// `entries` always holds `capacity` slots, so the data is of a fixed size.
+/// `head` is the slot the next entry is written to, and `len` is how many slots
+/// hold an entry. Like the `NautilusIndexData`, it's serialized after its
+/// 8-byte discriminator, which deserializing checks - so no other account of
+/// the program passes for the changelog.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexChangelogData {
+    pub head: u32,
+    pub len: u32,
+    pub entries: Vec<ChangelogEntry>,
+}
+
+impl IndexChangelogData {
+    /// An empty changelog with room for `capacity` entries.
+    pub fn with_capacity(capacity: u32) -> Self {
+        Self {
+            head: 0,
+            len: 0,
+            entries: vec![ChangelogEntry::default(); capacity as usize],
+        }
+    }
+
+    /// The number of entries the changelog holds before it wraps.
+    pub fn capacity(&self) -> u32 {
+        self.entries.len() as u32
+    }
+
+    /// Append an entry, overwriting the oldest one once the changelog is full.
+    ///
+    /// A changelog with no capacity drops every entry.
+    pub fn append(&mut self, entry: ChangelogEntry) {
+        let capacity = self.capacity();
+        if capacity == 0 {
+            return;
+        }
+        self.entries[self.head as usize] = entry;
+        self.head = (self.head + 1) % capacity;
+        self.len = (self.len + 1).min(capacity);
+    }
+
+    /// The entries held, from oldest to newest.
+    pub fn entries(&self) -> Vec<&ChangelogEntry> {
+        let capacity = self.capacity();
+        (0..self.len)
+            .map(|i| &self.entries[((self.head + capacity - self.len + i) % capacity) as usize])
+            .collect()
+    }
+}
+
+impl BorshDeserialize for IndexChangelogData {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, borsh::maybestd::io::Error> {
+        let discriminator: [u8; 8] = BorshDeserialize::deserialize(buf)?;
+        if discriminator != Self::default().discriminator() {
+            return Err(borsh::maybestd::io::Error::new(
+                borsh::maybestd::io::ErrorKind::InvalidData,
+                "Index Changelog discriminator mismatch",
+            ));
+        }
+        Ok(Self {
+            head: BorshDeserialize::deserialize(buf)?,
+            len: BorshDeserialize::deserialize(buf)?,
+            entries: BorshDeserialize::deserialize(buf)?,
+        })
+    }
+}
+
+impl BorshSerialize for IndexChangelogData {
+    fn serialize<W: borsh::maybestd::io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), borsh::maybestd::io::Error> {
+        BorshSerialize::serialize(&self.discriminator(), writer)?;
+        BorshSerialize::serialize(&self.head, writer)?;
+        BorshSerialize::serialize(&self.len, writer)?;
+        BorshSerialize::serialize(&self.entries, writer)
+    }
+}
+
+impl NautilusRecordData for IndexChangelogData {
+    const TABLE_NAME: &'static str = "nautilus_index_changelog";
+    const AUTO_INCREMENT: bool = false;
+
+    fn primary_key(&self) -> Vec<u8> {
+        vec![0]
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+/// The optional Nautilus object that keeps an on-chain audit trail of the
+/// record counts in the `NautilusIndex`.
+///
+/// An index set up `with_changelog(..)` appends an entry to the changelog for
+/// every record added or removed, so the changelog account must be passed -
+/// and be writable - in every instruction that modifies the index. Records
+/// added through an `IndexTxn` are appended when the batch is committed.
+///
+/// Capacity and wrapping: the changelog is created with room for a fixed
+/// number of entries, and is never reallocated. Once it's full, each new entry
+/// overwrites the oldest one, so it holds the last `capacity` changes. Readers
+/// that need the full history must read it before it wraps.
+#[derive(Clone)]
+pub struct IndexChangelog<'a> {
+    pub program_id: &'a Pubkey,
+    pub account_info: Box<AccountInfo<'a>>,
+    pub data: IndexChangelogData,
+}
+
+impl<'a> IndexChangelog<'a> {
+    /// Instantiate a new `IndexChangelog` without loading the account inner
+    /// data from on-chain.
+    pub fn new(program_id: &'a Pubkey, account_info: Box<AccountInfo<'a>>) -> Self {
+        Self {
+            program_id,
+            account_info,
+            data: IndexChangelogData::default(),
+        }
+    }
+
+    /// Instantiate a new `IndexChangelog` and load the account inner data from
+    /// on-chain.
+    ///
+    /// The underlying account must be owned by `program_id`, be at the
+    /// changelog's address derived under it, and hold data beginning with the
+    /// changelog's discriminator.
+    pub fn load(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+    ) -> Result<Self, ProgramError> {
+        let data = match IndexChangelogData::try_from_slice(match &account_info.try_borrow_data() {
+            Ok(acct_data) => acct_data,
+            Err(_) => {
+                return Err(NautilusError::LoadDataFailed(
+                    IndexChangelogData::TABLE_NAME.to_string(),
+                    account_info.key.to_string(),
+                )
+                .into())
+            }
+        }) {
+            Ok(state_data) => state_data,
+            Err(_) => {
+                return Err(NautilusError::DeserializeDataFailed(
+                    IndexChangelogData::TABLE_NAME.to_string(),
+                    account_info.key.to_string(),
+                )
+                .into());
+            }
+        };
+        let changelog = Self {
+            program_id,
+            account_info,
+            data,
+        };
+        changelog.assert_owner_is_deriver()?;
+        let (address, _) = changelog.pda();
+        if address != *changelog.key() {
+            return Err(NautilusError::AddressMismatch(
+                changelog.key().to_string(),
+                address.to_string(),
+            )
+            .into());
+        }
+        Ok(changelog)
+    }
+
+    /// Append the change of `table_name`'s count from `old` to `new`, stamped
+    /// with the `Clock` sysvar's current slot, and write the changelog.
+    ///
+    /// The changelog is reloaded from its account first: every copy of the
+    /// `NautilusIndex` carries its own copy of the changelog, so appending to
+    /// one that another copy has since appended to would otherwise overwrite
+    /// that copy's entry at the same `head`.
+    pub fn append(&mut self, table_name: &str, old: u32, new: u32) -> ProgramResult {
+        self.reload()?;
+        self.data.append(ChangelogEntry::new(
+            Clock::get()?.slot,
+            table_name,
+            old,
+            new,
+        ));
+        self.write()
+    }
+
+    /// Reloads the in-memory changelog data from the account.
+    fn reload(&mut self) -> ProgramResult {
+        self.data = match IndexChangelogData::try_from_slice(&self.account_info.try_borrow_data()?)
+        {
+            Ok(state_data) => state_data,
+            Err(_) => {
+                return Err(NautilusError::DeserializeDataFailed(
+                    IndexChangelogData::TABLE_NAME.to_string(),
+                    self.account_info.key.to_string(),
+                )
+                .into());
+            }
+        };
+        Ok(())
+    }
+
+    /// Serializes the in-memory changelog data into the account. The data is
+    /// of a fixed size, so no realloc is needed.
+    fn write(&mut self) -> ProgramResult {
+        self.data
+            .serialize(&mut &mut self.account_info.data.borrow_mut()[..])?;
+        Ok(())
+    }
+}
+
+impl<'a> NautilusAccountInfo<'a> for IndexChangelog<'a> {
+    fn account_info(&self) -> Box<AccountInfo<'a>> {
+        self.account_info.clone()
+    }
+
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        &self.account_info
+    }
+
+    fn key(&self) -> &'a Pubkey {
+        self.account_info.key
+    }
+
+    fn is_signer(&self) -> bool {
+        self.account_info.is_signer
+    }
+
+    fn is_writable(&self) -> bool {
+        self.account_info.is_writable
+    }
+
+    fn lamports(&self) -> u64 {
+        self.account_info.lamports()
+    }
+
+    fn mut_lamports(&self) -> Result<std::cell::RefMut<'_, &'a mut u64>, ProgramError> {
+        self.account_info.try_borrow_mut_lamports()
+    }
+
+    fn owner(&self) -> &'a Pubkey {
+        self.account_info.owner
+    }
+
+    fn span(&self) -> Result<usize, ProgramError> {
+        self.data.span()
+    }
+}
+
+impl<'a> NautilusRecord<'a> for IndexChangelog<'a> {
+    fn discriminator(&self) -> [u8; 8] {
+        self.data.discriminator()
+    }
+
+    fn seeds(&self) -> Vec<Vec<u8>> {
+        self.data.seeds()
+    }
+
+    fn pda(&self) -> (Pubkey, u8) {
+        self.data.pda(self.program_id)
+    }
+
+    fn deriver_program(&self) -> &'a Pubkey {
+        self.program_id
+    }
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.data.primary_key()
+    }
+
+    fn check_authorities(&self, accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        self.data.check_authorities(accounts)
+    }
+
+    fn count_authorities(&self) -> u8 {
+        self.data.count_authorities()
+    }
+}
+
+impl<'a> Create<'a, IndexChangelog<'a>> {
+    /// Create a new, empty Index Changelog account with room for `capacity`
+    /// entries.
+    pub fn create(&mut self, capacity: u32) -> ProgramResult {
+        let payer = Signer::new(Wallet {
+            account_info: self.fee_payer.clone(),
+            system_program: self.system_program.clone(),
+        })?;
+        self.create_with_payer(capacity, payer)
+    }
+
+    /// This function is the same as `create(&mut self, ..)` but allows you to
+    /// specify a rent payer.
+    pub fn create_with_payer(
+        &mut self,
+        capacity: u32,
+        payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        let data_pointer = Box::new(IndexChangelogData::with_capacity(capacity));
+        let (pda, bump) = self.pda();
+        assert_eq!(
+            &pda,
+            self.key(),
+            "Derived PDA does not match data for account {:#?}",
+            self.key()
+        );
+        let mut signer_seeds_vec = self.seeds();
+        signer_seeds_vec.push(vec![bump]);
+        let signer_seeds: Vec<&[u8]> = signer_seeds_vec.iter().map(AsRef::as_ref).collect();
+        cpi::system::create_pda(
+            self.self_account.clone(),
+            self.self_account.program_id,
+            payer,
+            data_pointer.clone(),
+            signer_seeds,
+        )?;
+        self.self_account.data = *data_pointer;
+        Ok(())
+    }
+}
+
+impl<'a> NautilusRecord<'a> for Create<'a, IndexChangelog<'a>> {
+    fn discriminator(&self) -> [u8; 8] {
+        self.self_account.discriminator()
+    }
+
+    fn seeds(&self) -> Vec<Vec<u8>> {
+        self.self_account.seeds()
+    }
+
+    fn pda(&self) -> (Pubkey, u8) {
+        self.self_account.pda()
+    }
+
+    fn deriver_program(&self) -> &'a Pubkey {
+        self.self_account.deriver_program()
+    }
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.self_account.primary_key()
+    }
+
+    fn check_authorities(&self, accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        self.self_account.check_authorities(accounts)
+    }
+
+    fn count_authorities(&self) -> u8 {
+        self.self_account.count_authorities()
+    }
+}
@@ -0,0 +1,121 @@
+//! The opt-in ring of recent count changes kept inside the `NautilusIndex`, for
+//! off-chain mirrors to sync incrementally.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::MAX_SEED_LEN;
+
+/// A change to a table's count in the `NautilusIndex`: the generation the
+/// index reached with it, the table, and the table's new count.
+///
+/// A count of zero after a move (see `NautilusIndexData::move_table(..)`)
+/// means the table left the index. The table name is kept zero-padded to
+/// `MAX_SEED_LEN` bytes, so every delta is of the same size.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CountDelta {
+    pub generation: u64,
+    pub table: [u8; MAX_SEED_LEN],
+    pub count: u32,
+}
+
+impl CountDelta {
+    /// A new delta, with `table_name` truncated to `MAX_SEED_LEN` bytes.
+    pub fn new(generation: u64, table_name: &str, count: u32) -> Self {
+        let mut table = [0; MAX_SEED_LEN];
+        let len = table_name.len().min(MAX_SEED_LEN);
+        table[..len].copy_from_slice(&table_name.as_bytes()[..len]);
+        Self {
+            generation,
+            table,
+            count,
+        }
+    }
+
+    /// The name of the table whose count changed.
+    pub fn table_name(&self) -> String {
+        let len = self
+            .table
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |last| last + 1);
+        String::from_utf8_lossy(&self.table[..len]).into_owned()
+    }
+}
+
+/// A ring buffer of the most recent `CountDelta`s of the `NautilusIndex`.
+///
+/// `entries` always holds `capacity` slots, so the ring is of a fixed size and
+/// the index only grows when it's enabled. `head` is the slot the next delta is
+/// written to, and `len` is how many slots hold a delta. `since` is the
+/// generation up to which deltas may be missing: the generation the ring was
+/// enabled at, and then that of the last delta it overwrote.
+///
+/// The default ring has no capacity, and so is disabled.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CountDeltaRing {
+    pub since: u64,
+    pub head: u32,
+    pub len: u32,
+    pub entries: Vec<CountDelta>,
+}
+
+impl CountDeltaRing {
+    /// An empty ring with room for `capacity` deltas, missing every delta up
+    /// to generation `since`.
+    pub fn with_capacity(capacity: u32, since: u64) -> Self {
+        Self {
+            since,
+            head: 0,
+            len: 0,
+            entries: vec![CountDelta::default(); capacity as usize],
+        }
+    }
+
+    /// The number of deltas the ring holds before it wraps.
+    pub fn capacity(&self) -> u32 {
+        self.entries.len() as u32
+    }
+
+    /// Whether the ring records deltas at all.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity() != 0
+    }
+
+    /// Append a delta, overwriting the oldest one once the ring is full.
+    ///
+    /// A disabled ring drops every delta.
+    pub fn append(&mut self, delta: CountDelta) {
+        let capacity = self.capacity();
+        if capacity == 0 {
+            return;
+        }
+        if self.len == capacity {
+            self.since = self.entries[self.head as usize].generation;
+        }
+        self.entries[self.head as usize] = delta;
+        self.head = (self.head + 1) % capacity;
+        self.len = (self.len + 1).min(capacity);
+    }
+
+    /// The deltas held, from oldest to newest.
+    pub fn entries(&self) -> Vec<&CountDelta> {
+        let capacity = self.capacity();
+        (0..self.len)
+            .map(|i| &self.entries[((self.head + capacity - self.len + i) % capacity) as usize])
+            .collect()
+    }
+
+    /// The deltas made after `generation`, from oldest to newest, or `None` if
+    /// some of them are no longer held - because the ring is disabled, was
+    /// enabled after `generation`, or has since wrapped past it.
+    pub fn changes_since(&self, generation: u64) -> Option<Vec<CountDelta>> {
+        if !self.is_enabled() || generation < self.since {
+            return None;
+        }
+        Some(
+            self.entries()
+                .into_iter()
+                .filter(|delta| delta.generation > generation)
+                .copied()
+                .collect(),
+        )
+    }
+}
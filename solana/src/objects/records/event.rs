@@ -0,0 +1,157 @@
+//! The `RecordEvent`s logged when records are created, updated and deleted,
+//! for off-chain indexers such as Geyser plugins to follow along in real time.
+use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::NautilusRecordData;
+
+/// The magic bytes every encoded `RecordEvent` begins with.
+pub const EVENT_MAGIC: [u8; 4] = *b"NTLS";
+
+/// The version of the encoding of `RecordEvent`s this crate writes.
+pub const EVENT_VERSION: u8 = 1;
+
+/// What happened to the record a `RecordEvent` is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordEventKind {
+    Created = 0,
+    Updated = 1,
+    Deleted = 2,
+}
+
+impl RecordEventKind {
+    /// The kind encoded as `byte`, if it's a known kind.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Created),
+            1 => Some(Self::Updated),
+            2 => Some(Self::Deleted),
+            _ => None,
+        }
+    }
+}
+
+/// An event about a record, logged with `sol_log_data` for parsers that don't
+/// have the program's IDL.
+///
+/// Each event is logged as a single data field - a `Program data: ` log line
+/// holding its base64-encoded bytes - laid out as follows, with every integer
+/// little-endian:
+///
+/// | Offset      | Size | Field                                          |
+/// |-------------|------|------------------------------------------------|
+/// | 0           | 4    | `EVENT_MAGIC`, `b"NTLS"`                       |
+/// | 4           | 1    | `EVENT_VERSION`, currently 1                   |
+/// | 5           | 1    | the `RecordEventKind`: 0, 1 or 2               |
+/// | 6           | 4    | `T`, the length of the table name              |
+/// | 10          | T    | the table name, in UTF-8                       |
+/// | 10 + T      | 4    | `K`, the length of the id                      |
+/// | 14 + T      | K    | the id: the record's primary key bytes         |
+/// | 14 + T + K  | 32   | the address of the record's account            |
+/// | 46 + T + K  | rest | the payload: the record's data                 |
+///
+/// The id is a record's primary key as its seeds hold it, so an
+/// autoincrementing record's id is a 4-byte `u32`. The payload is the data as
+/// serialized into the account after the change - its discriminator included,
+/// under `DISCRIMINATOR_PREFIXED` - and is empty for a deleted record. It runs
+/// to the end of the event, so a 0-length payload is a record with no data.
+///
+/// A parser should check the magic bytes and skip events of a version it
+/// doesn't know: later versions may append fields to the header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordEvent {
+    pub kind: RecordEventKind,
+    pub table_name: String,
+    pub id: Vec<u8>,
+    pub address: Pubkey,
+    pub payload: Vec<u8>,
+}
+
+impl RecordEvent {
+    /// The event as its documented bytes (see `RecordEvent`).
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(46 + self.table_name.len() + self.id.len() + self.payload.len());
+        bytes.extend_from_slice(&EVENT_MAGIC);
+        bytes.push(EVENT_VERSION);
+        bytes.push(self.kind as u8);
+        bytes.extend_from_slice(&(self.table_name.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(self.table_name.as_bytes());
+        bytes.extend_from_slice(&(self.id.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.id);
+        bytes.extend_from_slice(self.address.as_ref());
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+
+    /// Parses an event from its bytes, or `None` if they aren't an event of
+    /// version `EVENT_VERSION`.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let rest = bytes.strip_prefix(&EVENT_MAGIC[..])?;
+        let (version, rest) = rest.split_first()?;
+        if *version != EVENT_VERSION {
+            return None;
+        }
+        let (kind, rest) = rest.split_first()?;
+        let kind = RecordEventKind::from_byte(*kind)?;
+        let (table_name, rest) = split_prefixed(rest)?;
+        let table_name = String::from_utf8(table_name.to_vec()).ok()?;
+        let (id, rest) = split_prefixed(rest)?;
+        if rest.len() < 32 {
+            return None;
+        }
+        let (address, payload) = rest.split_at(32);
+        Some(Self {
+            kind,
+            table_name,
+            id: id.to_vec(),
+            address: Pubkey::try_from(address).ok()?,
+            payload: payload.to_vec(),
+        })
+    }
+}
+
+/// Splits a `u32` length-prefixed field off the front of `bytes`.
+fn split_prefixed(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    if bytes.len() < 4 {
+        return None;
+    }
+    let (len, rest) = bytes.split_at(4);
+    let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    match rest.len() >= len {
+        true => Some(rest.split_at(len)),
+        false => None,
+    }
+}
+
+/// Logs a `RecordEvent` of `kind` about the record at `address` holding
+/// `data`, as `Record` does when it creates, updates or deletes one.
+///
+/// Only logs with the `events` feature, which a program opts into by enabling
+/// it on its `nautilus` dependency, and is compiled out otherwise. Each event
+/// costs the compute of serializing the record's data again and of a
+/// `sol_log_data` syscall, and its bytes count toward the transaction's log
+/// limit - past which the runtime truncates the logs, dropping later events -
+/// so a program writing large records may prefer to log its own, smaller
+/// events instead.
+#[allow(unused_variables)]
+pub fn emit_record_event<T: NautilusRecordData>(
+    kind: RecordEventKind,
+    address: &Pubkey,
+    data: &T,
+) -> ProgramResult {
+    #[cfg(feature = "events")]
+    {
+        let event = RecordEvent {
+            kind,
+            table_name: data.variant_table_name(),
+            id: data.primary_key(),
+            address: *address,
+            payload: match kind {
+                RecordEventKind::Deleted => vec![],
+                _ => borsh::BorshSerialize::try_to_vec(data)?,
+            },
+        };
+        solana_program::log::sol_log_data(&[&event.encode()]);
+    }
+    Ok(())
+}
@@ -1,79 +1,872 @@
 //! The special `NautilusIndex` Nautilus object and all associated trait
 //! implementations.
+use std::borrow::Cow;
+
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
-    pubkey::Pubkey,
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEED_LEN},
+    sysvar::Sysvar,
 };
 
 use crate::{
-    cpi, error::NautilusError, Create, Mut, NautilusAccountInfo, NautilusMut, NautilusRecord,
-    NautilusRecordData, NautilusSigner, NautilusTransferLamports, Signer, Wallet,
+    assert_seed_count, cpi, emit_record_event,
+    error::NautilusError,
+    minimum_balance,
+    seeds::{normalize_table_name, record_seeds},
+    sorted_map_serialize, validate_string, verify_voucher_signature, CountDelta, CountDeltaRing,
+    Create, IndexChangelog, IndexLock, IndexVoucher, LamportReceipt, Mut, NautilusAccountInfo,
+    NautilusMut, NautilusRecord, NautilusRecordData, NautilusSigner, NautilusTransferLamports,
+    RateLimits, Record, RecordEventKind, Signer, Wallet, DISCRIMINATOR_PREFIXED,
 };
 
+/// A custom calculation of the rent required for an account with the given
+/// span (data length).
+pub type RentCalculator = fn(usize) -> Result<u64, ProgramError>;
+
+/// A table's entry in the `NautilusIndex`: its current record count, plus an
+/// optional blob of metadata (such as a schema version or creation slot) for
+/// tooling to attach to the table, and the sequence its ids are assigned from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableEntry {
+    pub count: u32,
+    pub metadata: Vec<u8>,
+    pub id_stride: IdStride,
+}
+
+/// The sequence a table's autoincrement ids are assigned from: `start`,
+/// `start + stride`, `start + 2 * stride`, and so on.
+///
+/// The default of `(1, 1)` assigns ids `1, 2, 3, ...`, so each id is the
+/// table's count once it's assigned. A table's count is always the number of
+/// ids it has assigned, whatever its stride.
+///
+/// Sharding: a table split across `N` shards - each a program with a Nautilus
+/// Index of its own - can give shard `S` (for `S` from 1 to `N`) the stride
+/// `(S, N)`, before any records are added to it (see
+/// `NautilusIndex::set_id_stride(..)`). Shard `S` then assigns the ids
+/// congruent to `S` modulo `N`, so no two shards ever assign the same id,
+/// without coordinating. Since ids are `u32`s, a stride divides the id space
+/// between shards: each shard runs out of ids after about `u32::MAX / N`
+/// records, and adding any more fails with `NautilusError::IdSpaceExhausted`.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdStride {
+    pub start: u32,
+    pub stride: u32,
+}
+
+impl Default for IdStride {
+    fn default() -> Self {
+        Self {
+            start: 1,
+            stride: 1,
+        }
+    }
+}
+
+impl IdStride {
+    /// The id of the `n`th record assigned from this sequence, counting from
+    /// one, or `None` if `n` is zero or the id would overflow a `u32`.
+    pub fn id(&self, n: u32) -> Option<u32> {
+        let offset = n.checked_sub(1)?.checked_mul(self.stride)?;
+        self.start.checked_add(offset)
+    }
+
+    /// The position of `id` in this sequence, counting from one, or `None` if
+    /// `id` isn't one of its ids.
+    pub fn position(&self, id: u32) -> Option<u32> {
+        let offset = id.checked_sub(self.start)?;
+        match self.stride != 0 && offset % self.stride == 0 {
+            true => Some(offset / self.stride + 1),
+            false => None,
+        }
+    }
+}
+
 /// The account inner data for the `NautilusIndex`.
 ///
-/// This `index` is simply a Hash Map that stores the current record count for
-/// each table, where the `String` key is the table name and the `u32` value is
-/// the current count.
+/// This `index` is simply a Hash Map that stores an entry for each table, where
+/// the `String` key is the table name and the `TableEntry` value holds the
+/// table's current count and metadata.
 ///
 /// This data is kept in one single account and used as a reference to enable
 /// autoincrementing of records.
+///
+/// The `unique_values` map is a secondary index over the fields of a table
+/// that must be unique (see `NautilusRecordData::unique_fields()`), mapping
+/// each claimed value of a `(table_name, field_name)` to the primary key of the
+/// record holding it.
+///
+/// The `generation` is bumped on every mutation of the index, so clients can
+/// compare generations to tell whether they're reading stale state, or whether
+/// the index was reset by a rollback or recovery.
+///
+/// The `last_mutation_slot` is the slot the index was last written in by the
+/// `NautilusIndex`, which stamps it on every mutation (see
+/// `NautilusIndex::load_fresh(..)`). It's zero for an index not yet written
+/// since the slot was tracked.
+///
+/// The `deltas` ring optionally holds the most recent count changes, each with
+/// the generation it brought the index to, so off-chain mirrors can sync
+/// incrementally (see `changes_since(..)`). It's disabled unless enabled with
+/// `enable_deltas(..)`.
+///
+/// The `admin` may set the `frozen` flag, an emergency circuit breaker that
+/// makes the `NautilusIndex` refuse every mutation until it's cleared (see
+/// `NautilusIndex::freeze(..)`). An `admin` of `Pubkey::default()` means the
+/// index has no admin, and can't be frozen.
+///
+/// The `voucher_nonce` is the nonce of the last `IndexVoucher` redeemed (see
+/// `NautilusIndex::add_record_with_voucher(..)`), which every new voucher must
+/// exceed.
+///
+/// Each table's entry holds the `IdStride` its ids are assigned from, so
+/// sharded tables can assign disjoint ids (see `set_id_stride(..)`).
+///
+/// The `rate_limits` optionally throttle how often each payer can create
+/// records in a table (see `set_rate_limit(..)`). They're disabled unless
+/// enabled with `enable_rate_limits(..)`.
+///
+/// On-chain, the index is laid out as the discriminator followed by a map of
+/// each table's count - the same layout as before tables had metadata - and
+/// then any trailing fields: the map of each table's metadata, the generation,
+/// the unique values, the last mutation slot, the ring of deltas, the admin,
+/// the frozen flag, the voucher nonce, the map of each table's id stride
+/// (holding only those other than the default), then the rate limits.
+/// Trailing fields are only written up to the last one holding a non-default
+/// value, and are read as their defaults when absent, so accounts written
+/// before a trailing field existed still deserialize, and are only rewritten
+/// in the new layout once it's needed.
+///
+/// With `case_insensitive_table_names` set, table names are normalized (see
+/// `nautilus::seeds::normalize_table_name(..)`) before they're looked up in or
+/// inserted into the index, so `"Users"` and `"users"` are the same table.
+/// This setting isn't stored on-chain: it's configured by the program (see
+/// `NautilusIndex::with_case_insensitive_table_names(..)`), and defaults to
+/// case-sensitive.
+///
+/// With `unprefixed_discriminator` set, the index is written without its
+/// discriminator, and read as beginning with its map of counts - the layout of
+/// a program that declares `DISCRIMINATOR_PREFIXED` false. Otherwise,
+/// deserializing fails unless the data begins with the index's discriminator.
+/// This isn't stored on-chain either: it's set by the loader (see
+/// `NautilusIndex::load_with_discriminator_prefix(..)`).
 #[derive(Clone, Default)]
 pub struct NautilusIndexData {
-    pub index: std::collections::HashMap<String, u32>,
+    pub index: std::collections::HashMap<String, TableEntry>,
+    pub generation: u64,
+    pub unique_values: std::collections::HashMap<UniqueField, UniqueValues>,
+    pub last_mutation_slot: u64,
+    pub deltas: CountDeltaRing,
+    pub admin: Pubkey,
+    pub frozen: bool,
+    pub voucher_nonce: u64,
+    pub rate_limits: RateLimits,
+    pub case_insensitive_table_names: bool,
+    pub unprefixed_discriminator: bool,
 }
 
+/// A unique field of a table, as its `(table_name, field_name)`.
+pub type UniqueField = (String, String);
+
+/// The claimed values of a unique field, each mapped to the primary key of the
+/// record holding it.
+pub type UniqueValues = std::collections::HashMap<Vec<u8>, Vec<u8>>;
+
+/// A claimed value of a unique field and the primary key holding it, as laid
+/// out on-chain.
+pub(crate) type UniqueValueEntry = (Vec<u8>, Vec<u8>);
+
+/// The position of the generation among the index's trailing fields.
+const GENERATION_FIELD: usize = 1;
+
+/// The position of the last mutation slot among the index's trailing fields.
+const SLOT_FIELD: usize = 3;
+
 impl NautilusIndexData {
+    /// The number of mutations made to the index since it was created (or
+    /// since it last had no generation, for accounts written before the
+    /// generation existed).
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The slot the index was last mutated in, or zero if it hasn't been
+    /// written since the slot was tracked.
+    pub fn last_mutation_slot(&self) -> u64 {
+        self.last_mutation_slot
+    }
+
+    /// Enables the ring of recent count changes with room for `capacity` of
+    /// them, replacing any ring the index already had.
+    ///
+    /// The ring holds the last `capacity` changes, so a mirror that falls
+    /// further behind than that has to resync in full. Since it's of a fixed
+    /// size, the index only grows when the ring is enabled - by 44 bytes per
+    /// change it can hold - and not as changes are recorded.
+    pub fn enable_deltas(&mut self, capacity: u32) {
+        self.bump_generation();
+        self.deltas = CountDeltaRing::with_capacity(capacity, self.generation);
+    }
+
+    /// Enables rate limits with room to track `capacity` payers at once,
+    /// keeping the most recent creates already tracked (see `RateLimits`). A
+    /// capacity of zero disables them.
+    ///
+    /// Since the payers are tracked in slots of a fixed size, the index only
+    /// grows when rate limits are enabled - by 72 bytes per payer it can track
+    /// - and not as payers create records.
+    pub fn enable_rate_limits(&mut self, capacity: u32) {
+        self.rate_limits.set_capacity(capacity);
+        self.bump_generation();
+    }
+
+    /// Sets the minimum number of slots each payer must wait between creates
+    /// in a table - 1 for one create per slot - or lifts the table's limit
+    /// with an interval of zero.
+    ///
+    /// Limits are only enforced while rate limits are enabled (see
+    /// `enable_rate_limits(..)`).
+    pub fn set_rate_limit(&mut self, table_name: &str, interval: u64) {
+        let table_name = self.table_name(table_name).into_owned();
+        self.rate_limits.set_interval(&table_name, interval);
+        self.bump_generation();
+    }
+
+    /// The slot from which `payer` may next create a record in a table, if
+    /// its rate limit doesn't allow one at `slot`.
+    pub fn rate_limit_retry_after(
+        &self,
+        table_name: &str,
+        payer: &Pubkey,
+        slot: u64,
+    ) -> Option<u64> {
+        match self.rate_limits.is_enabled() {
+            true => self
+                .rate_limits
+                .retry_after(&self.table_name(table_name), payer, slot),
+            false => None,
+        }
+    }
+
+    /// Tracks a create by `payer` in a rate-limited table at `slot` (see
+    /// `RateLimits::record(..)`).
+    pub fn record_create(&mut self, table_name: &str, payer: &Pubkey, slot: u64) {
+        let table_name = self.table_name(table_name).into_owned();
+        self.rate_limits.record(&table_name, payer, slot);
+    }
+
+    /// The count changes made after `generation`, from oldest to newest.
+    ///
+    /// A mirror reads the whole index once, notes its `generation()`, and from
+    /// then on applies the changes since the last generation it applied, which
+    /// is that of the last change returned. Full-resync fallback: this returns
+    /// `None` if some of those changes are no longer held - because the ring is
+    /// disabled, was enabled after `generation`, or has wrapped past it - in
+    /// which case the mirror must read the whole index again.
+    pub fn changes_since(&self, generation: u64) -> Option<Vec<CountDelta>> {
+        self.deltas.changes_since(generation)
+    }
+
+    /// Whether the index has been frozen by its admin.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// The name a table is kept under in the index: normalized in
+    /// case-insensitive mode, and as is otherwise.
+    pub fn table_name<'n>(&self, table_name: &'n str) -> Cow<'n, str> {
+        match self.case_insensitive_table_names {
+            true => Cow::Owned(normalize_table_name(table_name)),
+            false => Cow::Borrowed(table_name),
+        }
+    }
+
     /// Get the current record count for a table.
     pub fn get_count(&self, table_name: &str) -> Option<u32> {
-        self.index.get(&(table_name.to_string())).copied()
+        self.index
+            .get(self.table_name(table_name).as_ref())
+            .map(|entry| entry.count)
     }
 
-    /// Get the next record count for a table.
+    /// Get the id the next record added to a table will be assigned, following
+    /// its id stride - with the default stride, its count plus one.
+    ///
+    /// Returns zero, which is never an id, once the table's id space is
+    /// exhausted: when its next id would overflow a `u32`.
     pub fn get_next_count(&self, table_name: &str) -> u32 {
-        match self.index.get(&(table_name.to_string())) {
-            Some(count) => count + 1,
-            None => 1,
+        let (count, id_stride) = match self.index.get(self.table_name(table_name).as_ref()) {
+            Some(entry) => (entry.count, entry.id_stride),
+            None => (0, self.get_id_stride(table_name)),
+        };
+        count
+            .checked_add(1)
+            .and_then(|n| id_stride.id(n))
+            .unwrap_or_default()
+    }
+
+    /// The id of a table's most recently assigned record, if it has assigned
+    /// any.
+    pub fn get_last_id(&self, table_name: &str) -> Option<u32> {
+        let entry = self.index.get(self.table_name(table_name).as_ref())?;
+        entry.id_stride.id(entry.count)
+    }
+
+    /// The sequence a table's ids are assigned from: the default `(1, 1)`
+    /// unless it's been set with `set_id_stride(..)`.
+    pub fn get_id_stride(&self, table_name: &str) -> IdStride {
+        self.index
+            .get(self.table_name(table_name).as_ref())
+            .map(|entry| entry.id_stride)
+            .unwrap_or_default()
+    }
+
+    /// Sets the sequence a table's ids are assigned from (see `IdStride`),
+    /// returning whether it was set.
+    ///
+    /// If the table is not yet in the index, it is added with a count of zero.
+    /// The stride is left as it is - returning `false` - if its start or stride
+    /// is zero, or if the table has already assigned ids, since those could
+    /// then collide with the ids assigned after it.
+    pub fn set_id_stride(&mut self, table_name: &str, id_stride: IdStride) -> bool {
+        if id_stride.start == 0 || id_stride.stride == 0 {
+            return false;
+        }
+        let table_name = self.table_name(table_name).into_owned();
+        let entry = self.index.entry(table_name).or_default();
+        if entry.count > 0 {
+            return false;
         }
+        entry.id_stride = id_stride;
+        self.bump_generation();
+        true
     }
 
-    /// Add a new record to the index.
-    pub fn add_record(&mut self, table_name: &str) -> u32 {
-        match self.index.get_mut(&(table_name.to_string())) {
-            Some(count) => {
-                *count += 1;
-                *count
+    /// Get the metadata attached to a table.
+    pub fn get_metadata(&self, table_name: &str) -> Option<&[u8]> {
+        self.index
+            .get(self.table_name(table_name).as_ref())
+            .map(|entry| entry.metadata.as_slice())
+    }
+
+    /// Attach metadata to a table, replacing any it already had.
+    ///
+    /// If the table is not yet in the index, it is added with a count of zero.
+    pub fn set_metadata(&mut self, table_name: &str, metadata: Vec<u8>) {
+        let table_name = self.table_name(table_name).into_owned();
+        self.index.entry(table_name).or_default().metadata = metadata;
+        self.bump_generation();
+    }
+
+    /// The number of bytes the serialized index would grow by if a record were
+    /// added to this table.
+    ///
+    /// This is zero for a table already in the index, since its count is
+    /// updated in place - unless this is the index's first generation to be
+    /// written (see `generation_growth()`).
+    pub fn add_record_growth(&self, table_name: &str) -> usize {
+        let table_name = self.table_name(table_name);
+        let table_growth = match self.index.contains_key(table_name.as_ref()) {
+            true => 0,
+            false => Self::entry_span(&table_name),
+        };
+        table_growth + self.generation_growth()
+    }
+
+    /// The number of bytes the serialized index would grow by when its
+    /// generation is bumped.
+    ///
+    /// This is zero once the generation is non-zero, since it's updated in
+    /// place. Until then, it isn't written at all, along with any default
+    /// trailing fields ahead of it.
+    pub fn generation_growth(&self) -> usize {
+        self.trailing_growth(self.generation, 0, GENERATION_FIELD)
+    }
+
+    /// The number of bytes the serialized index would grow by, beyond its
+    /// `generation_growth()`, when it's stamped with its last mutation slot -
+    /// which the `NautilusIndex` does along with bumping the generation.
+    ///
+    /// This is zero once the last mutation slot is non-zero.
+    pub fn slot_growth(&self) -> usize {
+        self.trailing_growth(self.last_mutation_slot, GENERATION_FIELD + 1, SLOT_FIELD)
+    }
+
+    /// The number of bytes the trailing fields from `first` up to `last` would
+    /// add to the serialized index when the field at `last`, currently holding
+    /// `value`, becomes non-zero.
+    fn trailing_growth(&self, value: u64, first: usize, last: usize) -> usize {
+        if value != 0 {
+            return 0;
+        }
+        let trailing_spans = self.trailing_field_spans();
+        let written = written_trailing_spans(&trailing_spans);
+        trailing_spans
+            .iter()
+            .take(last + 1)
+            .skip(written.max(first))
+            .map(|(span, _)| span)
+            .sum()
+    }
+
+    /// The number of bytes a table's entry takes up in the serialized map of
+    /// counts: its name's length prefix, its name, and its count.
+    ///
+    /// Adding or removing a table changes the index's span by exactly this
+    /// much (beyond any trailing fields it also changes), which is what lets
+    /// `span()` be summed entry by entry rather than measured by serializing
+    /// the whole index.
+    pub fn entry_span(table_name: &str) -> usize {
+        4 + table_name.len() + 4
+    }
+
+    /// The serialized size of each trailing field, in order, with whether it
+    /// holds a non-default value - mirroring `trailing_fields()`, without
+    /// serializing anything.
+    ///
+    /// A trailing field's default value is the only one that serializes to
+    /// all-zero bytes, so it's the non-default ones that get written.
+    fn trailing_field_spans(&self) -> Vec<(usize, bool)> {
+        let (metadata_len, metadata_span) = self
+            .index
+            .iter()
+            .filter(|(_, entry)| !entry.metadata.is_empty())
+            .fold((0, 4), |(len, span), (table_name, entry)| {
+                (
+                    len + 1,
+                    span + 4 + table_name.len() + 4 + entry.metadata.len(),
+                )
+            });
+        let (unique_len, unique_span) = self
+            .unique_values
+            .iter()
+            .filter(|(_, values)| !values.is_empty())
+            .fold((0, 4), |(len, span), ((table_name, field_name), values)| {
+                let values_span: usize = values
+                    .iter()
+                    .map(|(value, primary_key)| 4 + value.len() + 4 + primary_key.len())
+                    .sum();
+                (
+                    len + 1,
+                    span + 4 + table_name.len() + 4 + field_name.len() + 4 + values_span,
+                )
+            });
+        let (id_strides_len, id_strides_span) = self
+            .index
+            .iter()
+            .filter(|(_, entry)| entry.id_stride != IdStride::default())
+            .fold((0, 4), |(len, span), (table_name, _)| {
+                (len + 1, span + 4 + table_name.len() + 8)
+            });
+        let rate_limits_span = 4
+            + self
+                .rate_limits
+                .intervals
+                .iter()
+                .map(|(table_name, _)| 4 + table_name.len() + 8)
+                .sum::<usize>()
+            + 4
+            + self.rate_limits.entries.len() * 72;
+        vec![
+            (metadata_span, metadata_len > 0),
+            (8, self.generation != 0),
+            (unique_span, unique_len > 0),
+            (8, self.last_mutation_slot != 0),
+            (
+                8 + 4 + 4 + 4 + self.deltas.entries.len() * 44,
+                self.deltas != CountDeltaRing::default(),
+            ),
+            (32, self.admin != Pubkey::default()),
+            (1, self.frozen),
+            (8, self.voucher_nonce != 0),
+            (id_strides_span, id_strides_len > 0),
+            (rate_limits_span, self.rate_limits != RateLimits::default()),
+        ]
+    }
+
+    /// Add each of `tables` to the index with a count of zero, returning how
+    /// many were added.
+    ///
+    /// Tables already in the index are left as they are.
+    pub fn init_tables(&mut self, tables: &[&str]) -> usize {
+        let mut added = 0;
+        for table_name in tables {
+            let table_name = self.table_name(table_name).into_owned();
+            if let std::collections::hash_map::Entry::Vacant(entry) = self.index.entry(table_name) {
+                entry.insert(TableEntry::default());
+                added += 1;
             }
-            None => {
-                self.index.insert(table_name.to_string(), 1);
-                1
+        }
+        if added > 0 {
+            self.bump_generation();
+        }
+        added
+    }
+
+    /// Add a new record to the index, returning the id it's assigned (see
+    /// `get_next_count(..)`).
+    ///
+    /// Returns zero, leaving the index unchanged, if the table's id space is
+    /// exhausted.
+    pub fn add_record(&mut self, table_name: &str) -> u32 {
+        let id = self.get_next_count(table_name);
+        if id == 0 {
+            return 0;
+        }
+        let table_name = self.table_name(table_name).into_owned();
+        let entry = self.index.entry(table_name.clone()).or_default();
+        entry.count += 1;
+        let count = entry.count;
+        self.bump_generation();
+        self.record_delta(&table_name, count);
+        id
+    }
+
+    /// Remove a record from the index, returning the table's new count.
+    ///
+    /// Returns `None` if the table is not in the index. A table's count never
+    /// drops below zero.
+    pub fn remove_record(&mut self, table_name: &str) -> Option<u32> {
+        let table_name = self.table_name(table_name).into_owned();
+        let count = self.index.get_mut(&table_name).map(|entry| {
+            entry.count = entry.count.saturating_sub(1);
+            entry.count
+        })?;
+        self.bump_generation();
+        self.record_delta(&table_name, count);
+        Some(count)
+    }
+
+    /// Whether `id` is the table's most recently assigned id - with the
+    /// default id stride, its count - so that its record can be removed without
+    /// leaving a gap.
+    pub fn is_tail_record(&self, table_name: &str, id: u32) -> bool {
+        id != 0 && self.get_last_id(table_name) == Some(id)
+    }
+
+    /// Remove the record with the given id from the index, returning the
+    /// table's new count.
+    ///
+    /// The count is only decremented if `id` is the tail record (see
+    /// `is_tail_record(..)`), so the id gets reused by the next record added.
+    /// Closing any other record leaves the count as it is: decrementing it
+    /// would hand the tail record's id out again, colliding with the live
+    /// record. The closed id is instead left as a gap, never reassigned -
+    /// track it with soft deletes or a free list if it should be reused.
+    ///
+    /// Returns `None` if the table is not in the index.
+    pub fn close_record(&mut self, table_name: &str, id: u32) -> Option<u32> {
+        match self.is_tail_record(table_name, id) {
+            true => self.remove_record(table_name),
+            false => self.get_count(table_name),
+        }
+    }
+
+    /// Move a table out of this index and into `dest`, returning the table's
+    /// count in `dest`.
+    ///
+    /// If `dest` already has the table, the counts are summed, and the table
+    /// keeps `dest`'s metadata unless it has none, and `dest`'s id stride.
+    /// Otherwise, the table keeps its own id stride. The claimed values of the
+    /// table's unique fields move along with it.
+    ///
+    /// Returns `None`, changing neither index, if the table is not in this
+    /// index.
+    pub fn move_table(&mut self, table_name: &str, dest: &mut NautilusIndexData) -> Option<u32> {
+        let entry = self.index.remove(self.table_name(table_name).as_ref())?;
+        let dest_entry = dest
+            .index
+            .entry(dest.table_name(table_name).into_owned())
+            .or_insert_with(|| TableEntry {
+                id_stride: entry.id_stride,
+                ..TableEntry::default()
+            });
+        dest_entry.count += entry.count;
+        if dest_entry.metadata.is_empty() {
+            dest_entry.metadata = entry.metadata;
+        }
+        let count = dest_entry.count;
+        let source_table = self.table_name(table_name).into_owned();
+        let dest_table = dest.table_name(table_name).into_owned();
+        let fields: Vec<UniqueField> = self
+            .unique_values
+            .keys()
+            .filter(|(table, _)| *table == source_table)
+            .cloned()
+            .collect();
+        for field in fields {
+            if let Some(values) = self.unique_values.remove(&field) {
+                dest.unique_values
+                    .entry((dest_table.clone(), field.1))
+                    .or_default()
+                    .extend(values);
             }
         }
+        self.bump_generation();
+        self.record_delta(&source_table, 0);
+        dest.bump_generation();
+        dest.record_delta(&dest_table, count);
+        Some(count)
+    }
+
+    pub(crate) fn bump_generation(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// A page of up to `limit` tables and their counts, starting `offset`
+    /// tables in, along with whether any tables come after the page.
+    ///
+    /// Tables are listed sorted by name, whatever order the index's map holds
+    /// them in, so the same offset always lands on the same table. Paging is
+    /// only stable while the set of tables doesn't change, though: a table
+    /// added or moved out between two pages shifts the tables after it, so
+    /// tooling should start over if the number of tables changes while it
+    /// pages through them.
+    pub fn list_tables_page(&self, offset: usize, limit: usize) -> (Vec<(String, u32)>, bool) {
+        let entries = self.sorted_entries();
+        let page = entries
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(table_name, entry)| (table_name.to_string(), entry.count))
+            .collect();
+        let has_more = entries.len() > offset.saturating_add(limit);
+        (page, has_more)
+    }
+
+    /// The tables whose count satisfies `predicate`, and their counts, sorted
+    /// by table name - such as `|count| count > 10_000` for candidates to
+    /// shard, or `|count| count == 0` for candidates to prune.
+    ///
+    /// A read-only helper for tooling and admin instructions. Each count is
+    /// tested in place, before anything is sorted or copied, so only the
+    /// matching tables are: a selective predicate over a large index allocates
+    /// little more than its result.
+    pub fn tables_where(&self, predicate: impl Fn(u32) -> bool) -> Vec<(String, u32)> {
+        let mut tables: Vec<(&String, u32)> = self
+            .index
+            .iter()
+            .filter(|(_, entry)| predicate(entry.count))
+            .map(|(table_name, entry)| (table_name, entry.count))
+            .collect();
+        tables.sort_unstable_by_key(|(table_name, _)| *table_name);
+        tables
+            .into_iter()
+            .map(|(table_name, count)| (table_name.to_string(), count))
+            .collect()
+    }
+
+    /// Records a table's new count in the ring of deltas, at the current
+    /// generation.
+    fn record_delta(&mut self, table_name: &str, count: u32) {
+        self.deltas
+            .append(CountDelta::new(self.generation, table_name, count));
+    }
+
+    /// Each table's entry, sorted by table name.
+    fn sorted_entries(&self) -> Vec<(&String, &TableEntry)> {
+        let mut entries: Vec<(&String, &TableEntry)> = self.index.iter().collect();
+        entries.sort_by_key(|(table_name, _)| *table_name);
+        entries
+    }
+
+    /// The trailing fields of the serialized index, in order.
+    fn trailing_fields(&self) -> Result<Vec<Vec<u8>>, borsh::maybestd::io::Error> {
+        let mut metadata = vec![];
+        sorted_map_serialize(
+            self.index
+                .iter()
+                .filter(|(_, entry)| !entry.metadata.is_empty())
+                .map(|(table_name, entry)| (table_name, &entry.metadata)),
+            &mut metadata,
+        )?;
+        let mut unique_values = vec![];
+        sorted_map_serialize(
+            self.unique_values
+                .iter()
+                .filter(|(_, values)| !values.is_empty())
+                .map(|(field, values)| {
+                    let mut values: Vec<_> = values.iter().collect();
+                    values.sort();
+                    (field, values)
+                }),
+            &mut unique_values,
+        )?;
+        let mut id_strides = vec![];
+        sorted_map_serialize(
+            self.index
+                .iter()
+                .filter(|(_, entry)| entry.id_stride != IdStride::default())
+                .map(|(table_name, entry)| (table_name, entry.id_stride)),
+            &mut id_strides,
+        )?;
+        Ok(vec![
+            metadata,
+            self.generation.try_to_vec()?,
+            unique_values,
+            self.last_mutation_slot.try_to_vec()?,
+            self.deltas.try_to_vec()?,
+            self.admin.try_to_vec()?,
+            self.frozen.try_to_vec()?,
+            self.voucher_nonce.try_to_vec()?,
+            id_strides,
+            self.rate_limits.try_to_vec()?,
+        ])
     }
 }
 
-impl borsh::de::BorshDeserialize for NautilusIndexData
-where
-    std::collections::HashMap<String, u32>: borsh::BorshDeserialize,
-{
-    fn deserialize(buf: &mut &[u8]) -> ::core::result::Result<Self, borsh::maybestd::io::Error> {
-        let _discrim: [u8; 8] = borsh::BorshDeserialize::deserialize(buf)?; // Skip the first 8 bytes for discriminator
+/// The number of trailing fields that get written, given the spans of
+/// `NautilusIndexData::trailing_field_spans()`: all of them up to the last one
+/// holding a non-default value.
+fn written_trailing_spans(trailing_spans: &[(usize, bool)]) -> usize {
+    trailing_spans
+        .iter()
+        .rposition(|(_, written)| *written)
+        .map_or(0, |last| last + 1)
+}
+
+/// The number of trailing fields that get written: all of them up to the last
+/// one holding a non-default value.
+fn written_trailing_fields(trailing_fields: &[Vec<u8>]) -> usize {
+    trailing_fields
+        .iter()
+        .rposition(|field| field.iter().any(|byte| *byte != 0))
+        .map_or(0, |last| last + 1)
+}
+
+/// Reads a trailing field of the serialized index, falling back to its default
+/// if it's absent.
+///
+/// Default values of trailing fields serialize to all-zero bytes, so a
+/// remainder of only zero bytes (including none at all) is read as defaults.
+fn deserialize_trailing<T: BorshDeserialize + Default>(
+    buf: &mut &[u8],
+) -> Result<T, borsh::maybestd::io::Error> {
+    match buf.iter().all(|byte| *byte == 0) {
+        true => {
+            *buf = &buf[buf.len()..];
+            Ok(T::default())
+        }
+        false => T::deserialize(buf),
+    }
+}
+
+impl NautilusIndexData {
+    /// Deserializes the index from `buf`, which begins with the index's
+    /// discriminator if `discriminator_prefixed` (see `DISCRIMINATOR_PREFIXED`).
+    pub fn deserialize_with_discriminator_prefix(
+        buf: &mut &[u8],
+        discriminator_prefixed: bool,
+    ) -> Result<Self, borsh::maybestd::io::Error> {
+        if discriminator_prefixed {
+            let discrim: [u8; 8] = borsh::BorshDeserialize::deserialize(buf)?; // Check the first 8 bytes for discriminator
+            if discrim != crate::discriminator(Self::TABLE_NAME) {
+                return Err(borsh::maybestd::io::Error::new(
+                    borsh::maybestd::io::ErrorKind::InvalidData,
+                    "Nautilus Index discriminator mismatch",
+                ));
+            }
+        }
+        let counts: std::collections::HashMap<String, u32> =
+            borsh::BorshDeserialize::deserialize(buf)?;
+        let mut index: std::collections::HashMap<String, TableEntry> = counts
+            .into_iter()
+            .map(|(table_name, count)| {
+                (
+                    table_name,
+                    TableEntry {
+                        count,
+                        ..TableEntry::default()
+                    },
+                )
+            })
+            .collect();
+        let metadata: Vec<(String, Vec<u8>)> = deserialize_trailing(buf)?;
+        for (table_name, metadata) in metadata {
+            index.entry(table_name).or_default().metadata = metadata;
+        }
+        let generation: u64 = deserialize_trailing(buf)?;
+        let unique_values: Vec<(UniqueField, Vec<UniqueValueEntry>)> = deserialize_trailing(buf)?;
+        let unique_values = unique_values
+            .into_iter()
+            .map(|(field, values)| (field, values.into_iter().collect()))
+            .collect();
+        let last_mutation_slot: u64 = deserialize_trailing(buf)?;
+        let deltas: CountDeltaRing = deserialize_trailing(buf)?;
+        let admin: Pubkey = deserialize_trailing(buf)?;
+        let frozen: bool = deserialize_trailing(buf)?;
+        let voucher_nonce: u64 = deserialize_trailing(buf)?;
+        let id_strides: Vec<(String, IdStride)> = deserialize_trailing(buf)?;
+        for (table_name, id_stride) in id_strides {
+            index.entry(table_name).or_default().id_stride = id_stride;
+        }
+        let rate_limits: RateLimits = deserialize_trailing(buf)?;
+        if buf.iter().all(|byte| *byte == 0) {
+            *buf = &buf[buf.len()..]; // Skip any zero padding past the last field
+        }
         Ok(Self {
-            index: borsh::BorshDeserialize::deserialize(buf)?,
+            index,
+            generation,
+            unique_values,
+            last_mutation_slot,
+            deltas,
+            admin,
+            frozen,
+            voucher_nonce,
+            rate_limits,
+            case_insensitive_table_names: false,
+            unprefixed_discriminator: !discriminator_prefixed,
         })
     }
+
+    /// Deserializes the index from the whole of `data` (see
+    /// `deserialize_with_discriminator_prefix(..)`), failing if any of it is
+    /// left unread.
+    pub fn try_from_slice_with_discriminator_prefix(
+        data: &[u8],
+        discriminator_prefixed: bool,
+    ) -> Result<Self, borsh::maybestd::io::Error> {
+        let mut buf = data;
+        let index = Self::deserialize_with_discriminator_prefix(&mut buf, discriminator_prefixed)?;
+        if !buf.is_empty() {
+            return Err(borsh::maybestd::io::Error::new(
+                borsh::maybestd::io::ErrorKind::InvalidData,
+                "Not all bytes read",
+            ));
+        }
+        Ok(index)
+    }
+}
+
+impl borsh::de::BorshDeserialize for NautilusIndexData {
+    fn deserialize(buf: &mut &[u8]) -> ::core::result::Result<Self, borsh::maybestd::io::Error> {
+        Self::deserialize_with_discriminator_prefix(buf, DISCRIMINATOR_PREFIXED)
+    }
 }
-impl borsh::ser::BorshSerialize for NautilusIndexData
-where
-    std::collections::HashMap<String, u32>: borsh::BorshSerialize,
-{
+impl borsh::ser::BorshSerialize for NautilusIndexData {
     fn serialize<W: borsh::maybestd::io::Write>(
         &self,
         writer: &mut W,
     ) -> ::core::result::Result<(), borsh::maybestd::io::Error> {
-        borsh::BorshSerialize::serialize(&self.discriminator(), writer)?; // Serialize the discriminator first
-        borsh::BorshSerialize::serialize(&self.index, writer)?;
+        if !self.unprefixed_discriminator {
+            borsh::BorshSerialize::serialize(&self.discriminator(), writer)?; // Serialize the discriminator first
+        }
+        sorted_map_serialize(
+            self.index
+                .iter()
+                .map(|(table_name, entry)| (table_name, entry.count)),
+            writer,
+        )?;
+        let trailing_fields = self.trailing_fields()?;
+        for field in trailing_fields
+            .iter()
+            .take(written_trailing_fields(&trailing_fields))
+        {
+            writer.write_all(field)?;
+        }
         Ok(())
     }
 }
@@ -83,7 +876,35 @@ impl NautilusRecordData for NautilusIndexData {
     const AUTO_INCREMENT: bool = false;
 
     fn primary_key(&self) -> Vec<u8> {
-        vec![0]
+        INDEX_PRIMARY_KEY.to_vec()
+    }
+
+    /// The span of the serialized index, summed from the size of each table's
+    /// entry (see `entry_span(..)`) and each trailing field that gets written,
+    /// without serializing it.
+    ///
+    /// Measuring a large index by serializing it costs as much as writing it,
+    /// and mutations measure it ahead of writing it - to check the realloc
+    /// limit, plan a transaction's budget, or project its rent. Summing sizes
+    /// instead allocates nothing, and measures a mutation as the index's old
+    /// span plus the bytes of the entries it adds or removes.
+    fn span(&self) -> Result<usize, ProgramError> {
+        let counts_span: usize = self
+            .index
+            .keys()
+            .map(|table_name| Self::entry_span(table_name))
+            .sum();
+        let trailing_spans = self.trailing_field_spans();
+        let trailing_span: usize = trailing_spans
+            .iter()
+            .take(written_trailing_spans(&trailing_spans))
+            .map(|(span, _)| span)
+            .sum();
+        let discriminator_span = match self.unprefixed_discriminator {
+            true => 0,
+            false => 8,
+        };
+        Ok(discriminator_span + 4 + counts_span + trailing_span)
     }
 
     fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
@@ -95,6 +916,51 @@ impl NautilusRecordData for NautilusIndexData {
     }
 }
 
+/// The primary key of the singleton Nautilus Index.
+const INDEX_PRIMARY_KEY: &[u8] = &[0];
+
+/// Returns the address and bump of the Nautilus Index of `program_id` - the
+/// same address `NautilusIndex` derives - so clients and other programs can
+/// find the index without loading it.
+pub fn nautilus_index_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    let seeds_vec = record_seeds(NautilusIndexData::TABLE_NAME, INDEX_PRIMARY_KEY);
+    let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+    Pubkey::find_program_address(&seeds, program_id)
+}
+
+/// Returns the address and bump the next record of `table_name` will be created
+/// at - that of the id `get_next_count(table_name)` returns - without adding it
+/// to the index, so clients can pass the record's account to the instruction
+/// creating it.
+///
+/// This derives the same seeds the program does when it creates a record of
+/// type `T` from `index_data` (see `NautilusRecordData::seeds_for(..)`, and
+/// `NautilusIndexData::table_name(..)` for case-insensitive mode), so the
+/// address matches as long as no other record of the table is added first. It
+/// doesn't apply to child records, which are derived under their parent.
+///
+/// Fails with `NautilusError::IdSpaceExhausted` if the table has no ids left
+/// to assign - within `T`'s primary key type, too - and with
+/// `ProgramError::InvalidArgument` if `T`'s primary key isn't autoincremented.
+pub fn next_pda<T: NautilusRecordData>(
+    program_id: &Pubkey,
+    index_data: &NautilusIndexData,
+    table_name: &str,
+) -> Result<(Pubkey, u8), ProgramError> {
+    if !T::AUTO_INCREMENT {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let exhausted = || NautilusError::IdSpaceExhausted(table_name.to_string());
+    let id = match index_data.get_next_count(table_name) {
+        0 => return Err(exhausted().into()),
+        id => id,
+    };
+    let primary_key = T::primary_key_for_id(id).ok_or_else(exhausted)?;
+    let seeds_vec = T::seeds_for(&index_data.table_name(table_name), &primary_key);
+    let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+    Ok(Pubkey::find_program_address(&seeds, program_id))
+}
+
 /// The special Nautilus object representing the accompanying index for a
 /// Nautilus program.
 ///
@@ -103,40 +969,187 @@ impl NautilusRecordData for NautilusIndexData {
 ///
 /// This single account is used as a reference to enable autoincrementing of
 /// records.
+///
+/// The `original_span` field holds the length of the account's data when it
+/// was first handed to this instruction, which is what the runtime measures the
+/// per-instruction realloc limit against. The `synced_generation` field holds
+/// the generation of the index as this copy last read it from, or wrote it to,
+/// its account - `None` for a copy instantiated without loading it - so that
+/// `refresh(..)` can tell whether another copy has written the account since.
+///
+/// The `rent_calculator` field optionally overrides how much rent the index
+/// requires (see `with_rent_calculator(..)`), and the `lock` field optionally
+/// holds an `IndexLock` to check before any modification, along with the
+/// session to check it for (see `with_lock(..)`). The `changelog` field
+/// optionally holds an `IndexChangelog` to append every count change to (see
+/// `with_changelog(..)`). The `count_fallback` field sets whether `get_count(..)`
+/// falls back to the account's data (see `with_count_fallback(..)`). The
+/// `voucher_authority` field optionally holds the signer of the vouchers the
+/// index accepts, along with the Instructions sysvar account to verify their
+/// signatures through (see `with_voucher_authority(..)`).
 #[derive(Clone)]
 pub struct NautilusIndex<'a> {
     pub program_id: &'a Pubkey,
     pub account_info: Box<AccountInfo<'a>>,
     pub data: NautilusIndexData,
+    pub original_span: usize,
+    pub synced_generation: Option<u64>,
+    pub rent_calculator: Option<RentCalculator>,
+    pub lock: Option<(IndexLock<'a>, &'a Pubkey)>,
+    pub changelog: Option<IndexChangelog<'a>>,
+    pub count_fallback: bool,
+    pub voucher_authority: Option<(&'a Pubkey, Box<AccountInfo<'a>>)>,
 }
 
 impl<'a> NautilusIndex<'a> {
     /// Instantiate a new `NautilusIndex` without loading the account inner data
     /// from on-chain.
     pub fn new(program_id: &'a Pubkey, account_info: Box<AccountInfo<'a>>) -> Self {
+        let original_span = account_info.data_len();
         Self {
             program_id,
             account_info,
             data: NautilusIndexData::default(),
+            original_span,
+            synced_generation: None,
+            rent_calculator: None,
+            lock: None,
+            changelog: None,
+            count_fallback: false,
+            voucher_authority: None,
         }
     }
 
+    /// Instantiate a new `NautilusIndex` without loading the account inner data
+    /// from on-chain, with each of `starting_counts`' tables starting at the
+    /// given count, so its first record is assigned the count plus one.
+    ///
+    /// Reproducibility: with the same starting counts, the same sequence of
+    /// additions to a table always assigns the same ids - and so derives the
+    /// same addresses - however tests are ordered or scheduled, so snapshot
+    /// assertions on them stay stable across runs.
+    ///
+    /// Only available with the `test-utils` feature, so a production program
+    /// can't start a table at an arbitrary count.
+    #[cfg(feature = "test-utils")]
+    pub fn new_with_starting_counts(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        starting_counts: &[(&str, u32)],
+    ) -> Self {
+        let mut index = Self::new(program_id, account_info);
+        for (table_name, count) in starting_counts {
+            let table_name = index.data.table_name(table_name).into_owned();
+            index.data.index.entry(table_name).or_default().count = *count;
+        }
+        index
+    }
+
+    /// Instantiate a new `NautilusIndex` like `new(..)`, to be written with its
+    /// discriminator only if `discriminator_prefixed`.
+    ///
+    /// A program's entrypoint passes it the program's `DISCRIMINATOR_PREFIXED`
+    /// (see `nautilus::DISCRIMINATOR_PREFIXED`), which `new(..)` takes to be
+    /// the default.
+    pub fn new_with_discriminator_prefix(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        discriminator_prefixed: bool,
+    ) -> Self {
+        let mut index = Self::new(program_id, account_info);
+        index.data.unprefixed_discriminator = !discriminator_prefixed;
+        index
+    }
+
     /// Instantiate a new `NautilusIndex` and load the account inner data from
     /// on-chain.
+    ///
+    /// The underlying account must be owned by `program_id`.
     pub fn load(
         program_id: &'a Pubkey,
         account_info: Box<AccountInfo<'a>>,
     ) -> Result<Self, ProgramError> {
-        let data = match NautilusIndexData::try_from_slice(match &account_info.try_borrow_data() {
-            Ok(acct_data) => acct_data,
+        Self::load_with_discriminator_prefix(program_id, account_info, DISCRIMINATOR_PREFIXED)
+    }
+
+    /// Instantiate a new `NautilusIndex` like `load(..)`, reading and writing
+    /// the account's data as beginning with the index's discriminator only if
+    /// `discriminator_prefixed`.
+    ///
+    /// A program's entrypoint passes it the program's `DISCRIMINATOR_PREFIXED`
+    /// (see `nautilus::DISCRIMINATOR_PREFIXED`), which `load(..)` takes to be
+    /// the default.
+    pub fn load_with_discriminator_prefix(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        discriminator_prefixed: bool,
+    ) -> Result<Self, ProgramError> {
+        let data = match NautilusIndexData::try_from_slice_with_discriminator_prefix(
+            match &account_info.try_borrow_data() {
+                Ok(acct_data) => acct_data,
+                Err(_) => {
+                    return Err(NautilusError::LoadDataFailed(
+                        NautilusIndexData::TABLE_NAME.to_string(),
+                        account_info.key.to_string(),
+                    )
+                    .into())
+                }
+            },
+            discriminator_prefixed,
+        ) {
+            Ok(state_data) => state_data,
             Err(_) => {
-                return Err(NautilusError::LoadDataFailed(
+                return Err(NautilusError::DeserializeDataFailed(
                     NautilusIndexData::TABLE_NAME.to_string(),
                     account_info.key.to_string(),
                 )
-                .into())
+                .into());
             }
-        }) {
+        };
+        let original_span = account_info.data_len();
+        Self::from_data(program_id, account_info, data, original_span)
+    }
+
+    /// Instantiate a new `NautilusIndex`, loading the account inner data from
+    /// `data` - such as a snapshot of the account's data - rather than
+    /// borrowing it from the account.
+    ///
+    /// This leaves borrowing the account's data to the caller, for handlers
+    /// that hold another view of the same account. Writes through the index
+    /// still borrow the account's data, and write over it.
+    ///
+    /// `data` is taken to be the account's whole data, so its length is what
+    /// the realloc limit for this instruction is measured from (see
+    /// `check_realloc(..)`).
+    ///
+    /// The underlying account must be owned by `program_id`.
+    pub fn load_from_slice(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        data: &[u8],
+    ) -> Result<Self, ProgramError> {
+        Self::load_from_slice_with_discriminator_prefix(
+            program_id,
+            account_info,
+            data,
+            DISCRIMINATOR_PREFIXED,
+        )
+    }
+
+    /// Instantiate a new `NautilusIndex` like `load_from_slice(..)`, reading
+    /// `data` as beginning with the index's discriminator only if
+    /// `discriminator_prefixed` (see `load_with_discriminator_prefix(..)`).
+    pub fn load_from_slice_with_discriminator_prefix(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        data: &[u8],
+        discriminator_prefixed: bool,
+    ) -> Result<Self, ProgramError> {
+        let original_span = data.len();
+        let data = match NautilusIndexData::try_from_slice_with_discriminator_prefix(
+            data,
+            discriminator_prefixed,
+        ) {
             Ok(state_data) => state_data,
             Err(_) => {
                 return Err(NautilusError::DeserializeDataFailed(
@@ -146,37 +1159,1252 @@ impl<'a> NautilusIndex<'a> {
                 .into());
             }
         };
-        Ok(Self {
+        Self::from_data(program_id, account_info, data, original_span)
+    }
+
+    /// Instantiate a new `NautilusIndex` and load the account inner data from
+    /// on-chain (see `load(..)`), failing with `NautilusError::StaleIndex` if
+    /// the index was last mutated more than `max_age_slots` slots before
+    /// `clock`'s slot.
+    ///
+    /// Staleness model: every mutation made through the `NautilusIndex` stamps
+    /// the index with the slot it was made in, so the age of an index is the
+    /// number of slots since its last mutation - not since it was last read.
+    /// This guards flows that act on counts which must be recent, such as ones
+    /// priced or scheduled off of them. An index that hasn't been written since
+    /// the slot was tracked has a last mutation slot of zero, and so is as stale
+    /// as can be.
+    pub fn load_fresh(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        clock: &Clock,
+        max_age_slots: u64,
+    ) -> Result<Self, ProgramError> {
+        let index = Self::load(program_id, account_info)?;
+        let age = clock.slot.saturating_sub(index.data.last_mutation_slot());
+        if age > max_age_slots {
+            return Err(
+                NautilusError::StaleIndex(index.key().to_string(), age, max_age_slots).into(),
+            );
+        }
+        Ok(index)
+    }
+
+    fn from_data(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        data: NautilusIndexData,
+        original_span: usize,
+    ) -> Result<Self, ProgramError> {
+        let index = Self {
             program_id,
             account_info,
+            synced_generation: Some(data.generation()),
             data,
-        })
+            original_span,
+            rent_calculator: None,
+            lock: None,
+            changelog: None,
+            count_fallback: false,
+            voucher_authority: None,
+        };
+        index.assert_owner_is_deriver()?;
+        Ok(index)
+    }
+
+    /// Overrides the rent the index requires with a custom calculation, such as
+    /// one that over-funds the account with a buffer.
+    ///
+    /// The calculator is handed the index's span and is then used by
+    /// `required_rent(..)`, which the write path relies on: whenever the index
+    /// grows, the fee payer tops its balance up to what the calculator returns.
+    /// Lamports held above that amount are surplus - they're never topped up,
+    /// and they're what any shrinking of the index would refund. So a calculator
+    /// that over-funds keeps its buffer, while one that returns less than the
+    /// Rent Sysvar's minimum leaves the account open to not being rent-exempt.
+    pub fn with_rent_calculator(mut self, rent_calculator: RentCalculator) -> Self {
+        self.rent_calculator = Some(rent_calculator);
+        self
+    }
+
+    /// Sets whether table names are case-insensitive, normalizing them (see
+    /// `nautilus::seeds::normalize_table_name(..)`) before they're looked up
+    /// in or inserted into the index.
+    ///
+    /// This is meant to be set the same way everywhere a program uses its
+    /// index, so pass it a program-wide const, such as:
+    /// `const CASE_INSENSITIVE_TABLE_NAMES: bool = true;`. Records loaded or
+    /// created with a case-insensitive index derive their seeds from the
+    /// normalized table name, so their addresses differ from those of a
+    /// case-sensitive program whenever the table name isn't already lowercase.
+    ///
+    /// Note: tables already stored under a name that isn't normalized are not
+    /// renamed, and won't be found in case-insensitive mode.
+    pub fn with_case_insensitive_table_names(mut self, case_insensitive: bool) -> Self {
+        self.data.case_insensitive_table_names = case_insensitive;
+        self
     }
 
+    /// Guards modifications of the index with an `IndexLock`, which must be
+    /// free, expired or held by `session` for any record to be added or
+    /// removed, or table metadata set.
+    ///
+    /// The session must sign, failing with `NautilusError::AccountNotSigner`
+    /// otherwise, so no one can claim another's hold on the lock. The lock is
+    /// then loaded from its account under the index's program (see
+    /// `IndexLock::load(..)`), so a lock instantiated with `IndexLock::new(..)`
+    /// - whose default data reads as free - can't bypass the guard.
+    pub fn with_lock(
+        mut self,
+        lock: IndexLock<'a>,
+        session: &AccountInfo<'a>,
+    ) -> Result<Self, ProgramError> {
+        if !session.is_signer {
+            return Err(NautilusError::AccountNotSigner(session.key.to_string()).into());
+        }
+        let lock = IndexLock::load(self.program_id, lock.account_info)?;
+        self.lock = Some((lock, session.key));
+        Ok(self)
+    }
+
+    /// Accepts `IndexVoucher`s signed by `authority` in
+    /// `add_record_with_voucher(..)`, verifying their signatures through
+    /// `instructions_sysvar`, the Instructions sysvar account.
+    pub fn with_voucher_authority(
+        mut self,
+        authority: &'a Pubkey,
+        instructions_sysvar: Box<AccountInfo<'a>>,
+    ) -> Self {
+        self.voucher_authority = Some((authority, instructions_sysvar));
+        self
+    }
+
+    /// Appends every change to a table's count - from records being added or
+    /// removed - to `changelog`, which must then be passed and writable in
+    /// every instruction that modifies the index.
+    ///
+    /// See `IndexChangelog` for how the changelog wraps once it's full.
+    pub fn with_changelog(mut self, changelog: IndexChangelog<'a>) -> Self {
+        self.changelog = Some(changelog);
+        self
+    }
+
+    /// Appends a change of a table's count to the changelog, if the index has
+    /// one.
+    pub(crate) fn log_change(&mut self, table_name: &str, old: u32, new: u32) -> ProgramResult {
+        match &mut self.changelog {
+            Some(changelog) => changelog.append(&self.data.table_name(table_name), old, new),
+            None => Ok(()),
+        }
+    }
+
+    /// Stamps the index with the `Clock` sysvar's current slot as the slot it
+    /// was last mutated in.
+    fn stamp_mutation_slot(&mut self) -> ProgramResult {
+        self.data.last_mutation_slot = Clock::get()?.slot;
+        Ok(())
+    }
+
+    /// Reloads the in-memory index from its account if the account has been
+    /// written by another copy of the index since this one was loaded - as told
+    /// by the account's generation differing from the one this copy last read
+    /// or wrote (see `synced_generation`) - so that copy's mutations aren't
+    /// lost when this one writes over them.
+    ///
+    /// Each `NautilusIndex` holds its own deserialized copy of the index, while
+    /// copies of the same account share its data within an instruction. So
+    /// when two handlers - or two `Create<'_, Record<'_, T>>`s, each with its
+    /// own clone of the index - add records to the same table, without this
+    /// the last to write would overwrite the other's increment, assigning the
+    /// same id twice. Every mutation of the index's tables, metadata, unique
+    /// values, admin or frozen flag refreshes it first, as do shrinking it,
+    /// `IndexTxn::add_record(..)` for the first record of a batch and
+    /// `IndexTxn::commit(..)`. Dry runs such as `simulate_add_record(..)` read
+    /// the copy as it is, and `commit(..)` of a two-phase commit fails once its
+    /// refreshed index has moved on from the one it was prepared against.
+    ///
+    /// A copy with mutations of its own that haven't been written - its
+    /// generation ahead of the one it synced - can't be reloaded without
+    /// losing them, so if the account has been written since, this fails with
+    /// `NautilusError::IndexWriteConflict` rather than let either copy's
+    /// mutations be lost. An account without data yet is left alone, as is a
+    /// copy instantiated without loading the account, unless the account's
+    /// generation is ahead of its own.
+    pub fn refresh(&mut self) -> ProgramResult {
+        let account_data = self.account_info.try_borrow_data()?;
+        if account_data.is_empty() {
+            return Ok(());
+        }
+        let data = NautilusIndexData::try_from_slice_with_discriminator_prefix(
+            &account_data,
+            !self.data.unprefixed_discriminator,
+        )
+        .map_err(|_| {
+            NautilusError::DeserializeDataFailed(
+                NautilusIndexData::TABLE_NAME.to_string(),
+                self.account_info.key.to_string(),
+            )
+        })?;
+        drop(account_data);
+        let stale = match self.synced_generation {
+            Some(synced) => data.generation() != synced,
+            None => data.generation() > self.data.generation(),
+        };
+        if !stale {
+            return Ok(());
+        }
+        if let Some(synced) = self.synced_generation {
+            if self.data.generation() != synced {
+                return Err(NautilusError::IndexWriteConflict(
+                    self.account_info.key.to_string(),
+                    synced,
+                    data.generation(),
+                )
+                .into());
+            }
+        }
+        let case_insensitive_table_names = self.data.case_insensitive_table_names;
+        self.synced_generation = Some(data.generation());
+        self.data = data;
+        self.data.case_insensitive_table_names = case_insensitive_table_names;
+        Ok(())
+    }
+
+    /// Fails with `NautilusError::IndexLocked` if the index is guarded by an
+    /// `IndexLock` that another session holds.
+    pub fn check_lock(&self) -> ProgramResult {
+        match &self.lock {
+            Some((lock, session)) => lock.check(session),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with `NautilusError::IndexFrozen` if the index has been frozen by
+    /// its admin (see `freeze(..)`).
+    pub fn check_frozen(&self) -> ProgramResult {
+        match self.data.is_frozen() {
+            true => Err(NautilusError::IndexFrozen(self.key().to_string()).into()),
+            false => Ok(()),
+        }
+    }
+
+    /// Fails with `NautilusError::NotIndexAdmin` unless `authority` is the
+    /// index's admin.
+    fn check_admin(&self, authority: &Pubkey) -> ProgramResult {
+        if self.data.admin == Pubkey::default() || self.data.admin != *authority {
+            return Err(NautilusError::NotIndexAdmin(
+                self.key().to_string(),
+                authority.to_string(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Sets the index's admin - the authority that may freeze and unfreeze it -
+    /// writing the index with `authority` funding the additional rent.
+    ///
+    /// Once the index has an admin, only that admin can hand the role over, so
+    /// `authority` must be the current admin. Setting the first admin isn't
+    /// guarded by the index, though: the program must only do so from an
+    /// instruction that's itself restricted, such as the one initializing the
+    /// index.
+    pub fn set_admin(
+        &mut self,
+        admin: &Pubkey,
+        authority: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        self.refresh()?;
+        if self.data.admin != Pubkey::default() {
+            self.check_admin(authority.key())?;
+        }
+        self.data.admin = *admin;
+        self.data.bump_generation();
+        self.write(authority)
+    }
+
+    /// Freezes the index, so it refuses every mutation with
+    /// `NautilusError::IndexFrozen` - adding, removing and moving records and
+    /// tables, setting metadata, and claiming unique values - until the admin
+    /// unfreezes it. Reads are still allowed.
+    ///
+    /// This is an emergency circuit breaker for incident response, and so
+    /// requires the signature of the index's admin (see `set_admin(..)`), who
+    /// funds the at most one byte the index grows by. It bypasses any
+    /// `IndexLock`, so the index can be frozen while a session holds it.
+    ///
+    /// Freezing only guards the index: record accounts that already exist
+    /// aren't affected, and can still be updated or closed by the program's
+    /// instructions that don't touch the index.
+    pub fn freeze(&mut self, admin: impl NautilusSigner<'a>) -> ProgramResult {
+        self.refresh()?;
+        self.check_admin(admin.key())?;
+        self.data.frozen = true;
+        self.data.bump_generation();
+        self.write(admin)
+    }
+
+    /// Unfreezes an index frozen with `freeze(..)`. Like freezing, this requires
+    /// the signature of the index's admin.
+    pub fn unfreeze(&mut self, admin: impl NautilusSigner<'a>) -> ProgramResult {
+        self.refresh()?;
+        self.check_admin(admin.key())?;
+        self.data.frozen = false;
+        self.data.bump_generation();
+        self.write(admin)
+    }
+
+    /// Whether the index has been frozen by its admin (see `freeze(..)`).
+    pub fn is_frozen(&self) -> bool {
+        self.data.is_frozen()
+    }
+
+    /// Get the current record count for a table.
+    ///
+    /// By default, this only reads the in-memory index. With
+    /// `with_count_fallback(true)`, a table missing from it is looked up in the
+    /// account's data instead (see `with_count_fallback(..)`).
     pub fn get_count(&self, table_name: &str) -> Option<u32> {
-        self.data.get_count(table_name)
+        match self.data.get_count(table_name) {
+            Some(count) => Some(count),
+            None if self.count_fallback => self.read_count(table_name),
+            None => None,
+        }
+    }
+
+    /// Fails with `NautilusError::IdOutOfRange` unless `id` is one the table's
+    /// count has assigned: `1 <= id <= get_count(table_name)` with the default
+    /// id stride, and otherwise one of the first `get_count(table_name)` ids of
+    /// its stride - with a table missing from the index counted as zero.
+    ///
+    /// A cheap integrity check for a record account accepted by its id: while
+    /// verifying its address proves the account was derived from the id, this
+    /// proves the id was ever handed out, catching forged ids that point at
+    /// never-created records.
+    pub fn assert_id_in_range(&self, table_name: &str, id: u32) -> ProgramResult {
+        let count = self.get_count(table_name).unwrap_or_default();
+        let position = self.data.get_id_stride(table_name).position(id);
+        if position.map_or(true, |position| position > count) {
+            return Err(NautilusError::IdOutOfRange(table_name.to_string(), id, count).into());
+        }
+        Ok(())
+    }
+
+    /// Sets whether `get_count(..)` falls back to reading the account's data
+    /// when a table is missing from the in-memory index - such as one that was
+    /// never loaded, or was replaced after the account changed.
+    ///
+    /// The fallback borrows and deserializes the whole index account each time
+    /// it's taken, costing compute proportional to the index's span, so it's off
+    /// by default. The in-memory index isn't updated by the fallback.
+    pub fn with_count_fallback(mut self, count_fallback: bool) -> Self {
+        self.count_fallback = count_fallback;
+        self
+    }
+
+    /// Reads a table's count from the account's data, bypassing the in-memory
+    /// index.
+    fn read_count(&self, table_name: &str) -> Option<u32> {
+        let mut data = NautilusIndexData::try_from_slice_with_discriminator_prefix(
+            &self.account_info.try_borrow_data().ok()?,
+            !self.data.unprefixed_discriminator,
+        )
+        .ok()?;
+        data.case_insensitive_table_names = self.data.case_insensitive_table_names;
+        data.get_count(table_name)
+    }
+
+    /// The number of mutations made to the index (see
+    /// `NautilusIndexData::generation()`).
+    pub fn generation(&self) -> u64 {
+        self.data.generation()
     }
 
     pub fn get_next_count(&self, table_name: &str) -> u32 {
         self.data.get_next_count(table_name)
     }
 
+    /// The sequence a table's ids are assigned from (see `IdStride`).
+    pub fn get_id_stride(&self, table_name: &str) -> IdStride {
+        self.data.get_id_stride(table_name)
+    }
+
+    /// Sets the sequence a table's ids are assigned from (see `IdStride`),
+    /// writing the index with the fee payer funding any additional rent.
+    ///
+    /// Fails with `NautilusError::InvalidIdStride` if the stride's start or
+    /// stride is zero, or if the table has already assigned ids - so a sharded
+    /// table's stride is set from the instruction that sets up each shard's
+    /// index, before any records are added to it.
+    pub fn set_id_stride(
+        &mut self,
+        table_name: &str,
+        id_stride: IdStride,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        check_table_name(table_name)?;
+        if !self.data.set_id_stride(table_name, id_stride) {
+            return Err(NautilusError::InvalidIdStride(table_name.to_string()).into());
+        }
+        self.write(fee_payer)
+    }
+
+    /// Fails with `NautilusError::IdSpaceExhausted` if the table's next id
+    /// would overflow a `u32`.
+    pub(crate) fn check_id_space(&self, table_name: &str) -> ProgramResult {
+        match self.data.get_next_count(table_name) {
+            0 => Err(NautilusError::IdSpaceExhausted(table_name.to_string()).into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// A page of the index's tables and their counts (see
+    /// `NautilusIndexData::list_tables_page(..)`).
+    pub fn list_tables_page(&self, offset: usize, limit: usize) -> (Vec<(String, u32)>, bool) {
+        self.data.list_tables_page(offset, limit)
+    }
+
+    /// The tables whose count satisfies `predicate`, and their counts (see
+    /// `NautilusIndexData::tables_where(..)`).
+    pub fn tables_where(&self, predicate: impl Fn(u32) -> bool) -> Vec<(String, u32)> {
+        self.data.tables_where(predicate)
+    }
+
+    /// Enables the ring of recent count changes (see
+    /// `NautilusIndexData::enable_deltas(..)`), writing the index with the fee
+    /// payer funding the additional rent.
+    pub fn enable_deltas(
+        &mut self,
+        capacity: u32,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        self.data.enable_deltas(capacity);
+        self.write(fee_payer)
+    }
+
+    /// Enables rate limits (see `NautilusIndexData::enable_rate_limits(..)`),
+    /// writing the index with the fee payer funding the additional rent.
+    pub fn enable_rate_limits(
+        &mut self,
+        capacity: u32,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        self.data.enable_rate_limits(capacity);
+        self.write(fee_payer)
+    }
+
+    /// Sets a table's rate limit (see `NautilusIndexData::set_rate_limit(..)`),
+    /// writing the index with the fee payer funding any additional rent.
+    ///
+    /// Limits are enforced by `add_record(..)` - and so by the derived
+    /// `create(..)` - along with `add_record_with_voucher(..)` and
+    /// `create_next(..)`, against the fee payer of each create, failing with
+    /// `NautilusError::RateLimited` until the slot the payer may create again
+    /// in. Batched additions through an `IndexTxn` or a two-phase commit are
+    /// the program's own, and aren't limited.
+    pub fn set_rate_limit(
+        &mut self,
+        table_name: &str,
+        interval: u64,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        check_table_name(table_name)?;
+        self.data.set_rate_limit(table_name, interval);
+        self.write(fee_payer)
+    }
+
+    /// Fails with `NautilusError::RateLimited` if `payer` may not create a
+    /// record in a table yet, returning the current slot to track the create
+    /// at if the table is rate-limited - and `None`, without reading the
+    /// `Clock` sysvar, if it isn't.
+    fn check_rate_limit(
+        &self,
+        table_name: &str,
+        payer: &Pubkey,
+    ) -> Result<Option<u64>, ProgramError> {
+        let limits = &self.data.rate_limits;
+        if !limits.is_enabled() || limits.interval(&self.data.table_name(table_name)).is_none() {
+            return Ok(None);
+        }
+        let slot = Clock::get()?.slot;
+        match self.data.rate_limit_retry_after(table_name, payer, slot) {
+            Some(retry_after) => {
+                Err(NautilusError::RateLimited(table_name.to_string(), retry_after).into())
+            }
+            None => Ok(Some(slot)),
+        }
+    }
+
+    /// The count changes made after `generation` (see
+    /// `NautilusIndexData::changes_since(..)`).
+    pub fn changes_since(&self, generation: u64) -> Option<Vec<CountDelta>> {
+        self.data.changes_since(generation)
+    }
+
+    /// Add a new record to the index, returning the id it's assigned (see
+    /// `NautilusIndexData::add_record(..)`).
+    ///
+    /// Fails with `NautilusError::IdSpaceExhausted` if the table has no ids
+    /// left to assign.
     pub fn add_record(
         &mut self,
         table_name: &str,
         fee_payer: impl NautilusSigner<'a>,
     ) -> Result<u32, ProgramError> {
-        let count = self.data.add_record(table_name);
-        cpi::system::transfer(
-            fee_payer,
-            Mut::<Self>::new(self.clone())?,
-            self.required_rent()? - self.lamports(),
+        self.add_record_with_receipt(table_name, fee_payer)
+            .map(|(id, _)| id)
+    }
+
+    /// Adds a new record to the index as `add_record(..)` does, returning the
+    /// id it's assigned along with the `LamportReceipt` of the rent the fee
+    /// payer funded the index's growth with.
+    pub fn add_record_with_receipt(
+        &mut self,
+        table_name: &str,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<(u32, LamportReceipt), ProgramError> {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        check_table_name(table_name)?;
+        self.check_id_space(table_name)?;
+        let rate_limit_slot = self.check_rate_limit(table_name, fee_payer.key())?;
+        self.check_realloc(
+            self.span()? + self.data.add_record_growth(table_name) + self.data.slot_growth(),
+        )?;
+        let id = self.data.add_record(table_name);
+        if let Some(slot) = rate_limit_slot {
+            self.data.record_create(table_name, fee_payer.key(), slot);
+        }
+        let count = self.data.get_count(table_name).unwrap_or_default();
+        let receipt = self.write_with_receipt(fee_payer)?;
+        self.log_change(table_name, count - 1, count)?;
+        Ok((id, receipt))
+    }
+
+    /// Adds a new record to the index and creates its account as one unit,
+    /// returning the id it's assigned along with the created record.
+    ///
+    /// `data` is given the table's next id (see
+    /// `NautilusRecordData::with_id(..)`), and `account_info` must be the
+    /// account at the address derived for it - clients find it with
+    /// `next_pda(..)`. The increment, and the values of `data`'s unique fields,
+    /// are only held in memory until the account is created: if anything fails
+    /// before then - `data` failing `validate()`, a unique value that's already
+    /// claimed, the account being at another address, or its creation - the
+    /// index is left exactly as it was, with no id spent. Only once the account
+    /// exists is the index written and the change logged.
+    ///
+    /// Fails with `ProgramError::InvalidArgument` if `T`'s primary key isn't
+    /// autoincremented, and with `NautilusError::IdSpaceExhausted` if the table
+    /// has no ids left to assign within `T`'s primary key type.
+    pub fn create_next<T: NautilusRecordData>(
+        &mut self,
+        data: T,
+        account_info: Box<AccountInfo<'a>>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<(u32, Record<'a, T>), ProgramError> {
+        self.create_next_with_receipt(data, account_info, fee_payer)
+            .map(|(id, record, _)| (id, record))
+    }
+
+    /// Adds a new record to the index and creates its account as
+    /// `create_next(..)` does, returning the `LamportReceipt` of both along
+    /// with the id and record: the rent of the record's account, plus that of
+    /// the index's growth.
+    pub fn create_next_with_receipt<T: NautilusRecordData>(
+        &mut self,
+        data: T,
+        account_info: Box<AccountInfo<'a>>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<(u32, Record<'a, T>, LamportReceipt), ProgramError> {
+        if !T::AUTO_INCREMENT {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        let table_name = data.variant_table_name();
+        check_table_name(&table_name)?;
+        self.check_id_space(&table_name)?;
+        let rate_limit_slot = self.check_rate_limit(&table_name, fee_payer.key())?;
+        let mut next = self.data.clone();
+        let id = next.add_record(&table_name);
+        if let Some(slot) = rate_limit_slot {
+            next.record_create(&table_name, fee_payer.key(), slot);
+        }
+        let data = data
+            .with_id(id)
+            .ok_or_else(|| NautilusError::IdSpaceExhausted(table_name.clone()))?;
+        data.validate()?;
+        next.claim_unique_fields(&data)?;
+        self.check_realloc(next.span()? + next.slot_growth())?;
+
+        let mut record = Record::new(self.program_id, account_info, self.clone());
+        *record.data = data;
+        let mut signer_seeds_vec = record.seeds();
+        assert_seed_count(&signer_seeds_vec)?;
+        let (address, bump) = record.pda();
+        if address != *record.key() {
+            return Err(NautilusError::AddressMismatch(
+                record.key().to_string(),
+                address.to_string(),
+            )
+            .into());
+        }
+        signer_seeds_vec.push(vec![bump]);
+        let signer_seeds: Vec<&[u8]> = signer_seeds_vec.iter().map(AsRef::as_ref).collect();
+        let record_receipt = LamportReceipt {
+            rent_paid: record.required_rent()?,
+            ..LamportReceipt::default()
+        };
+        cpi::system::create_pda(
+            record.clone(),
+            self.program_id,
+            fee_payer.clone(),
+            record.data.clone(),
+            signer_seeds,
         )?;
-        self.account_info.realloc(self.span()?, true)?;
-        self.data
-            .serialize(&mut &mut self.account_info.data.borrow_mut()[..])?;
+
+        self.data = next;
+        let index_receipt = self.write_with_receipt(fee_payer)?;
+        let count = self.data.get_count(&table_name).unwrap_or_default();
+        self.log_change(&table_name, count - 1, count)?;
+        emit_record_event(RecordEventKind::Created, record.key(), &*record.data)?;
+        record.index = self.clone();
+        Ok((id, record, record_receipt + index_receipt))
+    }
+
+    /// Add each of `tables` to the index with a count of zero (see
+    /// `NautilusIndexData::init_tables(..)`), in a single realloc and write,
+    /// with the fee payer funding the additional rent.
+    ///
+    /// One-time setup: a program with a fixed set of tables can call this once,
+    /// from the instruction that creates its index, so the index is sized for
+    /// every table up front - otherwise each table is added on its first
+    /// `add_record(..)`, which then pays for a realloc of its own. Calling it
+    /// again is harmless: tables already in the index are left as they are,
+    /// and if every table is, nothing is written.
+    pub fn init_tables(
+        &mut self,
+        tables: &[&str],
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        for table_name in tables {
+            check_table_name(table_name)?;
+        }
+        if self.data.init_tables(tables) == 0 {
+            return Ok(());
+        }
+        self.write(fee_payer)
+    }
+
+    /// Add a new record to the index, returning its count along with the
+    /// address and bump `derive` derives for that count.
+    ///
+    /// This saves the create path a separate `find_program_address(..)` once
+    /// the count is known - for example, with `derive` as
+    /// `|id| Pubkey::find_program_address(&[b"person", &id.to_le_bytes()],
+    /// program_id)`.
+    pub fn add_record_with_pda(
+        &mut self,
+        table_name: &str,
+        fee_payer: impl NautilusSigner<'a>,
+        derive: impl Fn(u32) -> (Pubkey, u8),
+    ) -> Result<(u32, Pubkey, u8), ProgramError> {
+        let count = self.add_record(table_name, fee_payer)?;
+        let (pda, bump) = derive(count);
+        Ok((count, pda, bump))
+    }
+
+    pub fn get_metadata(&self, table_name: &str) -> Option<&[u8]> {
+        self.data.get_metadata(table_name)
+    }
+
+    /// Attach metadata to a table, replacing any it already had.
+    ///
+    /// Since metadata is variable-length, this reallocates the index account,
+    /// with the fee payer funding any additional rent.
+    pub fn set_metadata(
+        &mut self,
+        table_name: &str,
+        metadata: Vec<u8>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        check_table_name(table_name)?;
+        self.data.set_metadata(table_name, metadata);
+        self.write(fee_payer)
+    }
+
+    /// Remove a record from the index, returning the table's new count.
+    ///
+    /// A table's count is decremented in place, but the index can still grow:
+    /// the first mutation of an index in an older layout adds the generation
+    /// and last mutation slot it now keeps. So, like `add_record(..)`, this
+    /// reallocates the index account, with the fee payer funding any
+    /// additional rent.
+    pub fn remove_record(
+        &mut self,
+        table_name: &str,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<u32, ProgramError> {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        let old = self.data.get_count(table_name).unwrap_or_default();
+        let count = match self.data.remove_record(table_name) {
+            Some(count) => count,
+            None => return Err(NautilusError::TableNotFound(table_name.to_string()).into()),
+        };
+        self.write(fee_payer)?;
+        self.log_change(table_name, old, count)?;
+        Ok(count)
+    }
+
+    /// Whether `id` is the table's most recently assigned id (see
+    /// `NautilusIndexData::is_tail_record(..)`).
+    pub fn is_tail_record(&self, table_name: &str, id: u32) -> bool {
+        let id_stride = self.data.get_id_stride(table_name);
+        id != 0
+            && self
+                .get_count(table_name)
+                .and_then(|count| id_stride.id(count))
+                == Some(id)
+    }
+
+    /// Remove the record with the given id from the index, returning the
+    /// table's new count.
+    ///
+    /// Like `NautilusIndexData::close_record(..)`, the count is only
+    /// decremented - through `remove_record(..)` - when `id` is the tail
+    /// record. Closing any other record leaves a gap: the index isn't written,
+    /// and the id is never reassigned.
+    pub fn close_record(
+        &mut self,
+        table_name: &str,
+        id: u32,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<u32, ProgramError> {
+        self.refresh()?;
+        if self.is_tail_record(table_name, id) {
+            return self.remove_record(table_name, fee_payer);
+        }
+        self.check_lock()?;
+        self.check_frozen()?;
+        self.get_count(table_name)
+            .ok_or_else(|| NautilusError::TableNotFound(table_name.to_string()).into())
+    }
+
+    /// Add a record to the index on the authority of a voucher signed off-chain
+    /// by the index's voucher authority (see `with_voucher_authority(..)`),
+    /// returning the table's new count - so a server can gate record creation
+    /// without signing the transaction itself, and the fee payer can be anyone.
+    ///
+    /// The voucher's signature is verified by the ed25519 precompile: the
+    /// instruction right before this one must be an ed25519 program
+    /// instruction verifying the authority's signature of
+    /// `IndexVoucher::message()` (build its data with
+    /// `ed25519_instruction_data(..)`). The precompile fails the transaction
+    /// if the signature is invalid, and this checks that it verified this
+    /// voucher by the voucher authority.
+    ///
+    /// The voucher must be for this index and `table_name` - the two names
+    /// compared as the index resolves table names, so a case-insensitive index
+    /// (see `with_case_insensitive_table_names(..)`) ignores their case - and
+    /// allow the id the record is assigned (`max_count`). Replay protection:
+    /// its nonce must be greater than that of the last voucher redeemed, which
+    /// it then replaces, so each voucher can only be redeemed once - failing
+    /// with `NautilusError::VoucherReplayed` after that. Any other mismatch
+    /// fails with `NautilusError::InvalidVoucher`.
+    pub fn add_record_with_voucher(
+        &mut self,
+        table_name: &str,
+        voucher: &IndexVoucher,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<u32, ProgramError> {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        let invalid = |reason: &str| NautilusError::InvalidVoucher(reason.to_string());
+        let (authority, instructions_sysvar) = match &self.voucher_authority {
+            Some(voucher_authority) => voucher_authority,
+            None => return Err(invalid("the index has no voucher authority").into()),
+        };
+        verify_voucher_signature(instructions_sysvar, authority, &voucher.message()?)?;
+        if voucher.index != *self.key() {
+            return Err(invalid("the voucher is for another index").into());
+        }
+        if self.data.table_name(&voucher.table_name) != self.data.table_name(table_name) {
+            return Err(invalid("the voucher is for another table").into());
+        }
+        if voucher.nonce <= self.data.voucher_nonce {
+            return Err(
+                NautilusError::VoucherReplayed(voucher.nonce, self.data.voucher_nonce).into(),
+            );
+        }
+        if self.data.get_next_count(table_name) > voucher.max_count {
+            return Err(invalid("the voucher's maximum count is reached").into());
+        }
+        let voucher_nonce = self.data.voucher_nonce;
+        self.data.voucher_nonce = voucher.nonce;
+        self.add_record(table_name, fee_payer).map_err(|err| {
+            self.data.voucher_nonce = voucher_nonce;
+            err
+        })
+    }
+
+    /// Plans `add_record(..)` without applying it: nothing is transferred or
+    /// serialized, and the index is left untouched.
+    ///
+    /// Fails the same way `add_record(..)` would for a create paid for by
+    /// `fee_payer` - including with `NautilusError::RateLimited` - short of
+    /// the fee payer's transfer. The create isn't tracked against the payer's
+    /// rate limit.
+    pub fn simulate_add_record(
+        &self,
+        table_name: &str,
+        fee_payer: &Pubkey,
+    ) -> Result<MutationPlan, ProgramError> {
+        self.check_lock()?;
+        self.check_frozen()?;
+        check_table_name(table_name)?;
+        self.check_id_space(table_name)?;
+        self.check_rate_limit(table_name, fee_payer)?;
+        let mut data = self.data.clone();
+        data.add_record(table_name);
+        let count = data.get_count(table_name).unwrap_or_default();
+        let span = data.span()? + data.slot_growth();
+        self.check_realloc(span)?;
+        Ok(MutationPlan {
+            count,
+            span,
+            realloc_size: span.saturating_sub(self.account_info.data_len()),
+            lamport_delta: self.rent_for_span(span)?.saturating_sub(self.lamports()),
+        })
+    }
+
+    /// The rent the index will require after `n` more records are added to
+    /// `table_name`, so a bulk insert can be pre-funded exactly.
+    ///
+    /// This projects the span the index reaches once the inserts are written -
+    /// including a new table's entry, the generation and the last mutation slot
+    /// they first write - and returns the rent for it, as `required_rent()`
+    /// would after the inserts (honoring `with_rent_calculator(..)`). Nothing
+    /// is mutated. With `n` of zero, this is the rent the index requires now.
+    pub fn projected_rent_after(&self, table_name: &str, n: u32) -> Result<u64, ProgramError> {
+        if n == 0 {
+            return self.required_rent();
+        }
+        check_table_name(table_name)?;
+        let mut data = self.data.clone();
+        for _ in 0..n {
+            data.add_record(table_name);
+        }
+        self.rent_for_span(data.span()? + data.slot_growth())
+    }
+
+    /// Plans `remove_record(..)` without applying it: nothing is serialized,
+    /// and the index is left untouched.
+    ///
+    /// Fails the same way `remove_record(..)` would.
+    pub fn simulate_remove_record(&self, table_name: &str) -> Result<MutationPlan, ProgramError> {
+        self.check_lock()?;
+        self.check_frozen()?;
+        let mut data = self.data.clone();
+        let count = match data.remove_record(table_name) {
+            Some(count) => count,
+            None => return Err(NautilusError::TableNotFound(table_name.to_string()).into()),
+        };
+        Ok(MutationPlan {
+            count,
+            span: data.span()?,
+            realloc_size: 0,
+            lamport_delta: 0,
+        })
+    }
+
+    /// Move a table out of this index and into `dest` (see
+    /// `NautilusIndexData::move_table(..)`), writing both indexes and returning
+    /// the table's count in `dest`.
+    ///
+    /// Fails with `NautilusError::TableNotFound` if the table is not in this
+    /// index, and checks both indexes' locks and `dest`'s realloc limit before
+    /// changing either. The fee payer funds any additional rent `dest`
+    /// requires.
+    ///
+    /// Note: both writes happen in the caller's instruction, so the move is
+    /// only atomic within it - the runtime applies all of an instruction's
+    /// account changes or none. A program moving tables between shards over
+    /// several instructions must sequence them itself, so no instruction adds
+    /// records to a table that's mid-move.
+    pub fn move_table(
+        &mut self,
+        table_name: &str,
+        dest: &mut NautilusIndex<'a>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<u32, ProgramError> {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        dest.refresh()?;
+        dest.check_lock()?;
+        dest.check_frozen()?;
+        let old_count = match self.data.get_count(table_name) {
+            Some(count) => count,
+            None => return Err(NautilusError::TableNotFound(table_name.to_string()).into()),
+        };
+        let old_dest_count = dest.data.get_count(table_name).unwrap_or_default();
+        let mut moved_dest = dest.data.clone();
+        let mut moved_source = self.data.clone();
+        let count = moved_source
+            .move_table(table_name, &mut moved_dest)
+            .unwrap_or_default();
+        dest.check_realloc(moved_dest.span()? + moved_dest.slot_growth())?;
+        self.data = moved_source;
+        dest.data = moved_dest;
+        dest.write(fee_payer.clone())?;
+        self.write(fee_payer)?;
+        self.log_change(table_name, old_count, 0)?;
+        dest.log_change(table_name, old_dest_count, count)?;
         Ok(count)
     }
+
+    /// Exports the whole index, serialized in its on-chain layout.
+    pub fn export(&self) -> Result<Vec<u8>, ProgramError> {
+        Ok(self.data.try_to_vec()?)
+    }
+
+    /// The length of the full `export()` in bytes.
+    pub fn export_len(&self) -> Result<usize, ProgramError> {
+        self.span()
+    }
+
+    /// Exports up to `len` bytes of the serialized index, starting at byte
+    /// `offset`. The chunk is empty once `offset` reaches `export_len()`.
+    ///
+    /// This works around the return data limit (`MAX_RETURN_DATA`, 1 KB) for
+    /// an index too large to return in one piece. A reader first gets the
+    /// `export_len()`, then requests consecutive chunks - each at the offset
+    /// the previous chunk ended at - until it has that many bytes, and stitches
+    /// them together into the full export.
+    ///
+    /// The chunks are only consistent if the index isn't modified between
+    /// them, so a reader spanning several transactions should note the
+    /// `generation()` along with the first chunk and the last, and start over
+    /// if they differ. The stitched export deserializes as
+    /// `NautilusIndexData`, whose layout is versioned by its trailing fields:
+    /// a reader that doesn't know about a newer trailing field can still read
+    /// every field ahead of it.
+    pub fn export_chunk(&self, offset: usize, len: usize) -> Result<Vec<u8>, ProgramError> {
+        let export = self.export()?;
+        let start = offset.min(export.len());
+        let end = offset.saturating_add(len).min(export.len());
+        Ok(export[start..end].to_vec())
+    }
+
+    /// The share of the index account's allocated data that the index actually
+    /// uses, from `0.0` to `1.0`.
+    ///
+    /// An account with no data allocated at all counts as fully used.
+    pub fn compaction_ratio(&self) -> f64 {
+        let allocated = self.account_info.data_len();
+        if allocated == 0 {
+            return 1.0;
+        }
+        self.span().unwrap_or(allocated) as f64 / allocated as f64
+    }
+
+    /// Shrinks the index account to exactly the span of the index, refunding
+    /// the lamports it holds above its required rent to the fee payer.
+    ///
+    /// Since the index account is owned by this program, the refund is debited
+    /// from it directly rather than through a CPI. Returns the `LamportReceipt`
+    /// of the refund.
+    pub fn shrink_to_fit(
+        &mut self,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<LamportReceipt, ProgramError> {
+        self.refresh()?;
+        let span = self.span()?;
+        if span < self.account_info.data_len() {
+            let bytes = self.data.try_to_vec()?;
+            self.account_info.realloc(span, false)?;
+            self.copy_into_account(&bytes)?;
+        }
+        let refund = self.lamports().saturating_sub(self.required_rent()?);
+        if refund > 0 {
+            **self.mut_lamports()? -= refund;
+            **fee_payer.mut_lamports()? += refund;
+        }
+        Ok(LamportReceipt {
+            refunded: refund,
+            ..LamportReceipt::default()
+        })
+    }
+
+    /// Calls `shrink_to_fit(..)` only if the `compaction_ratio()` has dropped
+    /// below `threshold`, and otherwise does nothing - returning the default,
+    /// all-zero `LamportReceipt`.
+    pub fn auto_compact_if(
+        &mut self,
+        threshold: f64,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<LamportReceipt, ProgramError> {
+        self.refresh()?;
+        match self.compaction_ratio() < threshold {
+            true => self.shrink_to_fit(fee_payer),
+            false => Ok(LamportReceipt::default()),
+        }
+    }
+
+    /// Opens an `IndexTxn` to batch several index mutations into one write.
+    pub fn txn(&mut self) -> IndexTxn<'a, '_> {
+        IndexTxn::begin(self)
+    }
+
+    /// Checks that resizing the index account to `new_span` stays within the
+    /// realloc limit for this instruction.
+    ///
+    /// The runtime allows an account's data to grow by at most
+    /// `MAX_PERMITTED_DATA_INCREASE` (10 KB) over its length at the start of
+    /// the instruction. Growth is cumulative: every realloc of the index made
+    /// during the instruction - including those made from within CPIs - counts
+    /// against the same allowance, so it is measured from `original_span`
+    /// rather than from the current account length.
+    pub fn check_realloc(&self, new_span: usize) -> ProgramResult {
+        let increase = new_span.saturating_sub(self.original_span);
+        if increase > MAX_PERMITTED_DATA_INCREASE {
+            return Err(NautilusError::ReallocTooLarge(self.key().to_string(), increase).into());
+        }
+        Ok(())
+    }
+
+    /// The rent the index requires at `span`, respecting any custom rent
+    /// calculator.
+    pub(crate) fn rent_for_span(&self, span: usize) -> Result<u64, ProgramError> {
+        match self.rent_calculator {
+            Some(rent_calculator) => rent_calculator(span),
+            None => minimum_balance(span),
+        }
+    }
+
+    /// Stamps the index with its last mutation slot, serializes it, checks the
+    /// realloc limit, funds any additional rent, then reallocates the account
+    /// to the serialized span and copies the bytes into it.
+    ///
+    /// Consistency guarantee: the account holds either the old index or the
+    /// new one, each complete, whenever anything else can read it. The index
+    /// is serialized into a buffer of its own before the account is touched,
+    /// so a failed serialization leaves the account as it was, and the rent
+    /// transfer - the one CPI of the write, and so the one point a program
+    /// could read the account partway through - sees the old index in full.
+    /// The realloc and copy that follow run with no CPI between them, so no
+    /// reader ever sees the account resized but not yet rewritten.
+    pub(crate) fn write(&mut self, fee_payer: impl NautilusSigner<'a>) -> ProgramResult {
+        self.write_with_receipt(fee_payer).map(|_| ())
+    }
+
+    /// Writes the index as `write(..)` does, returning the `LamportReceipt` of
+    /// the rent the fee payer funded: up to the Rent Sysvar's minimum, and
+    /// then any more a custom rent calculator requires as its fee.
+    pub(crate) fn write_with_receipt(
+        &mut self,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<LamportReceipt, ProgramError> {
+        self.stamp_mutation_slot()?;
+        let bytes = self.data.try_to_vec()?;
+        self.check_realloc(bytes.len())?;
+        let lamports = self.lamports();
+        let rent_owed = self.rent_for_span(bytes.len())?.saturating_sub(lamports);
+        let receipt = match (rent_owed, self.rent_calculator) {
+            (0, _) | (_, None) => LamportReceipt {
+                rent_paid: rent_owed,
+                ..LamportReceipt::default()
+            },
+            (_, Some(_)) => {
+                let rent_paid = minimum_balance(bytes.len())?
+                    .saturating_sub(lamports)
+                    .min(rent_owed);
+                LamportReceipt {
+                    rent_paid,
+                    fee_paid: rent_owed - rent_paid,
+                    ..LamportReceipt::default()
+                }
+            }
+        };
+        if rent_owed > 0 {
+            cpi::system::transfer(fee_payer, Mut::<Self>::new(self.clone())?, rent_owed)?;
+        }
+        self.account_info.realloc(bytes.len(), false)?;
+        self.copy_into_account(&bytes)?;
+        Ok(receipt)
+    }
+
+    /// Copies the serialized index over the front of the account's data, in
+    /// one go (see `write(..)`), syncing this copy's generation with it.
+    fn copy_into_account(&mut self, bytes: &[u8]) -> ProgramResult {
+        let mut data = self.account_info.try_borrow_mut_data()?;
+        match data.get_mut(..bytes.len()) {
+            Some(prefix) => prefix.copy_from_slice(bytes),
+            None => return Err(ProgramError::AccountDataTooSmall),
+        }
+        self.synced_generation = Some(self.data.generation());
+        Ok(())
+    }
+}
+
+/// Checks that a table name can be added to the index: that it fits in a seed,
+/// since records derive their addresses from it, and holds no control
+/// characters (see `validate_string(..)`).
+pub(crate) fn check_table_name(table_name: &str) -> ProgramResult {
+    validate_string("table_name", table_name, MAX_SEED_LEN)
+}
+
+/// The planned effects of a mutation of the `NautilusIndex`, as reported by a
+/// dry run such as `NautilusIndex::simulate_add_record(..)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MutationPlan {
+    /// The table's record count after the mutation.
+    pub count: u32,
+    /// The span of the index after the mutation.
+    pub span: usize,
+    /// The number of bytes the index account would be reallocated by beyond
+    /// its current length.
+    pub realloc_size: usize,
+    /// The lamports the fee payer would transfer to the index to fund its rent.
+    pub lamport_delta: u64,
+}
+
+/// A batching guard over several mutations of the `NautilusIndex`.
+///
+/// Records added through an `IndexTxn` only change the in-memory index, and the
+/// rent transfer, realloc and serialization for all of them happen once, on
+/// `commit(..)`. This is useful for instructions that add many records, or add
+/// records to many new tables, at once.
+///
+/// Since the runtime caps an account's growth at 10 KB per instruction
+/// (measured against the account's length when the instruction began - see
+/// `NautilusIndex::check_realloc(..)`), each addition is checked against what
+/// remains of that allowance. An addition that would exceed it fails early with
+/// `NautilusError::ReallocTooLarge` and leaves the index untouched, so the
+/// caller can commit what it has and continue the rest in another instruction.
+///
+/// Dropping an `IndexTxn` without committing it leaves its changes in memory
+/// but never writes them to the account.
+///
+/// Reads through an open `IndexTxn` are isolated at the read-committed level:
+/// `get_count(..)` returns a table's count as of before the batch began,
+/// ignoring the batch's uncommitted additions, while `get_count_pending(..)`
+/// includes them.
+pub struct IndexTxn<'a, 'b> {
+    index: &'b mut NautilusIndex<'a>,
+    changes: Vec<(String, u32)>,
+    committed: std::collections::HashMap<String, Option<u32>>,
+}
+
+impl<'a, 'b> IndexTxn<'a, 'b> {
+    /// Begin a new batch of mutations on the index.
+    pub fn begin(index: &'b mut NautilusIndex<'a>) -> Self {
+        Self {
+            index,
+            changes: vec![],
+            committed: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Add a new record to the in-memory index, returning the id it's
+    /// assigned.
+    pub fn add_record(&mut self, table_name: &str) -> Result<u32, ProgramError> {
+        if self.changes.is_empty() {
+            self.index.refresh()?;
+        }
+        self.index.check_lock()?;
+        self.index.check_frozen()?;
+        check_table_name(table_name)?;
+        self.index.check_id_space(table_name)?;
+        self.index.check_realloc(
+            self.index.span()?
+                + self.index.data.add_record_growth(table_name)
+                + self.index.data.slot_growth(),
+        )?;
+        let committed_count = self.index.get_count(table_name);
+        self.committed
+            .entry(self.index.data.table_name(table_name).into_owned())
+            .or_insert(committed_count);
+        let id = self.index.data.add_record(table_name);
+        let count = self.index.data.get_count(table_name).unwrap_or_default();
+        self.changes.push((table_name.to_string(), count));
+        Ok(id)
+    }
+
+    /// Get a table's record count as of before this batch began, ignoring its
+    /// uncommitted additions.
+    pub fn get_count(&self, table_name: &str) -> Option<u32> {
+        match self
+            .committed
+            .get(self.index.data.table_name(table_name).as_ref())
+        {
+            Some(committed_count) => *committed_count,
+            None => self.index.get_count(table_name),
+        }
+    }
+
+    /// Get a table's record count including this batch's uncommitted
+    /// additions.
+    pub fn get_count_pending(&self, table_name: &str) -> Option<u32> {
+        self.index.get_count(table_name)
+    }
+
+    /// The total number of bytes the index account has grown by during this
+    /// instruction, including the uncommitted changes in this batch - and the
+    /// last mutation slot that committing it stamps the index with.
+    pub fn realloc_delta(&self) -> Result<usize, ProgramError> {
+        Ok((self.index.span()? + self.index.data.slot_growth())
+            .saturating_sub(self.index.original_span))
+    }
+
+    /// The number of bytes the index account can still grow by during this
+    /// instruction.
+    pub fn realloc_remaining(&self) -> Result<usize, ProgramError> {
+        Ok(MAX_PERMITTED_DATA_INCREASE.saturating_sub(self.realloc_delta()?))
+    }
+
+    /// Write all batched mutations to the index account, then append them to
+    /// the index's changelog, if it has one, returning the `LamportReceipt` of
+    /// the rent the fee payer funded the index's growth with.
+    ///
+    /// Fails with `NautilusError::IndexWriteConflict`, writing nothing, if
+    /// another copy of the index has written the account since the batch
+    /// began (see `NautilusIndex::refresh(..)`).
+    pub fn commit(
+        self,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<LamportReceipt, ProgramError> {
+        self.index.refresh()?;
+        let receipt = self.index.write_with_receipt(fee_payer)?;
+        for (table_name, count) in self.changes {
+            self.index.log_change(&table_name, count - 1, count)?;
+        }
+        Ok(receipt)
+    }
 }
 
 impl<'a> NautilusAccountInfo<'a> for NautilusIndex<'a> {
@@ -184,6 +2412,10 @@ impl<'a> NautilusAccountInfo<'a> for NautilusIndex<'a> {
         self.account_info.clone()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        &self.account_info
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.account_info.key
     }
@@ -209,7 +2441,11 @@ impl<'a> NautilusAccountInfo<'a> for NautilusIndex<'a> {
     }
 
     fn span(&self) -> Result<usize, ProgramError> {
-        Ok(self.data.try_to_vec()?.len())
+        self.data.span()
+    }
+
+    fn required_rent(&self) -> Result<u64, ProgramError> {
+        self.rent_for_span(self.span()?)
     }
 }
 
@@ -226,6 +2462,10 @@ impl<'a> NautilusRecord<'a> for NautilusIndex<'a> {
         self.data.pda(self.program_id)
     }
 
+    fn deriver_program(&self) -> &'a Pubkey {
+        self.program_id
+    }
+
     fn primary_key(&self) -> Vec<u8> {
         self.data.primary_key()
     }
@@ -262,7 +2502,8 @@ impl<'a> Create<'a, NautilusIndex<'a>> {
             system_program: self.system_program.clone(),
         })?;
         let data = NautilusIndexData {
-            index: std::collections::HashMap::new(),
+            unprefixed_discriminator: self.self_account.data.unprefixed_discriminator,
+            ..NautilusIndexData::default()
         };
         let data_pointer = Box::new(data);
         let (pda, bump) = self.pda();
@@ -290,7 +2531,8 @@ impl<'a> Create<'a, NautilusIndex<'a>> {
     /// specify a rent payer.
     pub fn create_with_payer(&mut self, payer: impl NautilusSigner<'a>) -> ProgramResult {
         let data = NautilusIndexData {
-            index: std::collections::HashMap::new(),
+            unprefixed_discriminator: self.self_account.data.unprefixed_discriminator,
+            ..NautilusIndexData::default()
         };
         let data_pointer = Box::new(data);
         let (pda, bump) = self.pda();
@@ -328,6 +2570,10 @@ impl<'a> NautilusRecord<'a> for Create<'a, NautilusIndex<'a>> {
         self.self_account.pda()
     }
 
+    fn deriver_program(&self) -> &'a Pubkey {
+        self.self_account.deriver_program()
+    }
+
     fn primary_key(&self) -> Vec<u8> {
         self.self_account.primary_key()
     }
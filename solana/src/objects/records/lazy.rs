@@ -0,0 +1,192 @@
+//! The `LazyIndex`, a read path over the Nautilus Index that reads one table's
+//! entry from the account data instead of deserializing the whole index.
+use borsh::maybestd::io::Error;
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::NautilusError, seeds::normalize_table_name, CountDeltaRing, IdStride, NautilusIndex,
+    NautilusIndexData, NautilusRecordData, NautilusSigner, UniqueField, UniqueValueEntry,
+    ViewField,
+};
+
+/// A lazily-read view of the Nautilus Index, borrowing its account data on
+/// each read rather than deserializing it upfront.
+///
+/// Loading a `NautilusIndex` deserializes every table's entry - and the unique
+/// values, the ring of deltas and the rest of its trailing fields - into maps,
+/// which dominates the cost of an instruction that only needs one table's
+/// count from a large index. A `LazyIndex` instead scans the serialized index
+/// for the one table asked for: `get_count(..)` walks the map of counts at the
+/// front of the account without allocating, and `get_next_count(..)` also skips
+/// over the trailing fields, without deserializing them, to reach the table's
+/// id stride.
+///
+/// The savings are only on the read path. The index is a single serialized
+/// blob, so there's no writing one entry back in place: a mutation such as
+/// `add_record(..)` loads the full `NautilusIndex` - deserializing every entry -
+/// applies the change, and writes the whole modified index back, just as the
+/// `NautilusIndex` would. Instructions that mostly read counts gain the most;
+/// those that mutate the index on every call gain nothing.
+#[derive(Clone)]
+pub struct LazyIndex<'a> {
+    pub program_id: &'a Pubkey,
+    pub account_info: Box<AccountInfo<'a>>,
+    pub case_insensitive_table_names: bool,
+}
+
+impl<'a> LazyIndex<'a> {
+    /// Instantiate a new `LazyIndex` over the index's account, without reading
+    /// its data.
+    ///
+    /// The underlying account must be owned by `program_id`.
+    pub fn load(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+    ) -> Result<Self, ProgramError> {
+        if account_info.owner != program_id {
+            return Err(NautilusError::OwnerMismatch(
+                account_info.key.to_string(),
+                account_info.owner.to_string(),
+                program_id.to_string(),
+            )
+            .into());
+        }
+        Ok(Self {
+            program_id,
+            account_info,
+            case_insensitive_table_names: false,
+        })
+    }
+
+    /// Normalizes table names before they're looked up (see
+    /// `NautilusIndex::with_case_insensitive_table_names(..)`).
+    pub fn with_case_insensitive_table_names(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive_table_names = case_insensitive;
+        self
+    }
+
+    /// Get the current record count for a table, as
+    /// `NautilusIndex::get_count(..)` does.
+    pub fn get_count(&self, table_name: &str) -> Result<Option<u32>, ProgramError> {
+        let table_name = self.table_name(table_name);
+        self.read(|buf| {
+            <[u8; 8]>::skip(buf)?;
+            for _ in 0..u32::view(buf)? {
+                let name = String::view(buf)?;
+                let count = u32::view(buf)?;
+                if name == table_name {
+                    return Ok(Some(count));
+                }
+            }
+            Ok(None)
+        })
+    }
+
+    /// The sequence a table's ids are assigned from, as
+    /// `NautilusIndex::get_id_stride(..)` returns it.
+    pub fn get_id_stride(&self, table_name: &str) -> Result<IdStride, ProgramError> {
+        let table_name = self.table_name(table_name);
+        self.read(|buf| {
+            <[u8; 8]>::skip(buf)?;
+            <Vec<(String, u32)>>::skip(buf)?;
+            let present = skip_trailing::<Vec<(String, Vec<u8>)>>(buf)?
+                && skip_trailing::<u64>(buf)?
+                && skip_trailing::<Vec<(UniqueField, Vec<UniqueValueEntry>)>>(buf)?
+                && skip_trailing::<u64>(buf)?
+                && skip_trailing::<CountDeltaRing>(buf)?
+                && skip_trailing::<Pubkey>(buf)?
+                && skip_trailing::<bool>(buf)?
+                && skip_trailing::<u64>(buf)?;
+            if present && !is_absent(buf) {
+                for _ in 0..u32::view(buf)? {
+                    let name = String::view(buf)?;
+                    let id_stride = IdStride::view(buf)?;
+                    if name == table_name {
+                        return Ok(id_stride);
+                    }
+                }
+            }
+            Ok(IdStride::default())
+        })
+    }
+
+    /// Get the id the next record added to a table will be assigned, as
+    /// `NautilusIndex::get_next_count(..)` does - zero once the table's id
+    /// space is exhausted.
+    pub fn get_next_count(&self, table_name: &str) -> Result<u32, ProgramError> {
+        let count = self.get_count(table_name)?.unwrap_or_default();
+        let id_stride = self.get_id_stride(table_name)?;
+        Ok(count
+            .checked_add(1)
+            .and_then(|n| id_stride.id(n))
+            .unwrap_or_default())
+    }
+
+    /// Loads the full `NautilusIndex` from the account, deserializing every
+    /// entry, so it can be mutated.
+    pub fn to_index(&self) -> Result<NautilusIndex<'a>, ProgramError> {
+        Ok(
+            NautilusIndex::load(self.program_id, self.account_info.clone())?
+                .with_case_insensitive_table_names(self.case_insensitive_table_names),
+        )
+    }
+
+    /// Adds a new record to the index, returning the id it's assigned (see
+    /// `NautilusIndex::add_record(..)`).
+    ///
+    /// Like every mutation through a `LazyIndex`, this loads, modifies and
+    /// writes back the full index (see `to_index()`).
+    pub fn add_record(
+        &self,
+        table_name: &str,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<u32, ProgramError> {
+        self.to_index()?.add_record(table_name, fee_payer)
+    }
+
+    /// The name a table is kept under in the index (see
+    /// `NautilusIndexData::table_name(..)`).
+    fn table_name(&self, table_name: &str) -> String {
+        match self.case_insensitive_table_names {
+            true => normalize_table_name(table_name),
+            false => table_name.to_string(),
+        }
+    }
+
+    /// Reads from the borrowed account data with `read`, failing with
+    /// `NautilusError::DeserializeDataFailed` if it's malformed.
+    fn read<T>(
+        &self,
+        read: impl FnOnce(&mut &[u8]) -> Result<T, Error>,
+    ) -> Result<T, ProgramError> {
+        let data = self.account_info.try_borrow_data().map_err(|_| {
+            NautilusError::LoadDataFailed(
+                NautilusIndexData::TABLE_NAME.to_string(),
+                self.account_info.key.to_string(),
+            )
+        })?;
+        read(&mut &data[..]).map_err(|_| {
+            NautilusError::DeserializeDataFailed(
+                NautilusIndexData::TABLE_NAME.to_string(),
+                self.account_info.key.to_string(),
+            )
+            .into()
+        })
+    }
+}
+
+/// Whether the rest of the serialized index is absent: only zero bytes, or
+/// none at all, which read as the defaults of every remaining trailing field.
+fn is_absent(buf: &[u8]) -> bool {
+    buf.iter().all(|byte| *byte == 0)
+}
+
+/// Skips a trailing field of the serialized index, returning `false` if it's
+/// absent - and so is every trailing field after it.
+fn skip_trailing<'v, T: ViewField<'v>>(buf: &mut &'v [u8]) -> Result<bool, Error> {
+    if is_absent(buf) {
+        return Ok(false);
+    }
+    T::skip(buf)?;
+    Ok(true)
+}
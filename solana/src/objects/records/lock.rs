@@ -0,0 +1,354 @@
+//! The `IndexLock` Nautilus object for coordinating modifications of the
+//! `NautilusIndex` across instructions.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+};
+
+use crate::{
+    cpi, error::NautilusError, Create, NautilusAccountInfo, NautilusRecord, NautilusRecordData,
+    NautilusSigner, Signer, Wallet,
+};
+
+/// The data of the `IndexLock`: who holds the lock, and the slot at which
+/// their hold on it expires.
+///
+/// A `holder` of `Pubkey::default()` means the lock is free. Like the
+/// `NautilusIndexData`, it's serialized after its 8-byte discriminator, which
+/// deserializing checks - so no other account of the program passes for the
+/// lock.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexLockData {
+    pub holder: Pubkey,
+    pub expires_at_slot: u64,
+}
+
+impl IndexLockData {
+    /// Whether the lock is held by anyone other than `session` as of `slot`.
+    pub fn is_held_by_other(&self, session: &Pubkey, slot: u64) -> bool {
+        self.holder != Pubkey::default() && self.holder != *session && slot < self.expires_at_slot
+    }
+}
+
+impl BorshDeserialize for IndexLockData {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, borsh::maybestd::io::Error> {
+        let discriminator: [u8; 8] = BorshDeserialize::deserialize(buf)?;
+        if discriminator != Self::default().discriminator() {
+            return Err(borsh::maybestd::io::Error::new(
+                borsh::maybestd::io::ErrorKind::InvalidData,
+                "Index Lock discriminator mismatch",
+            ));
+        }
+        Ok(Self {
+            holder: BorshDeserialize::deserialize(buf)?,
+            expires_at_slot: BorshDeserialize::deserialize(buf)?,
+        })
+    }
+}
+
+impl BorshSerialize for IndexLockData {
+    fn serialize<W: borsh::maybestd::io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), borsh::maybestd::io::Error> {
+        BorshSerialize::serialize(&self.discriminator(), writer)?;
+        BorshSerialize::serialize(&self.holder, writer)?;
+        BorshSerialize::serialize(&self.expires_at_slot, writer)
+    }
+}
+
+impl NautilusRecordData for IndexLockData {
+    const TABLE_NAME: &'static str = "nautilus_index_lock";
+    const AUTO_INCREMENT: bool = false;
+
+    fn primary_key(&self) -> Vec<u8> {
+        vec![0]
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+/// The optional Nautilus object used to serialize modifications of the
+/// `NautilusIndex` across a whole batch of instructions or transactions.
+///
+/// A session - identified by the signer that acquires the lock - holds the lock
+/// from `acquire_lock(..)` until `release_lock(..)`. While it's held, an index
+/// set up `with_lock(..)` for any other session refuses to add or remove
+/// records or set table metadata, failing with `NautilusError::IndexLocked`.
+///
+/// Timeout semantics: a lock is acquired for a number of slots, and expires at
+/// the `Clock` sysvar's current slot plus that number. From the expiry slot
+/// onward, the lock counts as free, so a holder that crashes without releasing
+/// it only blocks other sessions until then. Slots advance roughly every
+/// 400ms, but not at a guaranteed rate, so choose a timeout with room to spare
+/// for the flow the lock protects. The holder can re-acquire the lock before
+/// it expires to extend its hold.
+#[derive(Clone)]
+pub struct IndexLock<'a> {
+    pub program_id: &'a Pubkey,
+    pub account_info: Box<AccountInfo<'a>>,
+    pub data: IndexLockData,
+}
+
+impl<'a> IndexLock<'a> {
+    /// Instantiate a new `IndexLock` without loading the account inner data
+    /// from on-chain.
+    pub fn new(program_id: &'a Pubkey, account_info: Box<AccountInfo<'a>>) -> Self {
+        Self {
+            program_id,
+            account_info,
+            data: IndexLockData::default(),
+        }
+    }
+
+    /// Instantiate a new `IndexLock` and load the account inner data from
+    /// on-chain.
+    ///
+    /// The underlying account must be owned by `program_id`, be at the lock's
+    /// address derived under it, and hold data beginning with the lock's
+    /// discriminator.
+    pub fn load(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+    ) -> Result<Self, ProgramError> {
+        let data = match IndexLockData::try_from_slice(match &account_info.try_borrow_data() {
+            Ok(acct_data) => acct_data,
+            Err(_) => {
+                return Err(NautilusError::LoadDataFailed(
+                    IndexLockData::TABLE_NAME.to_string(),
+                    account_info.key.to_string(),
+                )
+                .into())
+            }
+        }) {
+            Ok(state_data) => state_data,
+            Err(_) => {
+                return Err(NautilusError::DeserializeDataFailed(
+                    IndexLockData::TABLE_NAME.to_string(),
+                    account_info.key.to_string(),
+                )
+                .into());
+            }
+        };
+        let lock = Self {
+            program_id,
+            account_info,
+            data,
+        };
+        lock.assert_owner_is_deriver()?;
+        let (address, _) = lock.pda();
+        if address != *lock.key() {
+            return Err(NautilusError::AddressMismatch(
+                lock.key().to_string(),
+                address.to_string(),
+            )
+            .into());
+        }
+        Ok(lock)
+    }
+
+    /// Fails with `NautilusError::IndexLocked` if the lock is held by anyone
+    /// other than `session` and hasn't expired.
+    pub fn check(&self, session: &Pubkey) -> ProgramResult {
+        if self.data.is_held_by_other(session, Clock::get()?.slot) {
+            return Err(NautilusError::IndexLocked(
+                self.key().to_string(),
+                self.data.holder.to_string(),
+                self.data.expires_at_slot,
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Acquire the lock for `holder` for the next `timeout_slots` slots.
+    ///
+    /// Fails with `NautilusError::IndexLocked` if another session holds the
+    /// lock and it hasn't expired. If `holder` already holds it, this extends
+    /// its hold instead. The lock's account must be writable, failing with
+    /// `NautilusError::AccountNotMutable` otherwise.
+    pub fn acquire_lock(
+        &mut self,
+        holder: impl NautilusSigner<'a>,
+        timeout_slots: u64,
+    ) -> ProgramResult {
+        self.check_writable()?;
+        self.check(holder.key())?;
+        self.data = IndexLockData {
+            holder: *holder.key(),
+            expires_at_slot: Clock::get()?.slot.saturating_add(timeout_slots),
+        };
+        self.write()
+    }
+
+    /// Release the lock held by `holder`.
+    ///
+    /// Fails with `NautilusError::IndexLocked` if another session holds the
+    /// lock and it hasn't expired. Releasing a lock that's free or expired
+    /// simply clears it. Like acquiring it, this requires the lock's account to
+    /// be writable.
+    pub fn release_lock(&mut self, holder: impl NautilusSigner<'a>) -> ProgramResult {
+        self.check_writable()?;
+        self.check(holder.key())?;
+        self.data = IndexLockData::default();
+        self.write()
+    }
+
+    /// Fails with `NautilusError::AccountNotMutable` if the lock's account
+    /// isn't writable.
+    fn check_writable(&self) -> ProgramResult {
+        if !self.account_info.is_writable {
+            return Err(NautilusError::AccountNotMutable(self.key().to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Serializes the in-memory lock data into the account. The data is of a
+    /// fixed size, so no realloc is needed.
+    fn write(&mut self) -> ProgramResult {
+        self.data
+            .serialize(&mut &mut self.account_info.data.borrow_mut()[..])?;
+        Ok(())
+    }
+}
+
+impl<'a> NautilusAccountInfo<'a> for IndexLock<'a> {
+    fn account_info(&self) -> Box<AccountInfo<'a>> {
+        self.account_info.clone()
+    }
+
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        &self.account_info
+    }
+
+    fn key(&self) -> &'a Pubkey {
+        self.account_info.key
+    }
+
+    fn is_signer(&self) -> bool {
+        self.account_info.is_signer
+    }
+
+    fn is_writable(&self) -> bool {
+        self.account_info.is_writable
+    }
+
+    fn lamports(&self) -> u64 {
+        self.account_info.lamports()
+    }
+
+    fn mut_lamports(&self) -> Result<std::cell::RefMut<'_, &'a mut u64>, ProgramError> {
+        self.account_info.try_borrow_mut_lamports()
+    }
+
+    fn owner(&self) -> &'a Pubkey {
+        self.account_info.owner
+    }
+
+    fn span(&self) -> Result<usize, ProgramError> {
+        self.data.span()
+    }
+}
+
+impl<'a> NautilusRecord<'a> for IndexLock<'a> {
+    fn discriminator(&self) -> [u8; 8] {
+        self.data.discriminator()
+    }
+
+    fn seeds(&self) -> Vec<Vec<u8>> {
+        self.data.seeds()
+    }
+
+    fn pda(&self) -> (Pubkey, u8) {
+        self.data.pda(self.program_id)
+    }
+
+    fn deriver_program(&self) -> &'a Pubkey {
+        self.program_id
+    }
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.data.primary_key()
+    }
+
+    fn check_authorities(&self, accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        self.data.check_authorities(accounts)
+    }
+
+    fn count_authorities(&self) -> u8 {
+        self.data.count_authorities()
+    }
+}
+
+impl<'a> Create<'a, IndexLock<'a>> {
+    /// Create a new, free Index Lock account.
+    pub fn create(&mut self) -> ProgramResult {
+        let payer = Signer::new(Wallet {
+            account_info: self.fee_payer.clone(),
+            system_program: self.system_program.clone(),
+        })?;
+        self.create_with_payer(payer)
+    }
+
+    /// This function is the same as `create(&mut self, ..)` but allows you to
+    /// specify a rent payer.
+    pub fn create_with_payer(&mut self, payer: impl NautilusSigner<'a>) -> ProgramResult {
+        let data_pointer = Box::new(IndexLockData::default());
+        let (pda, bump) = self.pda();
+        assert_eq!(
+            &pda,
+            self.key(),
+            "Derived PDA does not match data for account {:#?}",
+            self.key()
+        );
+        let mut signer_seeds_vec = self.seeds();
+        signer_seeds_vec.push(vec![bump]);
+        let signer_seeds: Vec<&[u8]> = signer_seeds_vec.iter().map(AsRef::as_ref).collect();
+        cpi::system::create_pda(
+            self.self_account.clone(),
+            self.self_account.program_id,
+            payer,
+            data_pointer.clone(),
+            signer_seeds,
+        )?;
+        self.self_account.data = *data_pointer;
+        Ok(())
+    }
+}
+
+impl<'a> NautilusRecord<'a> for Create<'a, IndexLock<'a>> {
+    fn discriminator(&self) -> [u8; 8] {
+        self.self_account.discriminator()
+    }
+
+    fn seeds(&self) -> Vec<Vec<u8>> {
+        self.self_account.seeds()
+    }
+
+    fn pda(&self) -> (Pubkey, u8) {
+        self.self_account.pda()
+    }
+
+    fn deriver_program(&self) -> &'a Pubkey {
+        self.self_account.deriver_program()
+    }
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.self_account.primary_key()
+    }
+
+    fn check_authorities(&self, accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        self.self_account.check_authorities(accounts)
+    }
+
+    fn count_authorities(&self) -> u8 {
+        self.self_account.count_authorities()
+    }
+}
@@ -0,0 +1,127 @@
+//! Merkle commitments to the table counts of the `NautilusIndex`, for light
+//! clients to verify a table's count without downloading the whole index.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::hash::hashv;
+
+use crate::{NautilusIndex, NautilusIndexData};
+
+/// Domain separators, so a leaf can never be passed off as an inner node.
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// A sibling on the path from a leaf to the Merkle root, and which side of the
+/// path it sits on.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MerkleSibling {
+    Left([u8; 32]),
+    Right([u8; 32]),
+}
+
+/// The hash of a table's leaf: its name, followed by its count.
+pub fn merkle_leaf(table_name: &str, count: u32) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, table_name.as_bytes(), &count.to_le_bytes()]).to_bytes()
+}
+
+fn merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Pairs up the nodes of a level from left to right, carrying a node left
+/// without a pair up as is.
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => merkle_node(left, right),
+            single => single[0],
+        })
+        .collect()
+}
+
+/// Checks that `proof` proves table `table_name` has `count` records in an
+/// index with Merkle root `root`.
+pub fn verify_merkle_proof(
+    root: &[u8; 32],
+    table_name: &str,
+    count: u32,
+    proof: &[MerkleSibling],
+) -> bool {
+    let hash = proof.iter().fold(
+        merkle_leaf(table_name, count),
+        |hash, sibling| match sibling {
+            MerkleSibling::Left(left) => merkle_node(left, &hash),
+            MerkleSibling::Right(right) => merkle_node(&hash, right),
+        },
+    );
+    hash == *root
+}
+
+impl NautilusIndexData {
+    /// The Merkle root over each table's `(table_name, count)` leaf, with the
+    /// leaves in order of table name.
+    ///
+    /// Each level pairs up nodes from left to right, and a node left without a
+    /// pair is carried up to the next level as is. An empty index has a root of
+    /// all zeros.
+    pub fn merkle_root(&self) -> [u8; 32] {
+        let mut level = self.merkle_leaves();
+        if level.is_empty() {
+            return [0; 32];
+        }
+        while level.len() > 1 {
+            level = next_level(&level);
+        }
+        level[0]
+    }
+
+    /// The Merkle proof for a table's count against `merkle_root()`, from the
+    /// leaf's sibling up to the root's children.
+    ///
+    /// Returns `None` if the table is not in the index.
+    pub fn merkle_proof(&self, table_name: &str) -> Option<Vec<MerkleSibling>> {
+        let table_name = self.table_name(table_name);
+        let mut position = self
+            .sorted_table_names()
+            .iter()
+            .position(|t| t.as_str() == table_name.as_ref())?;
+        let mut level = self.merkle_leaves();
+        let mut proof = vec![];
+        while level.len() > 1 {
+            if position % 2 == 1 {
+                proof.push(MerkleSibling::Left(level[position - 1]));
+            } else if position + 1 < level.len() {
+                proof.push(MerkleSibling::Right(level[position + 1]));
+            }
+            level = next_level(&level);
+            position /= 2;
+        }
+        Some(proof)
+    }
+
+    fn sorted_table_names(&self) -> Vec<&String> {
+        let mut table_names: Vec<&String> = self.index.keys().collect();
+        table_names.sort();
+        table_names
+    }
+
+    fn merkle_leaves(&self) -> Vec<[u8; 32]> {
+        self.sorted_table_names()
+            .into_iter()
+            .map(|table_name| merkle_leaf(table_name, self.index[table_name].count))
+            .collect()
+    }
+}
+
+impl<'a> NautilusIndex<'a> {
+    /// The Merkle root of the index's table counts (see
+    /// `NautilusIndexData::merkle_root()`).
+    pub fn merkle_root(&self) -> [u8; 32] {
+        self.data.merkle_root()
+    }
+
+    /// The Merkle proof of a table's count (see
+    /// `NautilusIndexData::merkle_proof(..)`).
+    pub fn merkle_proof(&self, table_name: &str) -> Option<Vec<MerkleSibling>> {
+        self.data.merkle_proof(table_name)
+    }
+}
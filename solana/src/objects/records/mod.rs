@@ -1,15 +1,39 @@
 //! The `Record<T>` Nautilus object and all associated trait implementations.
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    account_info::AccountInfo,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    incinerator,
+    program_error::ProgramError,
     pubkey::Pubkey,
+    system_program,
 };
 
 use crate::{
-    cpi, error::NautilusError, Create, Mut, NautilusAccountInfo, NautilusIndex, NautilusMut,
-    NautilusRecord, NautilusRecordData, NautilusSigner, NautilusTransferLamports, Signer, Wallet,
+    assert_seed_count, cpi, emit_record_event,
+    error::NautilusError,
+    minimum_balance, read_chain,
+    seeds::{child_record_seeds, normalize_table_name},
+    write_chain, Create, LamportReceipt, LastWriter, Mut, NautilusAccountInfo, NautilusIndex,
+    NautilusMut, NautilusRecord, NautilusRecordData, NautilusSigner, NautilusTransferLamports,
+    PubkeyKeyed, RecordEventKind, Signer, Versioned, Wallet,
 };
 
+pub mod aggregate;
+pub mod cascade;
+pub mod changelog;
+pub mod delta;
+pub mod event;
 pub mod index;
+pub mod lazy;
+pub mod lock;
+pub mod merkle;
+pub mod overflow;
+pub mod prepare;
+pub mod rate_limit;
+pub mod receipt;
+pub mod schema;
+pub mod unique;
+pub mod voucher;
 
 /// The struct that allows you to treat a Program-Derived-Address (PDA) account
 /// as a table record.
@@ -55,6 +79,9 @@ where
     }
 
     /// Instantiate a new record and load the account inner data from on-chain.
+    ///
+    /// The underlying account must be owned by `program_id` - the program the
+    /// record's address is derived from.
     pub fn load(
         program_id: &'a Pubkey,
         account_info: Box<AccountInfo<'a>>,
@@ -79,13 +106,440 @@ where
                 .into())
             }
         };
-        Ok(Self {
+        let record = Self {
             program_id,
             index,
             account_info,
             data,
+        };
+        record.assert_owner_is_deriver()?;
+        Ok(record)
+    }
+
+    /// Migrates the record in place to a new data type `U`, returning the
+    /// record's new handle.
+    ///
+    /// Guarded by two checks: the account's data must begin with `T`'s
+    /// discriminator - so only a record actually holding `T` is reinterpreted -
+    /// and `authorities` must satisfy `T`'s declared authorities. `new_data`
+    /// must also pass `U::validate(..)`.
+    ///
+    /// The account is then resized to fit `new_data`, keeping its balance at
+    /// exactly the new rent-exempt minimum: the fee payer funds any extra rent
+    /// the new layout requires, and is refunded the rent a smaller layout
+    /// frees up.
+    ///
+    /// Note: the account keeps its address, which was derived from `T`'s
+    /// seeds, so `U`'s seeds won't generally derive it.
+    pub fn reinterpret<U: NautilusRecordData>(
+        self,
+        new_data: U,
+        authorities: Vec<AccountInfo>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<Record<'a, U>, ProgramError> {
+        self.assert_mutable(self.program_id)?;
+        let holds_data_type = self
+            .account_info
+            .try_borrow_data()?
+            .starts_with(&self.data.discriminator());
+        if !holds_data_type {
+            return Err(NautilusError::DiscriminatorMismatch(
+                T::TABLE_NAME.to_string(),
+                self.key().to_string(),
+            )
+            .into());
+        }
+        self.data.check_authorities(authorities)?;
+        new_data.validate()?;
+        self.resize_and_write(&new_data.try_to_vec()?, fee_payer)?;
+        Ok(Record {
+            program_id: self.program_id,
+            index: self.index,
+            account_info: self.account_info,
+            data: Box::new(new_data),
+        })
+    }
+
+    /// Replaces the record's data with `new_data` and writes it to the account,
+    /// unless it serializes to exactly the bytes already on-chain.
+    ///
+    /// The record must first pass `assert_mutable(..)`, and `authorities` must
+    /// satisfy the declared authorities of its current data, if it has any. A
+    /// no-op update then returns `WriteOutcome::Unchanged` without validating,
+    /// reallocating, transferring or serializing anything, saving the compute
+    /// of an idempotent update.
+    ///
+    /// Otherwise, `new_data` must pass `T::validate(..)`, the values of its
+    /// unique fields are claimed in the Nautilus Index, and the account is
+    /// resized to fit it - keeping its balance at exactly the new rent-exempt
+    /// minimum, like `reinterpret(..)`.
+    pub fn update(
+        &mut self,
+        new_data: T,
+        authorities: Vec<AccountInfo>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<WriteOutcome, ProgramError> {
+        self.assert_mutable(self.program_id)?;
+        if self.data.count_authorities() > 0 {
+            self.data.check_authorities(authorities)?;
+        }
+        let bytes = new_data.try_to_vec()?;
+        if **self.account_info.try_borrow_data()? == bytes[..] {
+            *self.data = new_data;
+            return Ok(WriteOutcome::Unchanged);
+        }
+        new_data.validate()?;
+        self.index
+            .claim_unique_fields(&new_data, fee_payer.clone())?;
+        self.resize_and_write(&bytes, fee_payer)?;
+        *self.data = new_data;
+        emit_record_event(RecordEventKind::Updated, self.key(), &*self.data)?;
+        Ok(WriteOutcome::Written)
+    }
+
+    /// Loads the record's data from an overflow chain: its own account, as the
+    /// chain's head, stitched together with the overflow accounts it links to
+    /// (see `nautilus::read_chain(..)`).
+    ///
+    /// A chained record's account begins with an `OverflowHeader` rather than
+    /// the record's data, so it can only be read this way - not with
+    /// `load(..)` - and written with `write_full(..)`. Read amplification:
+    /// every account of the chain must be passed and is borrowed on every read,
+    /// costing compute proportional to the chain's length.
+    pub fn read_full(&mut self, overflow: &[AccountInfo<'a>]) -> ProgramResult {
+        self.assert_owner_is_deriver()?;
+        let bytes = read_chain(&self.account_info, overflow)?;
+        *self.data = T::try_from_slice(&bytes).map_err(|_| {
+            NautilusError::DeserializeDataFailed(T::TABLE_NAME.to_string(), self.key().to_string())
+        })?;
+        Ok(())
+    }
+
+    /// Writes the record's data across an overflow chain headed by its own
+    /// account, for records too large for - or better kept out of - a single
+    /// account (see `nautilus::write_chain(..)`).
+    ///
+    /// Write amplification: every write serializes the whole record and
+    /// rewrites every account of the chain, even if only one field changed.
+    /// Each account is resized to fit its chunk of the data within the
+    /// per-instruction realloc limit, with the fee payer funding any extra rent
+    /// and receiving any refund, and overflow accounts passed beyond those the
+    /// data needs are emptied. The length of a chain is bounded by the number
+    /// of accounts a transaction can pass.
+    ///
+    /// Like `update(..)`, the record must first pass `assert_mutable(..)`, and
+    /// `authorities` must satisfy the declared authorities of its data, if it
+    /// has any.
+    pub fn write_full(
+        &self,
+        overflow: &[AccountInfo<'a>],
+        authorities: Vec<AccountInfo>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        self.assert_mutable(self.program_id)?;
+        if self.data.count_authorities() > 0 {
+            self.data.check_authorities(authorities)?;
+        }
+        self.data.validate()?;
+        write_chain(
+            &self.account_info,
+            overflow,
+            &self.data.try_to_vec()?,
+            fee_payer,
+        )
+    }
+
+    /// Closes the record, first appending its on-chain bytes to `archive`, so
+    /// its history is kept after it's deleted.
+    ///
+    /// The archive must be writable and owned by the record's program. Each
+    /// archived record is appended as a `u32` length followed by its bytes
+    /// (read them back with `archived_records(..)`), so the archive grows by
+    /// 4 bytes plus the record's span on every close. The rent for that growth
+    /// is paid out of the record's own balance, and what's left of it is
+    /// refunded to `refund_to`. The record's account is then emptied and handed
+    /// back to the System Program.
+    ///
+    /// Returns the `LamportReceipt` of the close: the archive's rent as
+    /// `rent_paid`, and the rest of the record's balance as `refunded` - both
+    /// out of the record's balance, rather than a fee payer's.
+    ///
+    /// Guarded like `close_and_burn(..)`: the record's authorities must sign
+    /// among `authorities`, and since the refund goes wherever the caller
+    /// sends it, a record of a table that declares none fails with
+    /// `NautilusError::CloseWithoutAuthority`.
+    ///
+    /// Archival is best-effort within the instruction's realloc budget: an
+    /// archive that can't grow by the record's bytes - past the 10 KB
+    /// per-instruction limit, or the account's maximum size - fails the close
+    /// as a whole, leaving the record in place. The Nautilus Index isn't
+    /// changed.
+    pub fn close_with_archive(
+        self,
+        authorities: Vec<AccountInfo>,
+        archive: &AccountInfo<'a>,
+        refund_to: impl NautilusMut<'a>,
+    ) -> Result<LamportReceipt, ProgramError> {
+        self.assert_mutable(self.program_id)?;
+        if self.data.count_authorities() == 0 {
+            return Err(NautilusError::CloseWithoutAuthority(T::TABLE_NAME.to_string()).into());
+        }
+        self.data.check_authorities(authorities)?;
+        if !archive.is_writable {
+            return Err(NautilusError::AccountNotMutable(archive.key.to_string()).into());
+        }
+        if archive.owner != self.program_id {
+            return Err(ProgramError::IllegalOwner);
+        }
+        let bytes = self.account_info.try_borrow_data()?.to_vec();
+        let increase = 4 + bytes.len();
+        if increase > MAX_PERMITTED_DATA_INCREASE {
+            return Err(NautilusError::ReallocTooLarge(archive.key.to_string(), increase).into());
+        }
+        let archive_span = archive.data_len() + increase;
+        let archive_rent = minimum_balance(archive_span)?.saturating_sub(archive.lamports());
+        let lamports = self.lamports();
+        if archive_rent > lamports {
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        let offset = archive.data_len();
+        archive.realloc(archive_span, false)?;
+        {
+            let mut archive_data = archive.try_borrow_mut_data()?;
+            archive_data[offset..offset + 4].copy_from_slice(&(bytes.len() as u32).to_le_bytes());
+            archive_data[offset + 4..].copy_from_slice(&bytes);
+        }
+        **self.mut_lamports()? = 0;
+        **archive.try_borrow_mut_lamports()? += archive_rent;
+        **refund_to.mut_lamports()? += lamports - archive_rent;
+        self.account_info.realloc(0, false)?;
+        self.account_info.assign(&system_program::ID);
+        emit_record_event(RecordEventKind::Deleted, self.key(), &*self.data)?;
+        Ok(LamportReceipt {
+            rent_paid: archive_rent,
+            refunded: lamports - archive_rent,
+            ..LamportReceipt::default()
         })
     }
+
+    /// Closes the record, burning its rent: every lamport it holds is sent to
+    /// the incinerator (`1nc1nerator11111111111111111111111111111111`) rather
+    /// than refunded, for programs that take rent out of circulation. The
+    /// record's account is then emptied and handed back to the System Program.
+    ///
+    /// Irreversible: the incinerator has no private key, and the runtime
+    /// destroys the lamports it holds at the end of every block, so burned rent
+    /// can never be recovered. Only close a record this way where that's the
+    /// point, and refund its rent with `close_with_archive(..)` otherwise.
+    ///
+    /// Guarded by the record's authorities, which must sign among
+    /// `authorities` - and since a record of a table that declares none could
+    /// be burned by anyone, those fail with
+    /// `NautilusError::BurnWithoutAuthority`. `incinerator` must be the
+    /// writable incinerator account. The Nautilus Index isn't changed.
+    pub fn close_and_burn(
+        self,
+        authorities: Vec<AccountInfo>,
+        incinerator: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        self.assert_mutable(self.program_id)?;
+        if self.data.count_authorities() == 0 {
+            return Err(NautilusError::BurnWithoutAuthority(T::TABLE_NAME.to_string()).into());
+        }
+        self.data.check_authorities(authorities)?;
+        if *incinerator.key != incinerator::ID {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !incinerator.is_writable {
+            return Err(NautilusError::AccountNotMutable(incinerator.key.to_string()).into());
+        }
+        let lamports = self.lamports();
+        **self.mut_lamports()? = 0;
+        **incinerator.try_borrow_mut_lamports()? += lamports;
+        self.account_info.realloc(0, false)?;
+        self.account_info.assign(&system_program::ID);
+        emit_record_event(RecordEventKind::Deleted, self.key(), &*self.data)
+    }
+
+    /// Resizes the account to fit `bytes` - funding or refunding rent so its
+    /// balance is exactly the new rent-exempt minimum - and writes them to it.
+    fn resize_and_write(&self, bytes: &[u8], fee_payer: impl NautilusSigner<'a>) -> ProgramResult {
+        let required_rent = minimum_balance(bytes.len())?;
+        let lamports = self.lamports();
+        if required_rent > lamports {
+            cpi::system::transfer(fee_payer, Mut::new(self.clone())?, required_rent - lamports)?;
+        } else if lamports > required_rent {
+            **self.mut_lamports()? -= lamports - required_rent;
+            **fee_payer.mut_lamports()? += lamports - required_rent;
+        }
+        self.account_info.realloc(bytes.len(), false)?;
+        self.account_info
+            .try_borrow_mut_data()?
+            .copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl<'a, T> Record<'a, T>
+where
+    T: Versioned,
+{
+    /// Instantiate a new record and load the account inner data from on-chain
+    /// (see `load(..)`), along with the version it was read at.
+    pub fn load_versioned(
+        program_id: &'a Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        index: NautilusIndex<'a>,
+    ) -> Result<(Self, u64), ProgramError> {
+        let record = Self::load(program_id, account_info, index)?;
+        let version = record.data.version();
+        Ok((record, version))
+    }
+
+    /// Writes `new_data` to the record, as `update(..)` does, but only if the
+    /// record on-chain is still at `version` - failing with
+    /// `NautilusError::VersionConflict` otherwise. The written data's version
+    /// is bumped past `version`, and returned.
+    ///
+    /// Like `update(..)`, the record must first pass `assert_mutable(..)`, and
+    /// `authorities` must satisfy the declared authorities of its current data,
+    /// if it has any - before the version is compared.
+    ///
+    /// The check reads the account's data, not the in-memory record, so it
+    /// catches a write made since the record was loaded - such as by a CPI, or
+    /// another handle on the same account.
+    ///
+    /// Retry pattern: on a conflict, reload the record with
+    /// `load_versioned(..)`, reapply the change to the fresh data, and write it
+    /// at the new version. Give up after a few attempts, failing the
+    /// instruction, rather than retrying without bound.
+    pub fn write_if_version(
+        &mut self,
+        mut new_data: T,
+        version: u64,
+        authorities: Vec<AccountInfo>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<u64, ProgramError> {
+        self.assert_mutable(self.program_id)?;
+        if self.data.count_authorities() > 0 {
+            self.data.check_authorities(authorities)?;
+        }
+        let current = match T::try_from_slice(&self.account_info.try_borrow_data()?) {
+            Ok(current) => current,
+            Err(_) => {
+                return Err(NautilusError::DeserializeDataFailed(
+                    T::TABLE_NAME.to_string(),
+                    self.key().to_string(),
+                )
+                .into())
+            }
+        };
+        if current.version() != version {
+            return Err(NautilusError::VersionConflict(
+                self.key().to_string(),
+                version,
+                current.version(),
+            )
+            .into());
+        }
+        let new_version = version.wrapping_add(1);
+        new_data.set_version(new_version);
+        new_data.validate()?;
+        self.index
+            .claim_unique_fields(&new_data, fee_payer.clone())?;
+        self.resize_and_write(&new_data.try_to_vec()?, fee_payer)?;
+        *self.data = new_data;
+        emit_record_event(RecordEventKind::Updated, self.key(), &*self.data)?;
+        Ok(new_version)
+    }
+}
+
+impl<'a, T> Record<'a, T>
+where
+    T: LastWriter,
+{
+    /// The key of whoever last wrote the record (see `LastWriter`).
+    pub fn last_writer(&self) -> &Pubkey {
+        self.data.last_writer()
+    }
+
+    /// Writes `new_data` to the record, as `update(..)` does, stamped with
+    /// `writer`'s key as its last writer.
+    ///
+    /// The record is stamped with the signer making the change - not the fee
+    /// payer, when a relayer pays for it - so an update signed by several
+    /// accounts records whichever one is passed as `writer`, typically the
+    /// record's authority. Pass the same signer as both to stamp the payer.
+    /// Stamping a different writer changes the record's bytes, so the update
+    /// is never a no-op unless the writer is the same as the last.
+    pub fn update_as(
+        &mut self,
+        mut new_data: T,
+        authorities: Vec<AccountInfo>,
+        writer: impl NautilusSigner<'a>,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> Result<WriteOutcome, ProgramError> {
+        new_data.set_last_writer(*writer.key());
+        self.update(new_data, authorities, fee_payer)
+    }
+}
+
+/// The records archived in an archive account's data by
+/// `Record::close_with_archive(..)`, oldest first, or `None` if the data isn't
+/// a valid archive.
+pub fn archived_records(archive_data: &[u8]) -> Option<Vec<Vec<u8>>> {
+    crate::seeds::decompose_key(archive_data)
+}
+
+/// Whether a write to an account went through, or was skipped because the
+/// account already held the bytes to write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteOutcome {
+    Written,
+    Unchanged,
+}
+
+impl<'a, T> Record<'a, T>
+where
+    T: PubkeyKeyed,
+{
+    /// Load the record keyed by `pubkey` from on-chain.
+    ///
+    /// Fails with `NautilusError::RecordNotFound` if the underlying account
+    /// isn't at the address derived from `pubkey`, or holds a record keyed by a
+    /// different pubkey.
+    pub fn find_by_pubkey(
+        program_id: &'a Pubkey,
+        pubkey: &Pubkey,
+        account_info: Box<AccountInfo<'a>>,
+        index: NautilusIndex<'a>,
+    ) -> Result<Self, ProgramError> {
+        let not_found = |account: &Pubkey| -> ProgramError {
+            NautilusError::RecordNotFound(
+                T::TABLE_NAME.to_string(),
+                pubkey.to_string(),
+                account.to_string(),
+            )
+            .into()
+        };
+        let (pda, _) = match index.data.case_insensitive_table_names {
+            true => {
+                let seeds_vec = T::seeds_for(&normalize_table_name(T::TABLE_NAME), pubkey.as_ref());
+                let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+                Pubkey::find_program_address(&seeds, program_id)
+            }
+            false => T::pda_for_pubkey(program_id, pubkey),
+        };
+        if pda != *account_info.key {
+            return Err(not_found(account_info.key));
+        }
+        let record = Self::load(program_id, account_info, index)?;
+        if record.data.key_pubkey() != pubkey {
+            return Err(not_found(record.key()));
+        }
+        Ok(record)
+    }
 }
 
 impl<'a, T> NautilusAccountInfo<'a> for Record<'a, T>
@@ -96,6 +550,10 @@ where
         self.account_info.clone()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        &self.account_info
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.account_info.key
     }
@@ -121,7 +579,7 @@ where
     }
 
     fn span(&self) -> Result<usize, ProgramError> {
-        Ok(self.data.try_to_vec()?.len())
+        self.data.span()
     }
 }
 
@@ -133,12 +591,31 @@ where
         self.data.discriminator()
     }
 
+    /// In case-insensitive mode (see
+    /// `NautilusIndex::with_case_insensitive_table_names(..)`), the seeds are
+    /// those of the record's primary key under the normalized table name (and
+    /// under its parent, for a child record).
     fn seeds(&self) -> Vec<Vec<u8>> {
-        self.data.seeds()
+        if !self.index.data.case_insensitive_table_names {
+            return self.data.seeds();
+        }
+        let table_name = normalize_table_name(&self.data.variant_table_name());
+        match self.data.parent() {
+            Some(parent) => {
+                child_record_seeds(parent.as_ref(), &table_name, &self.data.primary_key())
+            }
+            None => T::seeds_for(&table_name, &self.data.primary_key()),
+        }
     }
 
     fn pda(&self) -> (Pubkey, u8) {
-        self.data.pda(self.program_id)
+        let seeds_vec = self.seeds();
+        let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+        Pubkey::find_program_address(&seeds, self.program_id)
+    }
+
+    fn deriver_program(&self) -> &'a Pubkey {
+        self.program_id
     }
 
     fn primary_key(&self) -> Vec<u8> {
@@ -184,11 +661,20 @@ where
     /// This function is specifically not named `create` because `create(&mut
     /// self, ..)` is added by the derive macro
     /// `#[derive(nautilus::Table)]`, which then drives this function.
+    ///
+    /// The record's data is checked with `NautilusRecordData::validate(..)`
+    /// before anything is written, and the values of its unique fields are then
+    /// claimed in the Nautilus Index.
     pub fn create_record(&mut self) -> ProgramResult {
+        self.self_account.data.validate()?;
         let payer = Signer::new(Wallet {
             account_info: self.fee_payer.to_owned(),
             system_program: self.system_program.to_owned(),
         })?;
+        self.self_account
+            .index
+            .claim_unique_fields(&*self.self_account.data, payer.clone())?;
+        assert_seed_count(&self.seeds())?;
         let (pda, bump) = self.pda();
         assert_eq!(
             &pda,
@@ -205,12 +691,22 @@ where
             payer,
             self.self_account.data.clone(),
             signer_seeds,
+        )?;
+        emit_record_event(
+            RecordEventKind::Created,
+            self.key(),
+            &*self.self_account.data,
         )
     }
 
     /// This function is the same as `create_record(&mut self, ..)` but allows
     /// you to specify a rent payer.
     pub fn create_record_with_payer(&mut self, payer: impl NautilusSigner<'a>) -> ProgramResult {
+        self.self_account.data.validate()?;
+        self.self_account
+            .index
+            .claim_unique_fields(&*self.self_account.data, payer.clone())?;
+        assert_seed_count(&self.seeds())?;
         let (pda, bump) = self.pda();
         assert_eq!(
             &pda,
@@ -227,6 +723,60 @@ where
             payer,
             self.self_account.data.clone(),
             signer_seeds,
+        )?;
+        emit_record_event(
+            RecordEventKind::Created,
+            self.key(),
+            &*self.self_account.data,
+        )
+    }
+
+    /// Create a new record's underlying account, owned by `owner_program`
+    /// instead of this program - for example, to hand a program an account it
+    /// expects to be derived from yours.
+    ///
+    /// The account is created at the record's address as a system account,
+    /// then assigned to `owner_program`, so its seeds are still this program's
+    /// and its data must already be set (since the primary key determines the
+    /// address). Since this program no longer owns the account afterwards, the
+    /// record's data is *not* written: the account is left zeroed, for
+    /// `owner_program` to initialize.
+    ///
+    /// Security: `owner_program` must expect to receive accounts this way. A
+    /// program handed a funded, zeroed account it didn't create itself may
+    /// mistake it for one of its own uninitialized accounts, so only use this
+    /// with programs designed to take over accounts created by yours.
+    pub fn create_owned_by(&mut self, owner_program: &Pubkey) -> ProgramResult {
+        let payer = Signer::new(Wallet {
+            account_info: self.fee_payer.to_owned(),
+            system_program: self.system_program.to_owned(),
+        })?;
+        self.create_owned_by_with_payer(owner_program, payer)
+    }
+
+    /// This function is the same as `create_owned_by(&mut self, ..)` but allows
+    /// you to specify a rent payer.
+    pub fn create_owned_by_with_payer(
+        &mut self,
+        owner_program: &Pubkey,
+        payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        assert_seed_count(&self.seeds())?;
+        let (pda, bump) = self.pda();
+        assert_eq!(
+            &pda,
+            self.key(),
+            "Derived PDA does not match data for account {:#?}",
+            self.key()
+        );
+        let mut signer_seeds_vec = self.seeds();
+        signer_seeds_vec.push(vec![bump]);
+        let signer_seeds: Vec<&[u8]> = signer_seeds_vec.iter().map(AsRef::as_ref).collect();
+        cpi::system::create_pda_owned_by(
+            self.self_account.clone(),
+            owner_program,
+            payer,
+            signer_seeds,
         )
     }
 }
@@ -247,6 +797,10 @@ where
         self.self_account.pda()
     }
 
+    fn deriver_program(&self) -> &'a Pubkey {
+        self.self_account.deriver_program()
+    }
+
     fn primary_key(&self) -> Vec<u8> {
         self.self_account.primary_key()
     }
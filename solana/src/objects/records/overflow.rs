@@ -0,0 +1,175 @@
+//! Overflow chaining, for records whose data continues past their own account
+//! into linked overflow accounts.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+};
+
+use crate::{error::NautilusError, minimum_balance, NautilusSigner};
+
+/// The most bytes any account of a chain holds: the per-instruction realloc
+/// limit, so a whole chain can be written in one instruction, even into freshly
+/// created, empty accounts.
+pub const OVERFLOW_CHUNK_LEN: usize = MAX_PERMITTED_DATA_INCREASE;
+
+/// The header at the start of a chained record's head account: the length of
+/// the record's data, and the overflow accounts it continues in, in order.
+///
+/// The head account holds this header followed by the first chunk of the
+/// data, and each overflow account holds the next chunk as is.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OverflowHeader {
+    pub len: u64,
+    pub next: Vec<Pubkey>,
+}
+
+impl OverflowHeader {
+    /// The serialized size of a header linking to `overflow_accounts` accounts.
+    pub fn span(overflow_accounts: usize) -> usize {
+        8 + 4 + 32 * overflow_accounts
+    }
+
+    /// The number of accounts in the chain, including the head account.
+    pub fn chain_len(&self) -> usize {
+        self.next.len() + 1
+    }
+}
+
+/// The number of overflow accounts it takes to chain `len` bytes of data: the
+/// fewest for which the head account's chunk - what's left of
+/// `OVERFLOW_CHUNK_LEN` after the header - and the overflow accounts' chunks
+/// hold it all.
+pub fn overflow_accounts_needed(len: usize) -> usize {
+    let mut overflow_accounts = 0;
+    while OVERFLOW_CHUNK_LEN * (overflow_accounts + 1) - OverflowHeader::span(overflow_accounts)
+        < len
+    {
+        overflow_accounts += 1;
+    }
+    overflow_accounts
+}
+
+/// Splits `bytes` into the data each account of a chain holds, linking the
+/// head account to as many of `overflow` as it takes: the head account's data,
+/// then each overflow account's.
+///
+/// Fails with `NautilusError::OverflowChainTooShort` if `overflow` holds too
+/// few accounts.
+pub fn chain_account_data(bytes: &[u8], overflow: &[Pubkey]) -> Result<Vec<Vec<u8>>, ProgramError> {
+    let needed = overflow_accounts_needed(bytes.len());
+    if overflow.len() < needed {
+        return Err(NautilusError::OverflowChainTooShort(needed, overflow.len()).into());
+    }
+    let head_chunk_len = bytes
+        .len()
+        .min(OVERFLOW_CHUNK_LEN - OverflowHeader::span(needed));
+    let mut head_data = OverflowHeader {
+        len: bytes.len() as u64,
+        next: overflow[..needed].to_vec(),
+    }
+    .try_to_vec()?;
+    head_data.extend_from_slice(&bytes[..head_chunk_len]);
+    let mut data = vec![head_data];
+    data.extend(
+        bytes[head_chunk_len..]
+            .chunks(OVERFLOW_CHUNK_LEN)
+            .map(<[u8]>::to_vec),
+    );
+    Ok(data)
+}
+
+/// Reads the data of a chained record, stitched together from its head account
+/// and the overflow accounts it links to.
+///
+/// `overflow` must begin with the accounts the head links to, in order, each
+/// owned by the same program as the head account.
+pub fn read_chain(head: &AccountInfo, overflow: &[AccountInfo]) -> Result<Vec<u8>, ProgramError> {
+    let mismatch = || NautilusError::OverflowChainMismatch(head.key.to_string()).into();
+    let head_data = head.try_borrow_data()?;
+    let mut remainder: &[u8] = &head_data;
+    let header = OverflowHeader::deserialize(&mut remainder).map_err(|_| mismatch())?;
+    if overflow.len() < header.next.len()
+        || header
+            .next
+            .iter()
+            .zip(overflow)
+            .any(|(key, account)| key != account.key)
+    {
+        return Err(mismatch());
+    }
+    let mut bytes = remainder.to_vec();
+    for account in &overflow[..header.next.len()] {
+        if account.owner != head.owner {
+            return Err(ProgramError::IllegalOwner);
+        }
+        bytes.extend_from_slice(&account.try_borrow_data()?);
+    }
+    if bytes.len() as u64 != header.len {
+        return Err(mismatch());
+    }
+    Ok(bytes)
+}
+
+/// Writes `bytes` as a chained record's data, across its head account and as
+/// many of `overflow` as it takes (see `chain_account_data(..)`), linking the
+/// head account to them.
+///
+/// Every account written must be writable and owned by the same program as the
+/// head account. Each one is resized to fit its chunk, keeping its balance at
+/// exactly the new rent-exempt minimum: the fee payer funds any extra rent, and
+/// is refunded the rent that shrinking frees up. Since no chunk exceeds
+/// `OVERFLOW_CHUNK_LEN`, no account grows past the per-instruction realloc
+/// limit. Any accounts of `overflow` beyond those needed are emptied the same
+/// way - so pass the record's whole previous chain when its data shrinks, or
+/// the accounts dropped from it keep their stale chunks and rent.
+pub fn write_chain<'a>(
+    head: &AccountInfo<'a>,
+    overflow: &[AccountInfo<'a>],
+    bytes: &[u8],
+    fee_payer: impl NautilusSigner<'a>,
+) -> ProgramResult {
+    let keys: Vec<Pubkey> = overflow.iter().map(|account| *account.key).collect();
+    let data = chain_account_data(bytes, &keys)?;
+    let accounts = std::iter::once(head).chain(overflow);
+    for account in accounts.clone() {
+        if !account.is_writable {
+            return Err(NautilusError::AccountNotMutable(account.key.to_string()).into());
+        }
+        if account.owner != head.owner {
+            return Err(ProgramError::IllegalOwner);
+        }
+    }
+    let empty = vec![];
+    for (account, data) in accounts.zip(data.iter().chain(std::iter::repeat(&empty))) {
+        resize_and_write(account, data, fee_payer.clone())?;
+    }
+    Ok(())
+}
+
+/// Resizes an account of a chain to fit `bytes` - funding or refunding rent so
+/// its balance is exactly the new rent-exempt minimum - and writes them to it.
+fn resize_and_write<'a>(
+    account: &AccountInfo<'a>,
+    bytes: &[u8],
+    fee_payer: impl NautilusSigner<'a>,
+) -> ProgramResult {
+    let required_rent = minimum_balance(bytes.len())?;
+    let lamports = account.lamports();
+    if required_rent > lamports {
+        invoke(
+            &system_instruction::transfer(fee_payer.key(), account.key, required_rent - lamports),
+            &[*fee_payer.account_info(), account.clone()],
+        )?;
+    } else if lamports > required_rent {
+        **account.try_borrow_mut_lamports()? -= lamports - required_rent;
+        **fee_payer.mut_lamports()? += lamports - required_rent;
+    }
+    account.realloc(bytes.len(), false)?;
+    account.try_borrow_mut_data()?.copy_from_slice(bytes);
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! The `Prepare` and `Commit` phases of an all-or-nothing insert of several
+//! records through the `NautilusIndex`.
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey, system_program,
+};
+
+use crate::{
+    check_table_name, error::NautilusError, minimum_balance, NautilusAccountInfo, NautilusIndex,
+    NautilusIndexData, NautilusRecordData, NautilusSigner,
+};
+
+/// A plan for creating a set of records all at once, or none of them.
+///
+/// A two-phase commit splits an insert of several records into two steps:
+///
+/// * `NautilusIndex::prepare(..)` reserves an id for each record and validates
+///   every precondition of creating them - that the index isn't locked or
+///   frozen and can grow to hold the new counts, that each record's address is
+///   free, and that the fee payer holds the rent for the index and every
+///   record - without writing anything. The reservations only live in the
+///   `Commit` it returns, so if any check fails, nothing is reserved.
+/// * `NautilusIndex::commit(..)` then writes the reserved counts to the index
+///   and creates each record.
+///
+/// The upfront validation is the point: the runtime can't roll back an account
+/// created partway through an instruction short of failing the whole
+/// transaction, so an insert that creates its records one at a time, checking
+/// as it goes, can only abort the transaction it's in once it finds a record
+/// it can't create. A prepared insert finds out before creating any, and can
+/// instead leave the transaction to carry on without it. This is a stronger
+/// guarantee than a best-effort batch through an `IndexTxn`, which commits
+/// whatever it managed to add.
+#[derive(Clone, Debug, Default)]
+pub struct Prepare {
+    records: Vec<(String, usize)>,
+}
+
+impl Prepare {
+    /// Instantiate a new, empty `Prepare`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a record to the plan: a new record of `table_name` whose account
+    /// will hold `span` bytes.
+    pub fn add(&mut self, table_name: &str, span: usize) -> &mut Self {
+        self.records.push((table_name.to_string(), span));
+        self
+    }
+
+    /// All records in the plan, as their table names and spans, in the order
+    /// they were added.
+    pub fn records(&self) -> &[(String, usize)] {
+        &self.records
+    }
+}
+
+/// A record reserved by `NautilusIndex::prepare(..)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreparedRecord {
+    /// The table the record is added to.
+    pub table_name: String,
+    /// The id reserved for the record.
+    pub id: u32,
+    /// The address derived for the record's id.
+    pub address: Pubkey,
+    /// The bump derived along with the address.
+    pub bump: u8,
+    /// The number of bytes the record's account will hold.
+    pub span: usize,
+    /// The rent-exempt minimum for the record's account.
+    pub rent: u64,
+}
+
+/// The validated plan of a two-phase commit, ready for
+/// `NautilusIndex::commit(..)` (see `Prepare`).
+#[derive(Clone)]
+pub struct Commit {
+    generation: u64,
+    data: NautilusIndexData,
+    changes: Vec<(String, u32)>,
+    records: Vec<PreparedRecord>,
+    index_rent: u64,
+}
+
+impl Commit {
+    /// The records reserved, in the order they were added to the `Prepare`.
+    pub fn records(&self) -> &[PreparedRecord] {
+        &self.records
+    }
+
+    /// The lamports the fee payer funds the commit with: the index's
+    /// additional rent plus every record's rent.
+    pub fn total_rent(&self) -> u64 {
+        self.records
+            .iter()
+            .fold(self.index_rent, |total, record| total + record.rent)
+    }
+}
+
+impl<'a> NautilusIndex<'a> {
+    /// Prepares the records of `plan` for a two-phase commit (see `Prepare`),
+    /// returning the `Commit` holding their reservations.
+    ///
+    /// Each record is reserved the next id of its table, and `derive` derives
+    /// its address and bump from its table name and id. `accounts` must hold
+    /// each of those addresses' accounts, in any order, and each must be free -
+    /// unfunded, empty and owned by the System Program - or this fails with
+    /// `NautilusError::AccountExists`. If the fee payer holds fewer lamports
+    /// than the commit's `total_rent()`, this fails with
+    /// `ProgramError::InsufficientFunds`.
+    ///
+    /// Nothing is mutated, so the index is left exactly as it was whether or
+    /// not this succeeds.
+    pub fn prepare(
+        &self,
+        plan: &Prepare,
+        accounts: &[AccountInfo<'a>],
+        fee_payer: impl NautilusSigner<'a>,
+        derive: impl Fn(&str, u32) -> (Pubkey, u8),
+    ) -> Result<Commit, ProgramError> {
+        self.check_lock()?;
+        self.check_frozen()?;
+        let mut data = self.data.clone();
+        let mut changes = Vec::with_capacity(plan.records.len());
+        let mut records = Vec::with_capacity(plan.records.len());
+        for (table_name, span) in plan.records.iter() {
+            check_table_name(table_name)?;
+            let id = data.add_record(table_name);
+            if id == 0 {
+                return Err(NautilusError::IdSpaceExhausted(table_name.to_string()).into());
+            }
+            let (address, bump) = derive(table_name, id);
+            let account = accounts
+                .iter()
+                .find(|account| *account.key == address)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if account.lamports() != 0
+                || account.owner != &system_program::ID
+                || !account.data_is_empty()
+            {
+                return Err(NautilusError::AccountExists(address.to_string()).into());
+            }
+            changes.push((
+                table_name.to_string(),
+                data.get_count(table_name).unwrap_or_default(),
+            ));
+            records.push(PreparedRecord {
+                table_name: table_name.to_string(),
+                id,
+                address,
+                bump,
+                span: *span,
+                rent: minimum_balance(*span)?,
+            });
+        }
+        let span = data.span()? + data.slot_growth();
+        self.check_realloc(span)?;
+        let commit = Commit {
+            generation: self.generation(),
+            data,
+            changes,
+            records,
+            index_rent: self.rent_for_span(span)?.saturating_sub(self.lamports()),
+        };
+        if fee_payer.lamports() < commit.total_rent() {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        Ok(commit)
+    }
+
+    /// Commits a prepared two-phase commit (see `Prepare`): writes its reserved
+    /// counts to the index, with the fee payer funding any additional rent,
+    /// appends them to the index's changelog, if it has one, then calls
+    /// `create` for each reserved record, in order, to create its account.
+    ///
+    /// Fails with `NautilusError::StaleCommit` if the index was mutated since
+    /// the commit was prepared, since its reservations may no longer hold, and
+    /// checks the index's lock and freeze again - but doesn't repeat the rest of
+    /// `prepare(..)`'s checks, so a commit should follow its preparation within
+    /// the same instruction.
+    pub fn commit(
+        &mut self,
+        commit: Commit,
+        fee_payer: impl NautilusSigner<'a>,
+        mut create: impl FnMut(&PreparedRecord) -> ProgramResult,
+    ) -> ProgramResult {
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        if commit.generation != self.generation() {
+            return Err(NautilusError::StaleCommit(commit.generation, self.generation()).into());
+        }
+        self.data = commit.data;
+        self.write(fee_payer)?;
+        for (table_name, count) in commit.changes {
+            self.log_change(&table_name, count - 1, count)?;
+        }
+        commit.records.iter().try_for_each(&mut create)
+    }
+}
@@ -0,0 +1,179 @@
+//! The opt-in rate limits kept inside the `NautilusIndex`, throttling how often
+//! each payer can create records in a table.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::{Pubkey, MAX_SEED_LEN};
+
+/// A payer's last create in a rate-limited table: the table, the payer, and
+/// the slot the record was created in.
+///
+/// Like a `CountDelta`, the table name is kept zero-padded to `MAX_SEED_LEN`
+/// bytes, so every entry is of the same size. An entry with the default payer
+/// is an empty slot.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PayerCreate {
+    pub table: [u8; MAX_SEED_LEN],
+    pub payer: Pubkey,
+    pub slot: u64,
+}
+
+impl PayerCreate {
+    /// A new entry, with `table_name` truncated to `MAX_SEED_LEN` bytes.
+    pub fn new(table_name: &str, payer: &Pubkey, slot: u64) -> Self {
+        let mut table = [0; MAX_SEED_LEN];
+        let len = table_name.len().min(MAX_SEED_LEN);
+        table[..len].copy_from_slice(&table_name.as_bytes()[..len]);
+        Self {
+            table,
+            payer: *payer,
+            slot,
+        }
+    }
+
+    /// Whether this slot holds no entry.
+    pub fn is_empty(&self) -> bool {
+        self.payer == Pubkey::default()
+    }
+}
+
+/// The rate limits of the `NautilusIndex`: the minimum number of slots each
+/// payer must wait between creates in a table, as `intervals` sorted by table
+/// name, and the last create of each recently active payer, in `entries`.
+///
+/// An interval of 1 allows each payer one create per slot, an interval of 10
+/// one every ten slots, and so on. Only the payers in `entries` are limited,
+/// and `entries` always holds `capacity` slots, so - like the ring of deltas -
+/// the index only grows when rate limiting is enabled, by 72 bytes per payer
+/// it can track, and not as payers create records.
+///
+/// Eviction policy: when a payer not yet tracked creates a record, it takes
+/// the slot of an entry whose interval has already lapsed - which no longer
+/// limits anyone - if there is one, and otherwise that of the entry with the
+/// oldest create. An evicted payer is no longer limited, so the capacity should
+/// exceed the number of distinct payers expected to create records within one
+/// interval: past that, a spammer rotating through more payers than the
+/// capacity evicts its own entries and isn't throttled.
+///
+/// The default rate limits have no capacity, and so are disabled.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub intervals: Vec<(String, u64)>,
+    pub entries: Vec<PayerCreate>,
+}
+
+impl RateLimits {
+    /// Whether rate limits are enforced: whether there's room to track any
+    /// payer.
+    pub fn is_enabled(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    /// The number of payers tracked at once.
+    pub fn capacity(&self) -> u32 {
+        self.entries.len() as u32
+    }
+
+    /// Resizes the tracking of payers to `capacity` slots, keeping the most
+    /// recent creates when shrinking. A capacity of zero disables rate limits.
+    pub fn set_capacity(&mut self, capacity: u32) {
+        self.entries
+            .sort_by_key(|entry| (entry.is_empty(), std::cmp::Reverse(entry.slot)));
+        self.entries
+            .resize(capacity as usize, PayerCreate::default());
+    }
+
+    /// The minimum number of slots between a payer's creates in `table_name`,
+    /// if it's rate-limited.
+    pub fn interval(&self, table_name: &str) -> Option<u64> {
+        self.intervals
+            .binary_search_by(|(name, _)| name.as_str().cmp(table_name))
+            .ok()
+            .map(|position| self.intervals[position].1)
+    }
+
+    /// Sets the minimum number of slots between a payer's creates in
+    /// `table_name`. An interval of zero lifts the table's limit.
+    pub fn set_interval(&mut self, table_name: &str, interval: u64) {
+        match (
+            self.intervals
+                .binary_search_by(|(name, _)| name.as_str().cmp(table_name)),
+            interval,
+        ) {
+            (Ok(position), 0) => {
+                self.intervals.remove(position);
+            }
+            (Ok(position), _) => self.intervals[position].1 = interval,
+            (Err(_), 0) => (),
+            (Err(position), _) => self
+                .intervals
+                .insert(position, (table_name.to_string(), interval)),
+        }
+    }
+
+    /// The slot from which `payer` may next create a record in `table_name`,
+    /// if it may not at `slot`.
+    pub fn retry_after(&self, table_name: &str, payer: &Pubkey, slot: u64) -> Option<u64> {
+        let interval = self.interval(table_name)?;
+        let key = PayerCreate::new(table_name, payer, 0);
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.table == key.table && entry.payer == *payer)?;
+        let retry_after = entry.slot.saturating_add(interval);
+        match slot < retry_after {
+            true => Some(retry_after),
+            false => None,
+        }
+    }
+
+    /// Tracks a create by `payer` in `table_name` at `slot`, evicting another
+    /// payer if every slot is taken (see `RateLimits`).
+    ///
+    /// Creates in tables without a limit, and any create while rate limits are
+    /// disabled, aren't tracked.
+    pub fn record(&mut self, table_name: &str, payer: &Pubkey, slot: u64) {
+        if !self.is_enabled() || self.interval(table_name).is_none() {
+            return;
+        }
+        let create = PayerCreate::new(table_name, payer, slot);
+        let position = match self
+            .entries
+            .iter()
+            .position(|entry| entry.table == create.table && entry.payer == *payer)
+        {
+            Some(position) => position,
+            None => self.eviction_candidate(slot),
+        };
+        self.entries[position] = create;
+    }
+
+    /// The slot a new payer takes: an empty or lapsed entry if there is one,
+    /// and otherwise the oldest.
+    fn eviction_candidate(&self, slot: u64) -> usize {
+        let lapsed = |entry: &PayerCreate| {
+            let table_name = table_name(&entry.table);
+            match self.interval(&table_name) {
+                Some(interval) => entry.slot.saturating_add(interval) <= slot,
+                None => true,
+            }
+        };
+        self.entries
+            .iter()
+            .position(|entry| entry.is_empty() || lapsed(entry))
+            .unwrap_or_else(|| {
+                self.entries
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, entry)| entry.slot)
+                    .map_or(0, |(position, _)| position)
+            })
+    }
+}
+
+/// The table name held zero-padded in `table`.
+fn table_name(table: &[u8; MAX_SEED_LEN]) -> String {
+    let len = table
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |last| last + 1);
+    String::from_utf8_lossy(&table[..len]).into_owned()
+}
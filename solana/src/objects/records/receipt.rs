@@ -0,0 +1,57 @@
+//! The `LamportReceipt` of a mutation, reporting how lamports moved through it.
+
+/// How lamports moved in a mutation of the Nautilus Index or a record, for
+/// reconciling a program's treasury flows.
+///
+/// Every amount is unsigned, with its direction given by its field:
+///
+/// * `rent_paid` - lamports moved into the mutated accounts to keep them
+///   rent-exempt: the fee payer's top-up of an index that grew, or the rent of
+///   an account created. Up to the Rent Sysvar's minimum, so the index's
+///   balance is rent-exempt and no more.
+/// * `fee_paid` - lamports moved in beyond that minimum: what a custom rent
+///   calculator (see `NautilusIndex::with_rent_calculator(..)`) requires on top
+///   of the rent, such as a buffer. Zero without one.
+/// * `refunded` - lamports moved out of the mutated accounts, back to the fee
+///   payer or the refund recipient, such as the surplus rent of a shrunk index
+///   or a closed record.
+///
+/// So `net_paid()` - what went in, less what came out - is positive when the
+/// payer spent lamports on the mutation, and negative when they were refunded
+/// on balance. A mutation that moved no lamports, such as one that updates a
+/// count in place, has the default, all-zero receipt.
+///
+/// The mutations that create, reallocate or close accounts report a receipt:
+/// `NautilusIndex::shrink_to_fit(..)`, `auto_compact_if(..)`,
+/// `IndexTxn::commit(..)` and `Record::close_with_archive(..)` return theirs,
+/// while `NautilusIndex::add_record(..)` and `create_next(..)` keep returning
+/// their ids, with `add_record_with_receipt(..)` and
+/// `create_next_with_receipt(..)` returning the receipt alongside.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LamportReceipt {
+    pub rent_paid: u64,
+    pub fee_paid: u64,
+    pub refunded: u64,
+}
+
+impl LamportReceipt {
+    /// The lamports that went into the mutated accounts, less those that came
+    /// out of them.
+    pub fn net_paid(&self) -> i128 {
+        self.rent_paid as i128 + self.fee_paid as i128 - self.refunded as i128
+    }
+}
+
+/// Sums the receipts of two steps of one mutation, such as the rent of an
+/// index's growth and of the record it counts.
+impl std::ops::Add for LamportReceipt {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            rent_paid: self.rent_paid.saturating_add(rhs.rent_paid),
+            fee_paid: self.fee_paid.saturating_add(rhs.fee_paid),
+            refunded: self.refunded.saturating_add(rhs.refunded),
+        }
+    }
+}
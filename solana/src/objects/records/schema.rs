@@ -0,0 +1,102 @@
+//! The `SchemaRegistry` a program keeps of its record types' schema hashes, so
+//! clients can detect drift between the types they were built against and
+//! those deployed.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{error::NautilusError, seeds::record_seeds, NautilusRecordData};
+
+/// The table name the schema registry's address is derived under.
+const SCHEMA_REGISTRY_TABLE_NAME: &str = "nautilus_schema";
+
+/// The primary key of the singleton schema registry.
+const SCHEMA_REGISTRY_PRIMARY_KEY: &[u8] = &[0];
+
+/// The SHA-256 hash of a schema's UTF-8 bytes, as returned by
+/// `NautilusRecordData::schema_hash()`.
+///
+/// A client without the record type at hand can reproduce a derived record's
+/// hash by hashing its schema string (see `NautilusRecordData::schema()`).
+pub fn schema_hash(schema: &str) -> [u8; 32] {
+    solana_program::hash::hash(schema.as_bytes()).to_bytes()
+}
+
+/// Returns the address and bump of the schema registry of `program_id`.
+pub fn schema_registry_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    let seeds_vec = record_seeds(SCHEMA_REGISTRY_TABLE_NAME, SCHEMA_REGISTRY_PRIMARY_KEY);
+    let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+    Pubkey::find_program_address(&seeds, program_id)
+}
+
+/// A program-level registry of the schema hashes of its record types, each
+/// under its table name, sorted by table name.
+///
+/// A program stores its registry, Borsh-serialized, at
+/// `schema_registry_pda(..)`, rewriting it whenever it's upgraded with changed
+/// record types. Clients fetch its account data, deserialize it with
+/// `SchemaRegistry::try_from_slice(..)` (or, from an `AccountInfo`, read it with
+/// `read_schema_registry(..)`), and `check::<T>()` each record type they were
+/// built against before sending transactions.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaRegistry {
+    pub entries: Vec<(String, [u8; 32])>,
+}
+
+impl SchemaRegistry {
+    /// Instantiate a new, empty `SchemaRegistry`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `T`'s schema hash under its table name, replacing any hash
+    /// already registered for it.
+    pub fn register<T: NautilusRecordData>(&mut self) -> &mut Self {
+        match self
+            .entries
+            .binary_search_by(|(table_name, _)| table_name.as_str().cmp(T::TABLE_NAME))
+        {
+            Ok(position) => self.entries[position].1 = T::schema_hash(),
+            Err(position) => self
+                .entries
+                .insert(position, (T::TABLE_NAME.to_string(), T::schema_hash())),
+        }
+        self
+    }
+
+    /// The schema hash registered for `table_name`, if any.
+    pub fn get(&self, table_name: &str) -> Option<&[u8; 32]> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name == table_name)
+            .map(|(_, hash)| hash)
+    }
+
+    /// Checks that `T`'s schema hash is the one registered for its table,
+    /// failing with `NautilusError::SchemaMismatch` if it differs or none is.
+    pub fn check<T: NautilusRecordData>(&self) -> ProgramResult {
+        match self.get(T::TABLE_NAME) {
+            Some(hash) if *hash == T::schema_hash() => Ok(()),
+            _ => Err(NautilusError::SchemaMismatch(T::TABLE_NAME.to_string()).into()),
+        }
+    }
+}
+
+/// Reads `program_id`'s schema registry from its account, checking that the
+/// account is the registry's address and owned by the program.
+pub fn read_schema_registry(
+    program_id: &Pubkey,
+    account_info: &AccountInfo,
+) -> Result<SchemaRegistry, ProgramError> {
+    if *account_info.key != schema_registry_pda(program_id).0 {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if account_info.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(SchemaRegistry::try_from_slice(
+        &account_info.try_borrow_data()?,
+    )?)
+}
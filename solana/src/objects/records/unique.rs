@@ -0,0 +1,129 @@
+//! Unique constraints on the non-key fields of Nautilus records, enforced
+//! through the secondary index kept in the `NautilusIndex`.
+use solana_program::entrypoint::ProgramResult;
+
+use crate::{
+    error::NautilusError, NautilusIndex, NautilusIndexData, NautilusRecordData, NautilusSigner,
+};
+
+impl NautilusIndexData {
+    /// The primary key of the record holding `value` for a table's unique
+    /// field, if any record holds it.
+    pub fn get_unique_owner(
+        &self,
+        table_name: &str,
+        field_name: &str,
+        value: &[u8],
+    ) -> Option<&[u8]> {
+        self.unique_values
+            .get(&(
+                self.table_name(table_name).into_owned(),
+                field_name.to_string(),
+            ))
+            .and_then(|values| values.get(value))
+            .map(Vec::as_slice)
+    }
+
+    /// Checks that none of the values of `data`'s unique fields are already
+    /// held by a different record, failing with
+    /// `NautilusError::UniqueConstraintViolation` otherwise.
+    pub fn check_unique_fields<T: NautilusRecordData>(&self, data: &T) -> ProgramResult {
+        let primary_key = data.primary_key();
+        for (field_name, value) in data.unique_fields() {
+            if let Some(owner) = self.get_unique_owner(T::TABLE_NAME, field_name, &value) {
+                if owner != primary_key.as_slice() {
+                    return Err(NautilusError::UniqueConstraintViolation(
+                        T::TABLE_NAME.to_string(),
+                        field_name.to_string(),
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Claims the values of `data`'s unique fields for its record, on create or
+    /// update.
+    ///
+    /// All values are checked before any is claimed, so a violation leaves the
+    /// index untouched. Any value the record held before for one of these
+    /// fields is released.
+    pub fn claim_unique_fields<T: NautilusRecordData>(&mut self, data: &T) -> ProgramResult {
+        self.check_unique_fields(data)?;
+        let unique_fields = data.unique_fields();
+        if unique_fields.is_empty() {
+            return Ok(());
+        }
+        let primary_key = data.primary_key();
+        let table_name = self.table_name(T::TABLE_NAME).into_owned();
+        for (field_name, value) in unique_fields {
+            let values = self
+                .unique_values
+                .entry((table_name.clone(), field_name.to_string()))
+                .or_default();
+            values.retain(|held, owner| *owner != primary_key || *held == value);
+            values.insert(value, primary_key.clone());
+        }
+        self.bump_generation();
+        Ok(())
+    }
+
+    /// Releases the values of `data`'s unique fields held by its record, such
+    /// as when the record is deleted.
+    pub fn release_unique_fields<T: NautilusRecordData>(&mut self, data: &T) {
+        let unique_fields = data.unique_fields();
+        if unique_fields.is_empty() {
+            return;
+        }
+        let primary_key = data.primary_key();
+        let table_name = self.table_name(T::TABLE_NAME).into_owned();
+        for (field_name, _) in unique_fields {
+            if let Some(values) = self
+                .unique_values
+                .get_mut(&(table_name.clone(), field_name.to_string()))
+            {
+                values.retain(|_, owner| *owner != primary_key);
+            }
+        }
+        self.bump_generation();
+    }
+}
+
+impl<'a> NautilusIndex<'a> {
+    /// Claims the values of `data`'s unique fields for its record (see
+    /// `NautilusIndexData::claim_unique_fields(..)`), writing the index.
+    ///
+    /// Records without unique fields leave the index untouched.
+    pub fn claim_unique_fields<T: NautilusRecordData>(
+        &mut self,
+        data: &T,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        if data.unique_fields().is_empty() {
+            return Ok(());
+        }
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        self.data.claim_unique_fields(data)?;
+        self.write(fee_payer)
+    }
+
+    /// Releases the values of `data`'s unique fields held by its record (see
+    /// `NautilusIndexData::release_unique_fields(..)`), writing the index.
+    pub fn release_unique_fields<T: NautilusRecordData>(
+        &mut self,
+        data: &T,
+        fee_payer: impl NautilusSigner<'a>,
+    ) -> ProgramResult {
+        if data.unique_fields().is_empty() {
+            return Ok(());
+        }
+        self.refresh()?;
+        self.check_lock()?;
+        self.check_frozen()?;
+        self.data.release_unique_fields(data);
+        self.write(fee_payer)
+    }
+}
@@ -0,0 +1,124 @@
+//! The `IndexVoucher` for adding records to the `NautilusIndex` on the
+//! authority of an off-chain signer, verified through the ed25519 precompile.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, ed25519_program, program_error::ProgramError, pubkey::Pubkey,
+    sysvar::instructions::get_instruction_relative,
+};
+
+use crate::error::NautilusError;
+
+/// An off-chain authorization to add a record to a table of the
+/// `NautilusIndex`, signed by the index's voucher authority (see
+/// `NautilusIndex::with_voucher_authority(..)`).
+///
+/// A voucher is bound to one index and one table, and allows the table to
+/// assign ids up to `max_count` - its count, with the default `IdStride`. Its
+/// `nonce` protects against replay: the index keeps the last nonce it redeemed,
+/// and only accepts vouchers with a greater one, so the authority must issue
+/// nonces in increasing order - such as a counter or a timestamp.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexVoucher {
+    pub index: Pubkey,
+    pub table_name: String,
+    pub max_count: u32,
+    pub nonce: u64,
+}
+
+impl IndexVoucher {
+    /// The message the voucher authority signs: the voucher's serialized data.
+    pub fn message(&self) -> Result<Vec<u8>, ProgramError> {
+        Ok(self.try_to_vec()?)
+    }
+}
+
+/// The size of the ed25519 instruction's header: the number of signatures and
+/// a byte of padding.
+const ED25519_HEADER_LEN: usize = 2;
+
+/// The size of the ed25519 instruction's offsets of a signature: seven `u16`s.
+const ED25519_OFFSETS_LEN: usize = 14;
+
+/// The instruction index the ed25519 offsets use to point into the ed25519
+/// instruction's own data.
+const ED25519_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Builds the data of an ed25519 precompile instruction verifying `signature`
+/// of `message` by `pubkey`, with all three held in the instruction itself -
+/// the layout `verify_voucher_signature(..)` expects.
+///
+/// Clients add an instruction with this data, for the ed25519 program and no
+/// accounts, right before the instruction redeeming the voucher.
+pub fn ed25519_instruction_data(pubkey: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Vec<u8> {
+    let public_key_offset = ED25519_HEADER_LEN + ED25519_OFFSETS_LEN;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+    let mut data = vec![1, 0];
+    for value in [
+        signature_offset as u16,
+        ED25519_CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        ED25519_CURRENT_INSTRUCTION,
+        message_offset as u16,
+        message.len() as u16,
+        ED25519_CURRENT_INSTRUCTION,
+    ] {
+        data.extend(value.to_le_bytes());
+    }
+    data.extend(pubkey.as_ref());
+    data.extend(signature);
+    data.extend(message);
+    data
+}
+
+/// Checks that the instruction right before the current one is an ed25519
+/// precompile instruction verifying a signature of `message` by `authority`.
+///
+/// The runtime fails the whole transaction if the precompile's signature is
+/// invalid, so it only remains to check what was verified. That instruction
+/// must verify exactly one signature, with the public key, signature and
+/// message all held in its own data: offsets pointing into other instructions
+/// could be made to verify other data than what's checked here.
+pub(crate) fn verify_voucher_signature(
+    instructions_sysvar: &AccountInfo,
+    authority: &Pubkey,
+    message: &[u8],
+) -> Result<(), ProgramError> {
+    let invalid = |reason: &str| NautilusError::InvalidVoucher(reason.to_string()).into();
+    let instruction = get_instruction_relative(-1, instructions_sysvar)
+        .map_err(|_| invalid("no instruction precedes the voucher's redemption"))?;
+    if instruction.program_id != ed25519_program::ID {
+        return Err(invalid(
+            "the preceding instruction is not an ed25519 instruction",
+        ));
+    }
+    let data = &instruction.data;
+    if data.len() < ED25519_HEADER_LEN + ED25519_OFFSETS_LEN || data[0] != 1 {
+        return Err(invalid(
+            "the ed25519 instruction must verify exactly one signature",
+        ));
+    }
+    let offset = |i: usize| {
+        let start = ED25519_HEADER_LEN + 2 * i;
+        u16::from_le_bytes([data[start], data[start + 1]])
+    };
+    let (public_key_offset, message_offset, message_len) =
+        (offset(2) as usize, offset(4) as usize, offset(5) as usize);
+    if [offset(1), offset(3), offset(6)]
+        .iter()
+        .any(|index| *index != ED25519_CURRENT_INSTRUCTION)
+    {
+        return Err(invalid(
+            "the ed25519 instruction must hold the data it verifies",
+        ));
+    }
+    if data.get(public_key_offset..public_key_offset + 32) != Some(authority.as_ref()) {
+        return Err(invalid(
+            "the voucher is not signed by the voucher authority",
+        ));
+    }
+    if data.get(message_offset..message_offset + message_len) != Some(message) {
+        return Err(invalid("the signed message is not the voucher"));
+    }
+    Ok(())
+}
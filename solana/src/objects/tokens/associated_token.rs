@@ -83,6 +83,10 @@ impl<'a> NautilusAccountInfo<'a> for AssociatedTokenAccount<'a> {
         self.account_info.clone()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        &self.account_info
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.account_info.key
     }
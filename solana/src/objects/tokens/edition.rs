@@ -78,6 +78,10 @@ impl<'a> NautilusAccountInfo<'a> for Edition<'a> {
         self.account_info.clone()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        &self.account_info
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.account_info.key
     }
@@ -179,6 +183,10 @@ impl<'a> NautilusAccountInfo<'a> for MasterEdition<'a> {
         self.account_info.clone()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        &self.account_info
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.account_info.key
     }
@@ -56,6 +56,10 @@ impl<'a> NautilusAccountInfo<'a> for Nft<'a> {
         self.mint.account_info()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        self.mint.account_info_ref()
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.mint.account_info.key
     }
@@ -55,6 +55,10 @@ where
         self.self_account.account_info()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        self.self_account.account_info_ref()
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.self_account.key()
     }
@@ -82,6 +86,10 @@ where
     fn span(&self) -> Result<usize, ProgramError> {
         self.self_account.span()
     }
+
+    fn required_rent(&self) -> Result<u64, ProgramError> {
+        self.self_account.required_rent()
+    }
 }
 
 impl<'a, T> NautilusMut<'a> for Create<'a, T> where T: NautilusAccountInfo<'a> + 'a {}
@@ -89,7 +97,7 @@ impl<'a, T> NautilusMut<'a> for Create<'a, T> where T: NautilusAccountInfo<'a> +
 impl<'a, T> NautilusSigner<'a> for Create<'a, T> where T: NautilusAccountInfo<'a> + 'a {}
 
 fn check_account_does_not_exist<'a>(account: &impl NautilusAccountInfo<'a>) -> bool {
-    let account_info = account.account_info();
+    let account_info = account.account_info_ref();
     account_info.lamports() == 0
         && account_info.owner.eq(&solana_program::system_program::ID)
         && account_info.data_is_empty()
@@ -1,6 +1,13 @@
 //! Traits used for managing the account data of Nautilus objects.
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEEDS},
+};
+
+use crate::error::NautilusError;
 
 use super::NautilusAccountInfo;
 
@@ -47,12 +54,161 @@ pub trait NautilusRecordData: BorshDeserialize + BorshSerialize + Clone + Defaul
     /// key for this table.
     fn primary_key(&self) -> Vec<u8>;
 
+    /// This data with its primary key set to `id`, an id assigned by the
+    /// Nautilus Index.
+    ///
+    /// For a `#[derive(nautilus::Table)]` struct with an integer primary key,
+    /// this is `None` only if `id` doesn't fit the key's type. For other
+    /// implementations, it's always `None` unless overridden.
+    fn with_id(self, _id: u32) -> Option<Self> {
+        None
+    }
+
+    /// The primary key of the record of this type assigned `id` by the Nautilus
+    /// Index, as `primary_key()` would return it (see `with_id(..)`).
+    fn primary_key_for_id(id: u32) -> Option<Vec<u8>> {
+        Self::default().with_id(id).map(|data| data.primary_key())
+    }
+
+    /// The span (data length) of an account holding this record: the length
+    /// of the serialized data.
+    ///
+    /// This is the span rent is computed from, both on-chain and in client-side
+    /// estimates.
+    fn span(&self) -> Result<usize, ProgramError> {
+        Ok(self.try_to_vec()?.len())
+    }
+
+    /// The span of a record of this type holding `fields`, without an instance
+    /// of it: the discriminator (if it's written) plus the length of each
+    /// field's serialized value, equal to the `span()` of the record holding
+    /// those values.
+    ///
+    /// For clients that assemble a record's data piece by piece, so they can
+    /// fund its account before all of it exists as one value. For a
+    /// `#[derive(nautilus::Table)]` struct, `fields` must hold a value for
+    /// every field - except those the create path fills in itself, an
+    /// autoincrementing primary key and fields marked
+    /// `#[nautilus(authority_is_payer)]` or `#[nautilus(last_writer)]`, which
+    /// are of a fixed size - or this fails with
+    /// `NautilusError::MissingFieldValue`. A value for a field the struct
+    /// doesn't have, or that doesn't deserialize as its field's type, fails
+    /// with `NautilusError::InvalidFieldValue`. For other implementations,
+    /// which declare no fields, this fails with `ProgramError::InvalidArgument`
+    /// unless overridden.
+    fn size_for_fields(_fields: &FieldValues) -> Result<usize, ProgramError>
+    where
+        Self: Sized,
+    {
+        Err(ProgramError::InvalidArgument)
+    }
+
+    /// The smallest span of a record of this type: that of its `Default`
+    /// instance, whose strings and collections are empty and options are
+    /// `None`.
+    ///
+    /// Together with `max_span()`, this bounds the rent a record of this type
+    /// can require, for budgeting rent and sizing reserves.
+    fn min_span() -> Result<usize, ProgramError> {
+        Self::default().span()
+    }
+
+    /// The largest span of a record of this type, if it has one.
+    ///
+    /// For a `#[derive(nautilus::Table)]` struct, this is the discriminator
+    /// (if it's written) plus the sum of its fields' `MaxSpan::max_span()`, so
+    /// it's `None` as soon as any field is unbounded, such as a `String` or a
+    /// `Vec<T>`. Use bounded types like `InlineVec<T, N>` instead to keep a
+    /// maximum. For other implementations, this is `None` unless overridden.
+    fn max_span() -> Option<usize> {
+        None
+    }
+
+    /// A description of this record type's layout, hashed by `schema_hash()`.
+    ///
+    /// For a `#[derive(nautilus::Table)]` struct, this is its table name
+    /// followed by each of its fields, in order, as `name:Type` - the type as
+    /// spelled in the struct, with all whitespace removed - separated by commas
+    /// and wrapped in parentheses, such as `person(id:u32,name:String)`. For
+    /// other implementations, it's only `TABLE_NAME` unless overridden, so it
+    /// can't catch drift in their fields.
+    fn schema() -> &'static str {
+        Self::TABLE_NAME
+    }
+
+    /// The schema hash of this record type: the SHA-256 hash of `schema()`'s
+    /// UTF-8 bytes (see `nautilus::schema_hash(..)`).
+    ///
+    /// A client built against a program embeds the hashes of its record types,
+    /// and checks them against the program's `SchemaRegistry` before sending
+    /// transactions, to catch being built against a stale version of the
+    /// program's types. Since the hash covers field names and types as
+    /// spelled, renaming a field or a type alias changes it even where the
+    /// serialized layout doesn't.
+    fn schema_hash() -> [u8; 32] {
+        crate::schema_hash(Self::schema())
+    }
+
+    /// A content hash of this record: the SHA-256 hash of its serialized data,
+    /// computed the same way on-chain and by clients, so they can compare
+    /// fingerprints to detect changes without diffing fields.
+    ///
+    /// The fingerprint is only as stable as the serialization: Borsh writes
+    /// `HashMap`s and `HashSet`s sorted by key, but a custom `BorshSerialize`
+    /// implementation must also serialize any map in sorted order (as the
+    /// `NautilusIndexData` does) for equal records to share a fingerprint.
+    fn fingerprint(&self) -> Result<[u8; 32], ProgramError> {
+        Ok(solana_program::hash::hash(&self.try_to_vec()?).to_bytes())
+    }
+
+    /// The name of the table this record is counted under in the Nautilus
+    /// Index, and whose name its address is derived from.
+    ///
+    /// `TABLE_NAME` by default. A record type standing for several logical
+    /// tables - such as one per variant of an enum field - can override this to
+    /// return `nautilus::seeds::variant_table_name(Self::TABLE_NAME, ..)`, so
+    /// each variant has its own index entry and id space, with `TABLE_NAME` as
+    /// the prefix of each. Records of every variant still share `TABLE_NAME`'s
+    /// discriminator and unique fields, since they're of the same type. Count
+    /// a variant with `NautilusIndex::get_count(&data.variant_table_name())`.
+    fn variant_table_name(&self) -> String {
+        Self::TABLE_NAME.to_string()
+    }
+
+    /// The address of the record this one is a child of, if any.
+    ///
+    /// `None` by default, for a flat record. A child record's address is
+    /// derived under its parent's (see `nautilus::seeds::child_record_seeds(..)`),
+    /// so it can be found with `find_child(..)`.
+    fn parent(&self) -> Option<Pubkey> {
+        None
+    }
+
     /// The seeds used to derive the program-derived address of this account.
     ///
     /// Accessible through the account's data type since some parameters for
     /// seeds may be based on fields in the data.
     fn seeds(&self) -> Vec<Vec<u8>> {
-        vec![Self::TABLE_NAME.as_bytes().to_vec(), self.primary_key()]
+        match self.parent() {
+            Some(parent) => crate::seeds::child_record_seeds(
+                parent.as_ref(),
+                &self.variant_table_name(),
+                &self.primary_key(),
+            ),
+            None => Self::seeds_for(&self.variant_table_name(), &self.primary_key()),
+        }
+    }
+
+    /// The seeds for the record with `primary_key` in table `table_name`,
+    /// without needing an instance of the data.
+    ///
+    /// Derived by `nautilus::seeds::record_seeds(..)`, the same code clients
+    /// use, so the two always produce identical bytes.
+    fn seeds_for(table_name: &str, primary_key: &[u8]) -> Vec<Vec<u8>>
+    where
+        Self: Sized,
+    {
+        crate::seeds::record_seeds(table_name, primary_key)
     }
 
     /// Returns the program-derived address and bump for an account containing
@@ -71,6 +227,89 @@ pub trait NautilusRecordData: BorshDeserialize + BorshSerialize + Clone + Defaul
 
     /// Counts the data's declared authorities.
     fn count_authorities(&self) -> u8;
+
+    /// The fields of this data type, other than the primary key, whose values
+    /// must be unique across the table - each as its field name and the bytes
+    /// of its value.
+    ///
+    /// None by default. The values are claimed in the Nautilus Index's
+    /// secondary index when a record is created, failing with
+    /// `NautilusError::UniqueConstraintViolation` if a different record already
+    /// holds one of them.
+    fn unique_fields(&self) -> Vec<(&'static str, Vec<u8>)> {
+        vec![]
+    }
+
+    /// Validates the data before it's written to a new record.
+    ///
+    /// Does nothing by default. Override it to reject invalid field values,
+    /// for example with `assert_non_default_key(..)` or `assert_on_curve(..)`.
+    fn validate(&self) -> ProgramResult {
+        Ok(())
+    }
+}
+
+/// A record data type with a version field, for optimistic concurrency: the
+/// version is read along with the record (see `Record::load_versioned(..)`),
+/// and a write only goes through if it hasn't changed since (see
+/// `Record::write_if_version(..)`), which bumps it.
+pub trait Versioned: NautilusRecordData {
+    /// The record's current version.
+    fn version(&self) -> u64;
+
+    /// Sets the record's version.
+    fn set_version(&mut self, version: u64);
+}
+
+/// A record data type with a last-writer field, recording the key of whoever
+/// last wrote the record, for audit.
+///
+/// The last writer isn't an authority: it's never checked, and stamping it
+/// grants nothing. Marking a `Pubkey` field `#[nautilus(last_writer)]` derives
+/// this trait and stamps the field when the record is created - with the fee
+/// payer's key, or the authority's with `create_with_authority(..)` - while
+/// `Record::update_as(..)` stamps it with the key of the updating signer. Like
+/// any `Pubkey`, the field is 32 bytes, so stamping it never resizes the
+/// record.
+pub trait LastWriter: NautilusRecordData {
+    /// The key of whoever last wrote the record.
+    fn last_writer(&self) -> &Pubkey;
+
+    /// Sets the key of whoever last wrote the record.
+    fn set_last_writer(&mut self, writer: Pubkey);
+}
+
+/// A record data type keyed directly by a `Pubkey` - such as a table with one
+/// record per wallet - rather than by an autoincrementing id.
+///
+/// Its `NautilusRecordData` implementation should have `AUTO_INCREMENT` set to
+/// `false`, so the Nautilus Index is never touched, and `primary_key()` return
+/// the 32 bytes of `key_pubkey()` (which the derive macro does for a `Pubkey`
+/// primary key). The record's address then only depends on that pubkey, so it
+/// can be found with `pda_for_pubkey(..)` and loaded with
+/// `Record::find_by_pubkey(..)`.
+pub trait PubkeyKeyed: NautilusRecordData {
+    /// The pubkey this record is keyed by.
+    fn key_pubkey(&self) -> &Pubkey;
+
+    /// The seeds of the record keyed by `pubkey`.
+    fn seeds_for_pubkey(pubkey: &Pubkey) -> Vec<Vec<u8>>
+    where
+        Self: Sized,
+    {
+        Self::seeds_for(Self::TABLE_NAME, pubkey.as_ref())
+    }
+
+    /// Returns the program-derived address and bump of the record keyed by
+    /// `pubkey`.
+    fn pda_for_pubkey(program_id: &Pubkey, pubkey: &Pubkey) -> (Pubkey, u8)
+    where
+        Self: Sized,
+    {
+        let seeds_vec = Self::seeds_for_pubkey(pubkey);
+        let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+        Pubkey::find_program_address(&seeds, program_id)
+    }
 }
 
 /// This trait provides methods accessible to Nautilus Accounts (PDAs).
@@ -110,6 +349,75 @@ pub trait NautilusRecord<'a>: NautilusAccountInfo<'a> {
     /// Returns the program-derived address and bump for an account.
     fn pda(&self) -> (Pubkey, u8);
 
+    /// Returns the address of the program this record's address is derived
+    /// from.
+    fn deriver_program(&self) -> &'a Pubkey;
+
+    /// Returns the address of the program that owns the underlying account.
+    ///
+    /// This is usually the same as `deriver_program(&self)`, but the two differ
+    /// when reading a record that belongs to another program.
+    fn owner_program(&self) -> &'a Pubkey {
+        self.owner()
+    }
+
+    /// Asserts that the program owning the underlying account is the same
+    /// program its address is derived from.
+    fn assert_owner_is_deriver(&self) -> ProgramResult {
+        match self.owner_program() == self.deriver_program() {
+            true => Ok(()),
+            false => Err(NautilusError::OwnerMismatch(
+                self.key().to_string(),
+                self.owner_program().to_string(),
+                self.deriver_program().to_string(),
+            )
+            .into()),
+        }
+    }
+
+    /// Asserts that a record can be safely mutated by `program_id`, returning
+    /// the bump of its verified address.
+    ///
+    /// This is the one guard to call before any write to a record, checking -
+    /// in order, and failing with the first that doesn't hold - that:
+    ///
+    /// * the underlying account is writable, or fails with
+    ///   `NautilusError::AccountNotMutable`.
+    /// * it's owned by `program_id`, or fails with
+    ///   `NautilusError::OwnerMismatch`.
+    /// * its address is the one derived from the record's seeds under
+    ///   `program_id`, or fails with `NautilusError::AddressMismatch` - and
+    ///   with `NautilusError::TooManySeeds` if it has too many seeds to derive
+    ///   one.
+    ///
+    /// Every write `Record` makes - updating, reinterpreting, writing an
+    /// overflow chain or closing the record - runs it first.
+    fn assert_mutable(&self, program_id: &Pubkey) -> Result<u8, ProgramError> {
+        if !self.is_writable() {
+            return Err(NautilusError::AccountNotMutable(self.key().to_string()).into());
+        }
+        if self.owner() != program_id {
+            return Err(NautilusError::OwnerMismatch(
+                self.key().to_string(),
+                self.owner().to_string(),
+                program_id.to_string(),
+            )
+            .into());
+        }
+        let seeds_vec = self.seeds();
+        assert_seed_count(&seeds_vec)?;
+        let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+        let (address, bump) = Pubkey::find_program_address(&seeds, program_id);
+        match *self.key() == address {
+            true => Ok(bump),
+            false => Err(NautilusError::AddressMismatch(
+                self.key().to_string(),
+                address.to_string(),
+            )
+            .into()),
+        }
+    }
+
     /// Returns the primary key of a record.
     fn primary_key(&self) -> Vec<u8>;
 
@@ -120,10 +428,230 @@ pub trait NautilusRecord<'a>: NautilusAccountInfo<'a> {
     fn count_authorities(&self) -> u8;
 }
 
-/// Helper function to return the 8-bit discriminator of an account data type.
-fn discriminator(discrim_str: &str) -> [u8; 8] {
+/// Deserializes a field of account data, or returns the field's default if the
+/// buffer has no bytes left for it.
+///
+/// This is what fields marked `#[default_on_missing]` in the derive macros
+/// deserialize with: it lets a field be appended to an existing data type
+/// without breaking accounts written before it existed, which simply end where
+/// the new field would begin. It only works for fields added at the end - a
+/// field added in the middle, or a reordering of fields, shifts the bytes of
+/// the fields after it, so they'd be misread rather than defaulted.
+pub fn deserialize_or_default<T: BorshDeserialize + Default>(
+    buf: &mut &[u8],
+) -> Result<T, borsh::maybestd::io::Error> {
+    match buf.is_empty() {
+        true => Ok(T::default()),
+        false => T::deserialize(buf),
+    }
+}
+
+/// Whether the data types derived with `#[derive(Table)]` and
+/// `#[derive(State)]`, and the `NautilusIndex`, are serialized with their
+/// discriminator prepended.
+///
+/// This is a program-level convention, so every record and account of a
+/// program shares one on-disk format. It's on by default - the layout is the
+/// 8-byte discriminator followed by the Borsh serialization of each field, in
+/// declaration order - and deserializing then fails unless the data begins with
+/// the type's own discriminator. A program opts out by declaring its own const
+/// beside its `use nautilus::*;`, which shadows this one for the types derived
+/// there and for its entrypoint:
+///
+/// `const DISCRIMINATOR_PREFIXED: bool = false;`
+///
+/// Such a program writes and reads the bare fields, and its entrypoint loads
+/// its index with `NautilusIndex::load_with_discriminator_prefix(..)` under the
+/// same convention.
+///
+/// Migrating from unprefixed accounts: drop the program's const, and have a
+/// one-off instruction rewrite each existing account as
+/// `prefix_discriminator(..)` of its data - which grows it by 8 bytes, so the
+/// account is reallocated and its rent topped up - before it's loaded as a
+/// record again.
+pub const DISCRIMINATOR_PREFIXED: bool = true;
+
+/// The values of some of a record's fields, each serialized on its own and
+/// kept by field name, for measuring the record with
+/// `NautilusRecordData::size_for_fields(..)` before it's assembled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldValues {
+    values: std::collections::HashMap<String, Vec<u8>>,
+}
+
+impl FieldValues {
+    /// Instantiate a new, empty `FieldValues`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of the field `field_name`, replacing any it already had.
+    pub fn set<T: BorshSerialize>(
+        &mut self,
+        field_name: &str,
+        value: &T,
+    ) -> Result<&mut Self, ProgramError> {
+        Ok(self.set_serialized(field_name, value.try_to_vec()?))
+    }
+
+    /// Sets the value of the field `field_name` to the already-serialized
+    /// `bytes`, replacing any it already had.
+    pub fn set_serialized(&mut self, field_name: &str, bytes: Vec<u8>) -> &mut Self {
+        self.values.insert(field_name.to_string(), bytes);
+        self
+    }
+
+    /// The serialized value of the field `field_name`, if it has one.
+    pub fn get(&self, field_name: &str) -> Option<&[u8]> {
+        self.values.get(field_name).map(Vec::as_slice)
+    }
+
+    /// The names of the fields with a value, in no particular order.
+    pub fn field_names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+}
+
+/// The data of an account written without its discriminator, with
+/// `discriminator` prepended - its layout under `DISCRIMINATOR_PREFIXED`.
+pub fn prefix_discriminator(discriminator: [u8; 8], unprefixed: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + unprefixed.len());
+    data.extend_from_slice(&discriminator);
+    data.extend_from_slice(unprefixed);
+    data
+}
+
+/// Logs that the field `field_name` of a `type_name` was read by `accessor`,
+/// as the getters generated for fields marked `#[nautilus(audit)]` do.
+///
+/// Only logs with the `audit-logs` feature, and is compiled out otherwise.
+/// Each logged access costs the compute of base58-encoding `accessor` and of a
+/// `sol_log` syscall - on the order of a few thousand compute units - so audit
+/// only the fields that need it. Note that only accesses through the generated
+/// getters are logged: reads of the field itself, or of the account's raw
+/// bytes, are not.
+#[allow(unused_variables)]
+pub fn log_field_access(type_name: &str, field_name: &str, accessor: &Pubkey) {
+    #[cfg(feature = "audit-logs")]
+    solana_program::msg!("Audit: {}.{} read by {}", type_name, field_name, accessor);
+}
+
+/// Panics with the colliding pair if any two of `names` - the `TABLE_NAME`s
+/// (or `DISCRIMINATOR_STR`s) of a program's data types - derive the same
+/// discriminator, including a name listed twice.
+///
+/// A safety check for the discriminator prefix (see `DISCRIMINATOR_PREFIXED`):
+/// deserializing only rejects data of another type if the two types'
+/// discriminators differ, so a program can call this from a test with every
+/// name it uses to rule out a silent hash collision.
+pub fn assert_no_discriminator_collisions(names: &[&str]) {
+    let mut seen: std::collections::HashMap<[u8; 8], &str> = std::collections::HashMap::new();
+    for name in names {
+        let discriminator = discriminator(name);
+        if let Some(other) = seen.insert(discriminator, name) {
+            panic!(
+                "Discriminator collision: `{}` and `{}` both derive {:?}",
+                other, name, discriminator
+            );
+        }
+    }
+}
+
+/// Helper function to return the 8-bit discriminator of an account data type,
+/// from its `TABLE_NAME` (or `DISCRIMINATOR_STR`).
+pub fn discriminator(discrim_str: &str) -> [u8; 8] {
     let mut discriminator = [0u8; 8];
     let preimage = format!("{}:{}", "global", discrim_str);
     discriminator.copy_from_slice(&solana_program::hash::hash(preimage.as_bytes()).to_bytes()[..8]); // First 8 bytes
     discriminator
 }
+
+/// Returns the program-derived address and bump of the child record with
+/// `primary_key` in table `table_name`, under the record at `parent`.
+///
+/// `primary_key` is the key's bytes as the record's seeds hold them (see
+/// `NautilusRecordData::primary_key(..)`) - so an autoincremented `u32` id is
+/// passed as `&id.to_le_bytes()`, and a `u16` one as its two bytes.
+pub fn find_child(
+    program_id: &Pubkey,
+    parent: &Pubkey,
+    table_name: &str,
+    primary_key: &[u8],
+) -> (Pubkey, u8) {
+    let seeds_vec = crate::seeds::child_record_seeds(parent.as_ref(), table_name, primary_key);
+    let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+    Pubkey::find_program_address(&seeds, program_id)
+}
+
+/// Fails with `NautilusError::TooManySeeds` if `seeds`, together with the bump
+/// appended to them, exceed the runtime's limit of `MAX_SEEDS` seeds for a
+/// program-derived address.
+///
+/// Checked before deriving an address from composite seeds, which would
+/// otherwise fail with an opaque runtime error.
+pub fn assert_seed_count(seeds: &[Vec<u8>]) -> ProgramResult {
+    let count = seeds.len() + 1; // The bump
+    if count > MAX_SEEDS {
+        return Err(NautilusError::TooManySeeds(count).into());
+    }
+    Ok(())
+}
+
+/// Field validator that fails with `NautilusError::DefaultKey` if `key` is the
+/// default (all-zeros) Pubkey - usually a sign the field was never set.
+pub fn assert_non_default_key(field_name: &str, key: &Pubkey) -> ProgramResult {
+    if *key == Pubkey::default() {
+        return Err(NautilusError::DefaultKey(field_name.to_string()).into());
+    }
+    Ok(())
+}
+
+/// Field validator that fails with `NautilusError::KeyNotOnCurve` if `key` is
+/// not on the ed25519 curve - meaning no wallet can sign for it, as with a
+/// program-derived address.
+pub fn assert_on_curve(field_name: &str, key: &Pubkey) -> ProgramResult {
+    if !is_on_curve(key) {
+        return Err(NautilusError::KeyNotOnCurve(field_name.to_string(), key.to_string()).into());
+    }
+    Ok(())
+}
+
+/// Field validator that fails with `NautilusError::StringTooLong` if `value`
+/// is longer than `max_len` bytes, or with `NautilusError::InvalidString` if it
+/// holds control characters (such as newlines or NULs), which downstream
+/// clients often mishandle.
+///
+/// A `String` field is always valid UTF-8 - Borsh rejects invalid bytes when
+/// deserializing one - so this only bounds its contents.
+pub fn validate_string(field_name: &str, value: &str, max_len: usize) -> ProgramResult {
+    if value.len() > max_len {
+        return Err(
+            NautilusError::StringTooLong(field_name.to_string(), value.len(), max_len).into(),
+        );
+    }
+    if value.chars().any(char::is_control) {
+        return Err(NautilusError::InvalidString(field_name.to_string()).into());
+    }
+    Ok(())
+}
+
+/// Helper function to check whether a Pubkey is on the ed25519 curve.
+///
+/// `Pubkey::is_on_curve()` isn't available on-chain, so programs validate the
+/// point through the curve syscall instead.
+fn is_on_curve(key: &Pubkey) -> bool {
+    #[cfg(target_os = "solana")]
+    {
+        const CURVE25519_EDWARDS: u64 = 0;
+        let mut result = 0u8;
+        unsafe {
+            solana_program::syscalls::sol_curve_validate_point(
+                CURVE25519_EDWARDS,
+                key.as_ref().as_ptr(),
+                &mut result,
+            ) == 0
+        }
+    }
+    #[cfg(not(target_os = "solana"))]
+    key.is_on_curve()
+}
@@ -35,6 +35,13 @@ pub trait NautilusAccountInfo<'a>: Clone {
     /// `nautilus::Token` is the Mint.
     fn account_info(&self) -> Box<AccountInfo<'a>>;
 
+    /// Returns a reference to the same `AccountInfo` as `account_info(&self)`,
+    /// without cloning it into a new `Box`.
+    ///
+    /// Prefer this for reading the underlying account, and keep
+    /// `account_info(&self)` for APIs that need ownership.
+    fn account_info_ref(&self) -> &AccountInfo<'a>;
+
     /// Returns a reference to the public key representing the underlying
     /// account.
     ///
@@ -99,12 +106,22 @@ pub trait NautilusAccountInfo<'a>: Clone {
 
     /// The amount of Lamports required to pay rent for the particular data type
     /// associated with this Nautilus object.
+    ///
+    /// Defaults to `minimum_balance(self.span()?)`. Objects that support a
+    /// custom rent model (such as the `NautilusIndex`) override this, and any
+    /// path that funds or refunds rent for them respects the override.
     fn required_rent(&self) -> Result<u64, solana_program::program_error::ProgramError> {
-        use solana_program::sysvar::Sysvar;
-        Ok((solana_program::sysvar::rent::Rent::get().unwrap()).minimum_balance(self.span()?))
+        minimum_balance(self.span()?)
     }
 }
 
+/// The rent-exempt minimum balance for an account with `span` bytes of data,
+/// according to the Rent Sysvar.
+pub fn minimum_balance(span: usize) -> Result<u64, ProgramError> {
+    use solana_program::sysvar::Sysvar;
+    Ok((solana_program::sysvar::rent::Rent::get().unwrap()).minimum_balance(span))
+}
+
 /// This is a standalone trait since its really only available to system
 /// accounts or PDAs owned by your program.
 ///
@@ -1,5 +1,8 @@
 //! Traits used for marking Nautilus objects as mutable.
-use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
 
 use crate::error::NautilusError;
 
@@ -40,6 +43,10 @@ where
         self.self_account.account_info()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        self.self_account.account_info_ref()
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.self_account.key()
     }
@@ -67,6 +74,58 @@ where
     fn span(&self) -> Result<usize, ProgramError> {
         self.self_account.span()
     }
+
+    fn required_rent(&self) -> Result<u64, ProgramError> {
+        self.self_account.required_rent()
+    }
 }
 
 impl<'a, T> NautilusMut<'a> for Mut<T> where T: NautilusAccountInfo<'a> {}
+
+/// Atomically moves lamports between several accounts.
+///
+/// Each move pairs an account with the signed change to its balance: negative
+/// to debit it, positive to credit it. An account may appear in more than one
+/// move, in which case its changes are combined.
+///
+/// Every check happens before any balance changes: each account must be
+/// mutable, the moves must sum to zero, and no account may end up with a
+/// negative balance or one that overflows a `u64`. So either every move is
+/// applied, or none of them are.
+///
+/// Note: the runtime only allows a program to debit accounts it owns, which
+/// it checks once the instruction completes.
+pub fn rebalance<'a>(moves: &[(Box<AccountInfo<'a>>, i64)]) -> ProgramResult {
+    let mut balances: Vec<(&AccountInfo<'a>, i128)> = Vec::with_capacity(moves.len());
+    let mut net: i128 = 0;
+    for (account_info, delta) in moves.iter() {
+        if !account_info.is_writable {
+            return Err(NautilusError::AccountNotMutable(account_info.key.to_string()).into());
+        }
+        net += *delta as i128;
+        match balances
+            .iter_mut()
+            .find(|(existing, _)| existing.key == account_info.key)
+        {
+            Some((_, balance)) => *balance += *delta as i128,
+            None => balances.push((
+                account_info,
+                account_info.lamports() as i128 + *delta as i128,
+            )),
+        }
+    }
+    if net != 0 {
+        return Err(NautilusError::RebalanceNotZeroSum(net).into());
+    }
+    for (account_info, balance) in balances.iter() {
+        if *balance < 0 || *balance > u64::MAX as i128 {
+            return Err(
+                NautilusError::RebalanceOutOfRange(account_info.key.to_string(), *balance).into(),
+            );
+        }
+    }
+    for (account_info, balance) in balances.iter() {
+        **account_info.try_borrow_mut_lamports()? = *balance as u64;
+    }
+    Ok(())
+}
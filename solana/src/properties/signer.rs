@@ -41,6 +41,10 @@ where
         self.self_account.account_info()
     }
 
+    fn account_info_ref(&self) -> &AccountInfo<'a> {
+        self.self_account.account_info_ref()
+    }
+
     fn key(&self) -> &'a Pubkey {
         self.self_account.key()
     }
@@ -68,6 +72,10 @@ where
     fn span(&self) -> Result<usize, ProgramError> {
         self.self_account.span()
     }
+
+    fn required_rent(&self) -> Result<u64, ProgramError> {
+        self.self_account.required_rent()
+    }
 }
 
 impl<'a, T> NautilusMut<'a> for Signer<T> where T: NautilusAccountInfo<'a> {}
@@ -0,0 +1,83 @@
+//! Seed derivation for Nautilus records, shared by on-chain programs and
+//! off-chain clients.
+//!
+//! Programs derive a record's address from these seeds, and clients must derive
+//! the exact same bytes to find it. Both go through this module, which depends
+//! on nothing but `alloc`, so it compiles the same way for either side.
+use alloc::{string::String, vec, vec::Vec};
+
+/// The seeds a record's address is derived from: the table name's bytes,
+/// followed by the record's primary key.
+pub fn record_seeds(table_name: &str, primary_key: &[u8]) -> Vec<Vec<u8>> {
+    vec![table_name.as_bytes().to_vec(), primary_key.to_vec()]
+}
+
+/// The seeds a child record's address is derived from: its parent's address,
+/// followed by the table name's bytes and the record's primary key.
+///
+/// Deriving under the parent scopes a child's address to it, so each parent
+/// holds its own namespace of ids, and its children can be found from the
+/// parent's address alone. Deeper hierarchies chain: a grandchild is derived
+/// under its parent's address, not the whole path, so every level costs the
+/// same three seeds (plus the bump) out of the runtime's budget of 16.
+pub fn child_record_seeds(parent: &[u8], table_name: &str, primary_key: &[u8]) -> Vec<Vec<u8>> {
+    vec![
+        parent.to_vec(),
+        table_name.as_bytes().to_vec(),
+        primary_key.to_vec(),
+    ]
+}
+
+/// The name of the logical table for `variant` of a record type whose
+/// `TABLE_NAME` is `table_name` (see
+/// `NautilusRecordData::variant_table_name()`): the table name and the
+/// variant, joined by a `:`.
+///
+/// Note: table names are used as seeds, so the joined name must fit in 32
+/// bytes.
+pub fn variant_table_name(table_name: &str, variant: &str) -> String {
+    alloc::format!("{}:{}", table_name, variant)
+}
+
+/// The normalized form of a table name, as kept by an index in case-insensitive
+/// mode (see `NautilusIndex::with_case_insensitive_table_names(..)`): the name
+/// lowercased.
+///
+/// Records of a program in that mode derive their addresses from the
+/// normalized name, so `record_seeds(..)` must be passed the normalized name to
+/// find them.
+pub fn normalize_table_name(table_name: &str) -> String {
+    table_name.to_lowercase()
+}
+
+/// Composes a primary key from several parts, such as the fields of a record
+/// whose `primary_key()` combines them.
+///
+/// Each part is prefixed with its length (as a little-endian `u32`), so the
+/// composition is injective: `["a", "bc"]` and `["ab", "c"]` compose to
+/// different keys, and so derive different addresses. `decompose_key(..)`
+/// recovers the parts.
+///
+/// Note: a seed is at most 32 bytes, and each part adds a 4-byte prefix.
+pub fn compose_key(parts: &[&[u8]]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(parts.iter().map(|part| 4 + part.len()).sum());
+    for part in parts {
+        key.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        key.extend_from_slice(part);
+    }
+    key
+}
+
+/// The parts of a primary key composed by `compose_key(..)`, or `None` if the
+/// key isn't a valid composition.
+pub fn decompose_key(key: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut parts = vec![];
+    let mut rest = key;
+    while !rest.is_empty() {
+        let len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let part = rest.get(4..4 + len)?;
+        parts.push(part.to_vec());
+        rest = &rest[4 + len..];
+    }
+    Some(parts)
+}
@@ -0,0 +1,166 @@
+//! The `Decimal<SCALE>` type and all associated trait implementations.
+use std::{fmt, str::FromStr};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::program_error::ProgramError;
+
+use crate::error::NautilusError;
+
+/// A fixed-point decimal number with `SCALE` digits after the decimal point,
+/// for exact money math in records without floats.
+///
+/// A `Decimal` is kept as an integer mantissa in units of `10^-SCALE`, so
+/// `Decimal::<2>::new(1234)` is `12.34`. It's serialized as just that `i128`
+/// mantissa, so equal values always serialize to the same 16 bytes, and its
+/// span never changes. The scale is part of the type rather than the data:
+/// only decimals of the same scale can be combined, and changing a field's
+/// scale misreads the records already written with the old one. `SCALE` can
+/// be at most 38, the most digits an `i128` holds.
+///
+/// Arithmetic is checked, failing with `NautilusError::ArithmeticOverflow`
+/// rather than wrapping. Results with more digits than the scale holds - the
+/// product of two decimals, or a parsed string with more fractional digits -
+/// are rounded half away from zero.
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+pub struct Decimal<const SCALE: u32>(pub i128);
+
+impl<const SCALE: u32> Decimal<SCALE> {
+    /// Instantiate a new `Decimal` from its mantissa, in units of `10^-SCALE`.
+    pub const fn new(mantissa: i128) -> Self {
+        Self(mantissa)
+    }
+
+    /// The decimal's mantissa, in units of `10^-SCALE`.
+    pub const fn mantissa(&self) -> i128 {
+        self.0
+    }
+
+    /// The decimal holding the whole number `value`.
+    pub fn from_integer(value: i128) -> Result<Self, ProgramError> {
+        Ok(Self(value.checked_mul(Self::unit()?).ok_or_else(overflow)?))
+    }
+
+    /// The mantissa of one: `10^SCALE`.
+    fn unit() -> Result<i128, ProgramError> {
+        10i128.checked_pow(SCALE).ok_or_else(overflow)
+    }
+
+    /// Adds `rhs`, failing with `NautilusError::ArithmeticOverflow` on
+    /// overflow.
+    pub fn checked_add(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(self.0.checked_add(rhs.0).ok_or_else(overflow)?))
+    }
+
+    /// Subtracts `rhs`, failing with `NautilusError::ArithmeticOverflow` on
+    /// overflow.
+    pub fn checked_sub(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(self.0.checked_sub(rhs.0).ok_or_else(overflow)?))
+    }
+
+    /// Multiplies by `rhs`, rounding the product half away from zero to
+    /// `SCALE` digits.
+    ///
+    /// Fails with `NautilusError::ArithmeticOverflow` if the product of the
+    /// two mantissas overflows an `i128`, even where the rounded result would
+    /// fit.
+    pub fn checked_mul(self, rhs: Self) -> Result<Self, ProgramError> {
+        let product = self.0.checked_mul(rhs.0).ok_or_else(overflow)?;
+        let unit = Self::unit()?;
+        let quotient = product / unit;
+        match (product % unit).unsigned_abs() * 2 >= unit.unsigned_abs() {
+            true => Ok(Self(quotient + product.signum())),
+            false => Ok(Self(quotient)),
+        }
+    }
+}
+
+/// The error of an overflowed operation.
+fn overflow() -> ProgramError {
+    NautilusError::ArithmeticOverflow.into()
+}
+
+/// Formats the decimal with exactly `SCALE` fractional digits, such as `-0.50`
+/// for `Decimal::<2>::new(-50)`.
+impl<const SCALE: u32> fmt::Display for Decimal<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scale = SCALE as usize;
+        let digits = format!("{:0>width$}", self.0.unsigned_abs(), width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        let sign = match self.0 < 0 {
+            true => "-",
+            false => "",
+        };
+        match scale {
+            0 => write!(f, "{}{}", sign, integer),
+            _ => write!(f, "{}{}.{}", sign, integer, fraction),
+        }
+    }
+}
+
+/// Parses a decimal from an optionally signed string of digits with at most
+/// one decimal point, such as `12.34`, `-0.5` or `.5` - rounding any digits
+/// past `SCALE` half away from zero.
+///
+/// Fails with `NautilusError::InvalidDecimal` if the string isn't a decimal
+/// number, and with `NautilusError::ArithmeticOverflow` if it's out of range.
+impl<const SCALE: u32> FromStr for Decimal<SCALE> {
+    type Err = ProgramError;
+
+    fn from_str(string: &str) -> Result<Self, ProgramError> {
+        let invalid = || ProgramError::from(NautilusError::InvalidDecimal(string.to_string()));
+        let (negative, unsigned) = match string.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, string.strip_prefix('+').unwrap_or(string)),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if (integer.is_empty() && fraction.is_empty())
+            || !integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|byte| byte.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        Self::unit()?;
+
+        let digits = integer.bytes().chain(
+            fraction
+                .bytes()
+                .chain(std::iter::repeat(b'0'))
+                .take(SCALE as usize),
+        );
+        let mut magnitude: u128 = 0;
+        for digit in digits {
+            magnitude = magnitude
+                .checked_mul(10)
+                .and_then(|magnitude| magnitude.checked_add((digit - b'0') as u128))
+                .ok_or_else(overflow)?;
+        }
+        if fraction
+            .as_bytes()
+            .get(SCALE as usize)
+            .map_or(false, |digit| *digit >= b'5')
+        {
+            magnitude = magnitude.checked_add(1).ok_or_else(overflow)?;
+        }
+
+        let mantissa = match negative {
+            false => i128::try_from(magnitude).ok(),
+            true if magnitude == i128::MIN.unsigned_abs() => Some(i128::MIN),
+            true => i128::try_from(magnitude).ok().map(|magnitude| -magnitude),
+        };
+        mantissa.map(Self).ok_or_else(overflow)
+    }
+}
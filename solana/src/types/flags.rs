@@ -0,0 +1,59 @@
+//! The `NautilusFlags` type and all associated trait implementations.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::entrypoint::ProgramResult;
+
+use crate::error::NautilusError;
+
+/// A bitfield of up to 64 per-record feature flags, such as a beta behavior
+/// enabled only for some accounts.
+///
+/// Name each flag with a constant of the bit it's kept in, built with
+/// `NautilusFlags::flag(..)`:
+/// `const BETA: u64 = NautilusFlags::flag(0);`. A flag can also be several bits
+/// combined, in which case it's only set if all of them are.
+///
+/// Always serialized as a single `u64`, so toggling flags never changes a
+/// record's span, and never reallocates it.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NautilusFlags(pub u64);
+
+impl NautilusFlags {
+    /// The flag kept in bit `bit` (from 0 to 63).
+    pub const fn flag(bit: u8) -> u64 {
+        1 << bit
+    }
+
+    /// Instantiate a new `NautilusFlags` with the given bits set.
+    pub const fn new(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// The raw bits of every flag.
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Whether `flag` is set.
+    pub const fn has_flag(&self, flag: u64) -> bool {
+        self.0 & flag == flag
+    }
+
+    /// Sets `flag`.
+    pub fn set_flag(&mut self, flag: u64) {
+        self.0 |= flag;
+    }
+
+    /// Clears `flag`.
+    pub fn clear_flag(&mut self, flag: u64) {
+        self.0 &= !flag;
+    }
+
+    /// Gates an operation on `flag`, failing with `NautilusError::FlagNotSet`
+    /// unless it's set.
+    pub fn require_flag(&self, flag: u64) -> ProgramResult {
+        if !self.has_flag(flag) {
+            return Err(NautilusError::FlagNotSet(flag).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+//! The `InlineVec<T, N>` type and all associated trait implementations.
+use borsh::{
+    maybestd::io::{Error, ErrorKind, Write},
+    BorshDeserialize, BorshSerialize,
+};
+use solana_program::program_error::ProgramError;
+
+use crate::error::NautilusError;
+
+/// A bounded collection of at most `N` elements, stored inline in a record's
+/// data rather than as `N` separate records.
+///
+/// This suits small collections with a known maximum - such as "a user's up to
+/// 8 roles" - where creating a PDA for each element would be wasteful.
+///
+/// On-chain, an `InlineVec` is serialized as a `u32` length followed by exactly
+/// `N` elements, where unused slots are filled with `T::default()`. This means
+/// its span never changes as elements are pushed or removed (as long as `T`
+/// itself is fixed-size), so a record containing one never needs a realloc.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlineVec<T, const N: usize> {
+    items: Vec<T>,
+}
+
+impl<T, const N: usize> InlineVec<T, N> {
+    /// Instantiate a new, empty `InlineVec`.
+    pub fn new() -> Self {
+        Self {
+            items: Vec::with_capacity(N),
+        }
+    }
+
+    /// The maximum number of elements this `InlineVec` can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of elements currently in this `InlineVec`.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether or not this `InlineVec` has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Whether or not this `InlineVec` has no free slots left.
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= N
+    }
+
+    /// Appends an element, returning `NautilusError::InlineVecFull` if all `N`
+    /// slots are already taken.
+    pub fn push(&mut self, item: T) -> Result<(), ProgramError> {
+        if self.is_full() {
+            return Err(NautilusError::InlineVecFull(N).into());
+        }
+        self.items.push(item);
+        Ok(())
+    }
+
+    /// Removes and returns the element at `index`, shifting all elements after
+    /// it to the left. Returns `None` if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        match index < self.items.len() {
+            true => Some(self.items.remove(index)),
+            false => None,
+        }
+    }
+
+    /// Returns a reference to the element at `index`, if there is one.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)
+    }
+
+    /// Returns the elements as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Returns an iterator over the elements.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+}
+
+impl<T, const N: usize> Default for InlineVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> TryFrom<Vec<T>> for InlineVec<T, N> {
+    type Error = ProgramError;
+
+    fn try_from(items: Vec<T>) -> Result<Self, Self::Error> {
+        match items.len() <= N {
+            true => Ok(Self { items }),
+            false => Err(NautilusError::InlineVecFull(N).into()),
+        }
+    }
+}
+
+impl<T, const N: usize> BorshSerialize for InlineVec<T, N>
+where
+    T: BorshSerialize + Default,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        (self.items.len() as u32).serialize(writer)?;
+        for item in self.items.iter() {
+            item.serialize(writer)?;
+        }
+        for _ in self.items.len()..N {
+            T::default().serialize(writer)?; // Pad unused slots to keep the span fixed
+        }
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> BorshDeserialize for InlineVec<T, N>
+where
+    T: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, Error> {
+        let len = u32::deserialize(buf)? as usize;
+        if len > N {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("InlineVec length {} exceeds its capacity of {}", len, N),
+            ));
+        }
+        let mut items = Vec::with_capacity(N);
+        for i in 0..N {
+            let item = T::deserialize(buf)?;
+            if i < len {
+                items.push(item);
+            }
+        }
+        Ok(Self { items })
+    }
+}
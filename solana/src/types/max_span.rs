@@ -0,0 +1,146 @@
+//! The `MaxSpan` trait, bounding the serialized size of record field types.
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::{Decimal, InlineVec, NautilusFlags, RentContributions, ShortVec, SortedMap};
+
+/// The largest number of bytes a value of a type can serialize to, if the type
+/// has one.
+///
+/// Fixed-size types, such as integers and `Pubkey`s, and types with a bounded
+/// number of elements, such as `InlineVec<T, N>`, have a maximum span. Types
+/// that can grow without bound - `String`, `Vec<T>`, and maps and sets - have
+/// none, and neither does any type holding one.
+///
+/// Every field of a struct deriving `#[derive(nautilus::Table)]` must
+/// implement this trait, since it drives the derived
+/// `NautilusRecordData::max_span()`. A custom field type that can't be bounded
+/// simply returns `None`.
+pub trait MaxSpan {
+    /// The largest number of bytes a value of this type can serialize to, or
+    /// `None` if it's unbounded.
+    fn max_span() -> Option<usize>;
+}
+
+macro_rules! impl_fixed_max_span {
+    ($($ty:ty),*) => {
+        $(
+            impl MaxSpan for $ty {
+                fn max_span() -> Option<usize> {
+                    Some(std::mem::size_of::<$ty>())
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_max_span!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    f32,
+    f64,
+    bool,
+    ()
+);
+
+impl MaxSpan for Pubkey {
+    fn max_span() -> Option<usize> {
+        Some(32)
+    }
+}
+
+impl<const SCALE: u32> MaxSpan for Decimal<SCALE> {
+    fn max_span() -> Option<usize> {
+        i128::max_span()
+    }
+}
+
+impl MaxSpan for NautilusFlags {
+    fn max_span() -> Option<usize> {
+        u64::max_span()
+    }
+}
+
+impl<T: MaxSpan, const N: usize> MaxSpan for [T; N] {
+    fn max_span() -> Option<usize> {
+        T::max_span()?.checked_mul(N)
+    }
+}
+
+impl<T: MaxSpan> MaxSpan for Option<T> {
+    fn max_span() -> Option<usize> {
+        T::max_span()?.checked_add(1)
+    }
+}
+
+impl<T: MaxSpan> MaxSpan for Box<T> {
+    fn max_span() -> Option<usize> {
+        T::max_span()
+    }
+}
+
+impl<A: MaxSpan, B: MaxSpan> MaxSpan for (A, B) {
+    fn max_span() -> Option<usize> {
+        A::max_span()?.checked_add(B::max_span()?)
+    }
+}
+
+impl<A: MaxSpan, B: MaxSpan, C: MaxSpan> MaxSpan for (A, B, C) {
+    fn max_span() -> Option<usize> {
+        <(A, B)>::max_span()?.checked_add(C::max_span()?)
+    }
+}
+
+/// A `u32` length followed by exactly `N` elements.
+impl<T: MaxSpan, const N: usize> MaxSpan for InlineVec<T, N> {
+    fn max_span() -> Option<usize> {
+        <[T; N]>::max_span()?.checked_add(4)
+    }
+}
+
+/// A `u16` length followed by at most `ShortVec::MAX_LEN` elements.
+impl<T: MaxSpan> MaxSpan for ShortVec<T> {
+    fn max_span() -> Option<usize> {
+        T::max_span()?
+            .checked_mul(ShortVec::<T>::MAX_LEN)?
+            .checked_add(2)
+    }
+}
+
+macro_rules! impl_unbounded_max_span {
+    ($($ty:ty => [$($param:ident),*]),*) => {
+        $(
+            impl<$($param),*> MaxSpan for $ty {
+                fn max_span() -> Option<usize> {
+                    None
+                }
+            }
+        )*
+    };
+}
+
+impl_unbounded_max_span!(
+    String => [],
+    RentContributions => [],
+    Vec<T> => [T],
+    HashSet<T> => [T],
+    HashMap<K, V> => [K, V]
+);
+
+impl<K: Eq + Hash, V> MaxSpan for SortedMap<K, V> {
+    fn max_span() -> Option<usize> {
+        None
+    }
+}
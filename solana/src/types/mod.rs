@@ -0,0 +1,10 @@
+//! Submodule containing data types that can be used as fields of Nautilus
+//! records and accounts.
+pub mod decimal;
+pub mod flags;
+pub mod inline_vec;
+pub mod max_span;
+pub mod rent_contributions;
+pub mod short_vec;
+pub mod sorted_map;
+pub mod view;
@@ -0,0 +1,78 @@
+//! The `RentContributions` type and all associated trait implementations.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// A record of how much each payer has contributed to an account's rent, for
+/// records funded by several parties - such as shared escrows - to be refunded
+/// fairly when they're closed.
+///
+/// This is opt-in: a record tracks its contributions by holding a
+/// `RentContributions` field, recording each payer's share with
+/// `record_contribution(..)` whenever it's funded, and splitting its balance on
+/// close with `refund_plan(..)`.
+///
+/// Space cost: the contributions are serialized as a `u32` length followed by
+/// 40 bytes (a `Pubkey` and a `u64`) per distinct payer, which itself adds to
+/// the record's rent - and grows the record the first time a new payer
+/// contributes.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RentContributions {
+    contributions: Vec<(Pubkey, u64)>,
+}
+
+impl RentContributions {
+    /// Instantiate a new, empty `RentContributions`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `amount` lamports contributed by `payer`, adding to any amount
+    /// it has contributed before.
+    pub fn record_contribution(&mut self, payer: Pubkey, amount: u64) {
+        match self.contributions.iter_mut().find(|(key, _)| *key == payer) {
+            Some((_, total)) => *total = total.saturating_add(amount),
+            None => self.contributions.push((payer, amount)),
+        }
+    }
+
+    /// The amount `payer` has contributed in total.
+    pub fn contribution(&self, payer: &Pubkey) -> u64 {
+        self.contributions
+            .iter()
+            .find(|(key, _)| key == payer)
+            .map_or(0, |(_, amount)| *amount)
+    }
+
+    /// The amount contributed by all payers.
+    pub fn total(&self) -> u64 {
+        self.contributions
+            .iter()
+            .fold(0, |total, (_, amount)| total.saturating_add(*amount))
+    }
+
+    /// Each payer's contribution, in the order they first contributed.
+    pub fn as_slice(&self) -> &[(Pubkey, u64)] {
+        &self.contributions
+    }
+
+    /// Splits `balance` - typically the lamports left in the account on close
+    /// - between the payers in proportion to their contributions.
+    ///
+    /// The shares always sum to `balance`: lamports left over from rounding
+    /// down go to the first payer. Returns no refunds if nothing was
+    /// contributed.
+    pub fn refund_plan(&self, balance: u64) -> Vec<(Pubkey, u64)> {
+        let total = self.total() as u128;
+        if total == 0 {
+            return vec![];
+        }
+        let mut plan: Vec<(Pubkey, u64)> = self
+            .contributions
+            .iter()
+            .map(|(payer, amount)| (*payer, (balance as u128 * *amount as u128 / total) as u64))
+            .collect();
+        let refunded: u64 = plan.iter().map(|(_, share)| share).sum();
+        plan[0].1 += balance - refunded;
+        plan
+    }
+}
@@ -0,0 +1,128 @@
+//! The `ShortVec<T>` type and all associated trait implementations.
+use borsh::{
+    maybestd::io::{Error, ErrorKind, Write},
+    BorshDeserialize, BorshSerialize,
+};
+use solana_program::program_error::ProgramError;
+
+use crate::error::NautilusError;
+
+/// A collection of at most `u16::MAX` elements, serialized with a `u16` length
+/// prefix rather than borsh's standard `u32`.
+///
+/// This saves 2 bytes per collection in space-constrained records, for
+/// collections known to stay short.
+///
+/// Tradeoff versus standard borsh: a `ShortVec` isn't laid out like a `Vec`,
+/// so clients must decode its prefix as a `u16`, and a field can't switch
+/// between the two without migrating existing accounts. Pushing past
+/// `u16::MAX` elements fails with `NautilusError::LengthPrefixOverflow`, and
+/// serializing such a collection fails rather than truncating its prefix.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShortVec<T> {
+    items: Vec<T>,
+}
+
+impl<T> ShortVec<T> {
+    /// The maximum number of elements a `ShortVec` can hold.
+    pub const MAX_LEN: usize = u16::MAX as usize;
+
+    /// Instantiate a new, empty `ShortVec`.
+    pub fn new() -> Self {
+        Self { items: vec![] }
+    }
+
+    /// The number of elements currently in this `ShortVec`.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether or not this `ShortVec` has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Appends an element, returning `NautilusError::LengthPrefixOverflow` if
+    /// the `ShortVec` already holds `MAX_LEN` elements.
+    pub fn push(&mut self, item: T) -> Result<(), ProgramError> {
+        if self.items.len() >= Self::MAX_LEN {
+            return Err(
+                NautilusError::LengthPrefixOverflow(self.items.len() + 1, Self::MAX_LEN).into(),
+            );
+        }
+        self.items.push(item);
+        Ok(())
+    }
+
+    /// Removes and returns the element at `index`, shifting all elements after
+    /// it to the left. Returns `None` if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        match index < self.items.len() {
+            true => Some(self.items.remove(index)),
+            false => None,
+        }
+    }
+
+    /// Returns a reference to the element at `index`, if there is one.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)
+    }
+
+    /// Returns the elements as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Returns an iterator over the elements.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+}
+
+impl<T> TryFrom<Vec<T>> for ShortVec<T> {
+    type Error = ProgramError;
+
+    fn try_from(items: Vec<T>) -> Result<Self, Self::Error> {
+        match items.len() <= Self::MAX_LEN {
+            true => Ok(Self { items }),
+            false => Err(NautilusError::LengthPrefixOverflow(items.len(), Self::MAX_LEN).into()),
+        }
+    }
+}
+
+impl<T> BorshSerialize for ShortVec<T>
+where
+    T: BorshSerialize,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let len: u16 = self.items.len().try_into().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "ShortVec length {} exceeds the maximum of {}",
+                    self.items.len(),
+                    Self::MAX_LEN
+                ),
+            )
+        })?;
+        len.serialize(writer)?;
+        for item in self.items.iter() {
+            item.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> BorshDeserialize for ShortVec<T>
+where
+    T: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, Error> {
+        let len = u16::deserialize(buf)? as usize;
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(T::deserialize(buf)?);
+        }
+        Ok(Self { items })
+    }
+}
@@ -0,0 +1,157 @@
+//! The `SortedMap<K, V>` type and all associated trait implementations.
+use std::{collections::HashMap, hash::Hash};
+
+use borsh::{
+    maybestd::io::{Error, ErrorKind, Write},
+    BorshDeserialize, BorshSerialize,
+};
+
+/// A hash map that always serializes its entries sorted by key, so equal maps
+/// produce the same bytes however their entries were inserted.
+///
+/// Use it for map fields of records, whose bytes must be stable for
+/// fingerprints (see `NautilusRecordData::fingerprint()`) and reproducible
+/// tests. It's laid out like borsh's own map encoding - a `u32` length followed
+/// by each key and value - so it can replace a `HashMap` field without
+/// migrating existing accounts. But where that encoding's order is left to the
+/// borsh version, and breaks down for keys without a total order, a
+/// `SortedMap` requires `Ord` keys, and only deserializes data in its one
+/// canonical form: keys strictly ascending, with no duplicates.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SortedMap<K, V>
+where
+    K: Eq + Hash,
+{
+    entries: HashMap<K, V>,
+}
+
+impl<K, V> SortedMap<K, V>
+where
+    K: Eq + Hash + Ord,
+{
+    /// Instantiate a new, empty `SortedMap`.
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// The number of entries in this `SortedMap`.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether or not this `SortedMap` has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inserts a value under `key`, returning the value it replaces, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.entries.insert(key, value)
+    }
+
+    /// Returns a reference to the value under `key`, if there is one.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    /// Returns a mutable reference to the value under `key`, if there is one.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.entries.get_mut(key)
+    }
+
+    /// Removes and returns the value under `key`, if there is one.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.entries.remove(key)
+    }
+
+    /// Whether or not there's a value under `key`.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the entries sorted by key, in the order they're serialized.
+    pub fn sorted_entries(&self) -> Vec<(&K, &V)> {
+        let mut entries: Vec<(&K, &V)> = self.entries.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        entries
+    }
+
+    /// Returns the entries as a `HashMap`.
+    pub fn as_map(&self) -> &HashMap<K, V> {
+        &self.entries
+    }
+}
+
+impl<K, V> From<HashMap<K, V>> for SortedMap<K, V>
+where
+    K: Eq + Hash,
+{
+    fn from(entries: HashMap<K, V>) -> Self {
+        Self { entries }
+    }
+}
+
+/// Serializes `entries` as a map in borsh's map encoding, sorted by key: a
+/// `u32` length followed by each key and value.
+///
+/// This is how a `SortedMap` is serialized, and is meant for custom
+/// serializers of data holding maps (such as the `NautilusIndexData`'s), so
+/// their bytes don't depend on the order of a map's entries.
+pub fn sorted_map_serialize<K, V, W>(
+    entries: impl IntoIterator<Item = (K, V)>,
+    writer: &mut W,
+) -> Result<(), Error>
+where
+    K: BorshSerialize + Ord,
+    V: BorshSerialize,
+    W: Write,
+{
+    let mut entries: Vec<(K, V)> = entries.into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    u32::try_from(entries.len())
+        .map_err(|_| Error::from(ErrorKind::InvalidInput))?
+        .serialize(writer)?;
+    for (key, value) in entries {
+        key.serialize(writer)?;
+        value.serialize(writer)?;
+    }
+    Ok(())
+}
+
+impl<K, V> BorshSerialize for SortedMap<K, V>
+where
+    K: BorshSerialize + Eq + Hash + Ord,
+    V: BorshSerialize,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        sorted_map_serialize(self.entries.iter(), writer)
+    }
+}
+
+impl<K, V> BorshDeserialize for SortedMap<K, V>
+where
+    K: BorshDeserialize + Eq + Hash + Ord,
+    V: BorshDeserialize,
+{
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, Error> {
+        let len = u32::deserialize(buf)? as usize;
+        let mut keys: Vec<K> = Vec::with_capacity(len);
+        let mut values: Vec<V> = Vec::with_capacity(len);
+        for _ in 0..len {
+            let key = K::deserialize(buf)?;
+            if keys.last().map_or(false, |last| *last >= key) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "SortedMap keys are not strictly ascending",
+                ));
+            }
+            keys.push(key);
+            values.push(V::deserialize(buf)?);
+        }
+        Ok(Self {
+            entries: keys.into_iter().zip(values).collect(),
+        })
+    }
+}
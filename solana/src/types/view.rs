@@ -0,0 +1,282 @@
+//! The `ViewField` trait, for reading the fields of a record lazily from its
+//! borrowed account data.
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use borsh::{
+    maybestd::io::{Error, ErrorKind},
+    BorshDeserialize,
+};
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    CountDeltaRing, Decimal, IdStride, InlineVec, NautilusFlags, RentContributions, ShortVec,
+    SortedMap,
+};
+
+/// A type that can be read from - or skipped over in - serialized account data
+/// without deserializing the fields around it.
+///
+/// This drives the borrowed views generated by `#[derive(nautilus::Table)]`: a
+/// `<T>View<'v>` holds a record's account data, and each of its accessors skips
+/// the fields ahead of its own and reads only that one. Every field of a derived
+/// struct must implement this trait.
+///
+/// Field types supporting lazy borrowed access:
+/// - `String` is read as a `&'v str` borrowed from the account data, without
+///   copying it.
+/// - Fixed-size types - integers, `bool`, `Pubkey`, `NautilusFlags`,
+///   `Decimal`, and arrays, options and tuples of those - are read as owned
+///   copies, and are skipped without being read at all.
+/// - `Vec<T>`, `InlineVec<T, N>` and `ShortVec<T>` are read as owned copies,
+///   and skipped element by element - so skipping them only costs their
+///   length prefix if their elements are also fixed-size.
+/// - Maps, sets and `RentContributions` are read as owned copies, and are
+///   deserialized even to be skipped, so they save nothing when placed ahead
+///   of the fields an instruction reads.
+pub trait ViewField<'v>: Sized {
+    /// What reading the field yields.
+    type Output;
+
+    /// Reads the field from the front of `buf`, advancing it past the field.
+    fn view(buf: &mut &'v [u8]) -> Result<Self::Output, Error>;
+
+    /// Advances `buf` past the field without reading it.
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error>;
+}
+
+/// Reads a field with `ViewField::view(..)`, or returns the default if the
+/// buffer has no bytes left for it - the view counterpart of
+/// `deserialize_or_default(..)`, for fields marked `#[default_on_missing]`.
+pub fn view_or_default<'v, T>(buf: &mut &'v [u8]) -> Result<T::Output, Error>
+where
+    T: ViewField<'v>,
+    T::Output: Default,
+{
+    match buf.is_empty() {
+        true => Ok(T::Output::default()),
+        false => T::view(buf),
+    }
+}
+
+/// Skips a field with `ViewField::skip(..)`, unless the buffer has no bytes
+/// left for it, for fields marked `#[default_on_missing]`.
+pub fn skip_or_default<'v, T: ViewField<'v>>(buf: &mut &'v [u8]) -> Result<(), Error> {
+    match buf.is_empty() {
+        true => Ok(()),
+        false => T::skip(buf),
+    }
+}
+
+/// Advances `buf` past `len` bytes.
+fn skip_bytes(buf: &mut &[u8], len: usize) -> Result<(), Error> {
+    if buf.len() < len {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Unexpected length of input",
+        ));
+    }
+    *buf = &buf[len..];
+    Ok(())
+}
+
+macro_rules! impl_fixed_view_field {
+    ($($ty:ty => $len:expr),*) => {
+        $(
+            impl<'v> ViewField<'v> for $ty {
+                type Output = Self;
+
+                fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+                    Self::deserialize(buf)
+                }
+
+                fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+                    skip_bytes(buf, $len)
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_view_field!(
+    u8 => 1, u16 => 2, u32 => 4, u64 => 8, u128 => 16,
+    i8 => 1, i16 => 2, i32 => 4, i64 => 8, i128 => 16,
+    f32 => 4, f64 => 8, bool => 1, Pubkey => 32, NautilusFlags => 8, IdStride => 8
+);
+
+impl<'v, const SCALE: u32> ViewField<'v> for Decimal<SCALE> {
+    type Output = Self;
+
+    fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+        Self::deserialize(buf)
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        skip_bytes(buf, 16)
+    }
+}
+
+impl<'v> ViewField<'v> for String {
+    type Output = &'v str;
+
+    fn view(buf: &mut &'v [u8]) -> Result<&'v str, Error> {
+        let len = u32::deserialize(buf)? as usize;
+        let data: &'v [u8] = buf;
+        skip_bytes(buf, len)?;
+        std::str::from_utf8(&data[..len]).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        let len = u32::deserialize(buf)? as usize;
+        skip_bytes(buf, len)
+    }
+}
+
+impl<'v, T> ViewField<'v> for Option<T>
+where
+    T: ViewField<'v> + BorshDeserialize,
+{
+    type Output = Self;
+
+    fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+        Self::deserialize(buf)
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        match u8::deserialize(buf)? {
+            0 => Ok(()),
+            _ => T::skip(buf),
+        }
+    }
+}
+
+impl<'v, T, const N: usize> ViewField<'v> for [T; N]
+where
+    T: ViewField<'v> + BorshDeserialize + Default + Copy,
+{
+    type Output = Self;
+
+    fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+        let mut items = [T::default(); N];
+        for item in items.iter_mut() {
+            *item = T::deserialize(buf)?;
+        }
+        Ok(items)
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        (0..N).try_for_each(|_| T::skip(buf))
+    }
+}
+
+impl<'v, A, B> ViewField<'v> for (A, B)
+where
+    A: ViewField<'v> + BorshDeserialize + Default,
+    B: ViewField<'v> + BorshDeserialize + Default,
+{
+    type Output = Self;
+
+    fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+        Self::deserialize(buf)
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        A::skip(buf)?;
+        B::skip(buf)
+    }
+}
+
+impl<'v, T> ViewField<'v> for Vec<T>
+where
+    T: ViewField<'v> + BorshDeserialize,
+{
+    type Output = Self;
+
+    fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+        Self::deserialize(buf)
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        let len = u32::deserialize(buf)?;
+        (0..len).try_for_each(|_| T::skip(buf))
+    }
+}
+
+/// A `u32` length followed by exactly `N` elements.
+impl<'v, T, const N: usize> ViewField<'v> for InlineVec<T, N>
+where
+    T: ViewField<'v> + BorshDeserialize + Default,
+{
+    type Output = Self;
+
+    fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+        Self::deserialize(buf)
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        skip_bytes(buf, 4)?;
+        (0..N).try_for_each(|_| T::skip(buf))
+    }
+}
+
+impl<'v, T> ViewField<'v> for ShortVec<T>
+where
+    T: ViewField<'v> + BorshDeserialize + Default,
+{
+    type Output = Self;
+
+    fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+        Self::deserialize(buf)
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        let len = u16::deserialize(buf)?;
+        (0..len).try_for_each(|_| T::skip(buf))
+    }
+}
+
+macro_rules! impl_deserialized_view_field {
+    ($($ty:ty => [$($param:ident),*]),*) => {
+        $(
+            impl<'v, $($param),*> ViewField<'v> for $ty
+            where
+                $ty: BorshDeserialize + Default,
+            {
+                type Output = Self;
+
+                fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+                    Self::deserialize(buf)
+                }
+
+                fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+                    Self::deserialize(buf).map(|_| ())
+                }
+            }
+        )*
+    };
+}
+
+impl_deserialized_view_field!(
+    RentContributions => [],
+    CountDeltaRing => [],
+    HashSet<T> => [T],
+    HashMap<K, V> => [K, V]
+);
+
+impl<'v, K, V> ViewField<'v> for SortedMap<K, V>
+where
+    K: Eq + Hash,
+    SortedMap<K, V>: BorshDeserialize + Default,
+{
+    type Output = Self;
+
+    fn view(buf: &mut &'v [u8]) -> Result<Self, Error> {
+        Self::deserialize(buf)
+    }
+
+    fn skip(buf: &mut &'v [u8]) -> Result<(), Error> {
+        Self::deserialize(buf).map(|_| ())
+    }
+}
@@ -128,7 +128,7 @@ impl NautilusEntrypointEnumVariant {
                     RequiredAccountSubtype::MintAuthority => mint_authority_ident(&r.ident),
                 },
                 RequiredAccountType::IndexAccount => {
-                    index_init = quote! { let nautilus_index = NautilusIndex::load(program_id, index_pointer)?; }; // TODO
+                    index_init = quote! { let nautilus_index = NautilusIndex::load_with_discriminator_prefix(program_id, index_pointer, DISCRIMINATOR_PREFIXED)?; }; // TODO
                     r.ident.clone()
                 }
                 _ => r.ident.clone(),
@@ -169,6 +169,15 @@ impl NautilusEntrypointEnumVariant {
                                     )
                                 },
                             };
+                            // The Nautilus Index follows the program's `DISCRIMINATOR_PREFIXED`, like its records.
+                            let (new_fn, load_fn, prefix_arg) = match obj_type == "NautilusIndex" {
+                                true => (
+                                    quote! { new_with_discriminator_prefix },
+                                    quote! { load_with_discriminator_prefix },
+                                    quote! { DISCRIMINATOR_PREFIXED, },
+                                ),
+                                false => (quote! { new }, quote! { load }, quote!()),
+                            };
                             let required_accounts_for_obj = obj.get_required_accounts();
                             // Identifiers for all accounts required "for read" - in other words, any `Box<AccountInfo<'_>>` fields required
                             // for that Nautilus object.
@@ -194,7 +203,7 @@ impl NautilusEntrypointEnumVariant {
                                         false => quote! {
                                             let mut #arg_ident = Create::new(
                                                 #(#create_call_idents,)*
-                                                #obj_type::new(#(#read_call_idents,)*)
+                                                #obj_type::#new_fn(#(#read_call_idents,)* #prefix_arg)
                                             )?;
                                         },
                                     };
@@ -203,16 +212,16 @@ impl NautilusEntrypointEnumVariant {
                                 None => {
                                     if config.is_signer {
                                         object_inits.push(
-                                            quote! { let #arg_ident = Signer::new(#obj_type::load(#(#read_call_idents,)*)?)?; },
+                                            quote! { let #arg_ident = Signer::new(#obj_type::#load_fn(#(#read_call_idents,)* #prefix_arg)?)?; },
                                         );
                                     } else if config.is_mut {
                                         object_inits.push(
-                                            quote! { let #arg_ident = Mut::new(#obj_type::load(#(#read_call_idents,)*)?)?; },
+                                            quote! { let #arg_ident = Mut::new(#obj_type::#load_fn(#(#read_call_idents,)* #prefix_arg)?)?; },
                                         );
                                     } else {
                                         object_inits.push(match is_custom {
                                                 true => quote! { let #arg_ident = #obj_type::< #arg_ty >::load(#(#read_call_idents,)*)?; },
-                                                false => quote! { let #arg_ident = #obj_type::load(#(#read_call_idents,)*)?; },
+                                                false => quote! { let #arg_ident = #obj_type::#load_fn(#(#read_call_idents,)* #prefix_arg)?; },
                                             }
                                         );
                                     }
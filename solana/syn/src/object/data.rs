@@ -4,7 +4,7 @@ use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::{token::Colon, Fields, FnArg, Ident, Pat, PatIdent, PatType, Type};
 
-use super::seeds::Seed;
+use super::{parser::parse_field_attributes, seeds::Seed};
 
 /// Generates tokens to implement `Clone` on a struct.
 pub fn impl_clone(ident: &Ident, fields: &Fields) -> TokenStream {
@@ -43,8 +43,12 @@ pub fn impl_default(ident: &Ident, fields: &Fields) -> TokenStream {
 }
 
 /// Generates tokens to implement `BorshDeserialize` and `BorshSerialize` on a
-/// struct.
-pub fn impl_borsh(ident: &Ident, fields: &Fields) -> TokenStream {
+/// struct, whose discriminator is derived from `discriminator_str`.
+///
+/// Whether the discriminator is written is up to the `DISCRIMINATOR_PREFIXED`
+/// in scope where the struct is declared (see
+/// `nautilus::DISCRIMINATOR_PREFIXED`).
+pub fn impl_borsh(ident: &Ident, fields: &Fields, discriminator_str: TokenStream) -> TokenStream {
     let borsh_ser_where = fields.iter().map(|f| {
         let field_ty = f.ty.clone();
         quote::quote! { #field_ty: nautilus::borsh::ser::BorshSerialize }
@@ -57,9 +61,13 @@ pub fn impl_borsh(ident: &Ident, fields: &Fields) -> TokenStream {
         let field_ty = f.ty.clone();
         quote::quote! { #field_ty: nautilus::borsh::de::BorshDeserialize }
     });
-    let borsh_deser_impl = fields.iter().map(|f| {
+    let default_on_missing = get_default_on_missing_fields(fields);
+    let borsh_deser_impl = fields.iter().zip(default_on_missing).map(|(f, default_on_missing)| {
         let field_name = f.ident.clone();
-        quote::quote! { #field_name: nautilus::borsh::BorshDeserialize::deserialize(buf)? }
+        match default_on_missing {
+            true => quote::quote! { #field_name: nautilus::deserialize_or_default(buf)? },
+            false => quote::quote! { #field_name: nautilus::borsh::BorshDeserialize::deserialize(buf)? },
+        }
     });
     quote::quote! {
         impl nautilus::borsh::ser::BorshSerialize for #ident
@@ -70,7 +78,9 @@ pub fn impl_borsh(ident: &Ident, fields: &Fields) -> TokenStream {
                 &self,
                 writer: &mut W,
             ) -> ::core::result::Result<(), nautilus::borsh::maybestd::io::Error> {
-                borsh::BorshSerialize::serialize(&self.discriminator(), writer)?; // Serialize the discriminator first
+                if DISCRIMINATOR_PREFIXED {
+                    borsh::BorshSerialize::serialize(&self.discriminator(), writer)?; // Serialize the discriminator first
+                }
                 #(#borsh_ser_impl;)*
                 Ok(())
             }
@@ -82,7 +92,15 @@ pub fn impl_borsh(ident: &Ident, fields: &Fields) -> TokenStream {
             fn deserialize(
                 buf: &mut &[u8],
             ) -> ::core::result::Result<Self, nautilus::borsh::maybestd::io::Error> {
-                let _discrim: [u8; 8] = borsh::BorshDeserialize::deserialize(buf)?; // Skip the first 8 bytes for discriminator
+                if DISCRIMINATOR_PREFIXED {
+                    let discrim: [u8; 8] = borsh::BorshDeserialize::deserialize(buf)?; // Check the first 8 bytes for discriminator
+                    if discrim != nautilus::discriminator(#discriminator_str) {
+                        return Err(nautilus::borsh::maybestd::io::Error::new(
+                            nautilus::borsh::maybestd::io::ErrorKind::InvalidData,
+                            "Account data does not begin with this type's discriminator",
+                        ));
+                    }
+                }
                 Ok(Self {
                     #(#borsh_deser_impl,)*
                 })
@@ -91,6 +109,128 @@ pub fn impl_borsh(ident: &Ident, fields: &Fields) -> TokenStream {
     }
 }
 
+/// Generates tokens for the getters of the fields of a struct marked
+/// `#[nautilus(audit)]`, which log each access (see
+/// `nautilus::log_field_access(..)`).
+///
+/// `type_name` is the expression naming the type in the logs, such as
+/// `Self::TABLE_NAME`.
+pub fn impl_audit_getters(ident: &Ident, fields: &Fields, type_name: TokenStream) -> TokenStream {
+    let getters = fields
+        .iter()
+        .filter(|f| parse_field_attributes(f).is_audited)
+        .map(|f| {
+            let field_name = f.ident.clone().unwrap();
+            let field_ty = &f.ty;
+            let getter_ident = Ident::new(&format!("get_{}", field_name), Span::call_site());
+            let field_name_str = field_name.to_string();
+            quote! {
+                /// Returns this field, logging the access by `accessor`.
+                pub fn #getter_ident(&self, accessor: &Pubkey) -> &#field_ty {
+                    nautilus::log_field_access(#type_name, #field_name_str, accessor);
+                    &self.#field_name
+                }
+            }
+        });
+    quote! {
+        impl #ident {
+            #(#getters)*
+        }
+    }
+}
+
+/// Generates tokens for the `<T>View<'v>` of a struct: a read-only view
+/// borrowing the struct's account data, with an accessor for each field that
+/// skips the fields ahead of it and reads only its own (see
+/// `nautilus::ViewField`).
+pub fn impl_view(ident: &Ident, fields: &Fields) -> TokenStream {
+    let view_ident = Ident::new(&format!("{}View", ident), Span::call_site());
+    let view_doc = format!(
+        "A read-only view of a `{}` borrowed from its account data, whose accessors read each field lazily.",
+        ident
+    );
+    let default_on_missing = get_default_on_missing_fields(fields);
+    let skips: Vec<TokenStream> = fields
+        .iter()
+        .zip(&default_on_missing)
+        .map(|(f, default_on_missing)| {
+            let field_ty = &f.ty;
+            match default_on_missing {
+                true => quote! { nautilus::skip_or_default::<#field_ty>(&mut buf)?; },
+                false => quote! { <#field_ty as nautilus::ViewField<'v>>::skip(&mut buf)?; },
+            }
+        })
+        .collect();
+    let accessors =
+        fields
+            .iter()
+            .zip(&default_on_missing)
+            .enumerate()
+            .map(|(i, (f, default_on_missing))| {
+                let field_name = f.ident.clone().unwrap();
+                let field_ty = &f.ty;
+                let skips = &skips[..i];
+                let read = match default_on_missing {
+                    true => quote! { nautilus::view_or_default::<#field_ty>(&mut buf)? },
+                    false => quote! { <#field_ty as nautilus::ViewField<'v>>::view(&mut buf)? },
+                };
+                quote! {
+                    pub fn #field_name(
+                        &self,
+                    ) -> Result<<#field_ty as nautilus::ViewField<'v>>::Output, ProgramError> {
+                        let mut buf: &'v [u8] = self.data;
+                        #(#skips)*
+                        Ok(#read)
+                    }
+                }
+            });
+    quote! {
+        #[doc = #view_doc]
+        pub struct #view_ident<'v> {
+            data: &'v [u8],
+        }
+
+        impl<'v> #view_ident<'v> {
+            /// Borrows a view of `data`, which must begin with the struct's
+            /// discriminator if discriminators are written.
+            pub fn new(data: &'v [u8]) -> Result<Self, ProgramError> {
+                let data = match DISCRIMINATOR_PREFIXED {
+                    true => {
+                        let discriminator =
+                            nautilus::discriminator(<#ident as NautilusRecordData>::TABLE_NAME);
+                        match data.strip_prefix(&discriminator[..]) {
+                            Some(data) => data,
+                            None => return Err(ProgramError::InvalidAccountData),
+                        }
+                    }
+                    false => data,
+                };
+                Ok(Self { data })
+            }
+
+            #(#accessors)*
+        }
+    }
+}
+
+/// Helper function that determines, for each field of a struct, whether it's
+/// marked `#[default_on_missing]`.
+///
+/// Only trailing fields can be read as their defaults when their bytes are
+/// missing, so once a field is marked, every field after it must be too.
+fn get_default_on_missing_fields(fields: &Fields) -> Vec<bool> {
+    let default_on_missing: Vec<bool> = fields
+        .iter()
+        .map(|f| parse_field_attributes(f).is_default_on_missing)
+        .collect();
+    if let Some(first) = default_on_missing.iter().position(|marked| *marked) {
+        if default_on_missing[first..].iter().any(|marked| !marked) {
+            panic!("Only trailing fields can be marked `#[default_on_missing]`! Every field after a `#[default_on_missing]` field must be marked too.");
+        }
+    }
+    default_on_missing
+}
+
 /// Generates tokens to implement `NautilusRecordData` on a struct.
 pub fn impl_nautilus_record_data(
     ident: &Ident,
@@ -107,8 +247,180 @@ pub fn impl_nautilus_record_data(
 
     let tokens_primary_key_seed = build_tokens_primary_key_seed(primary_key_ident, primary_key_ty);
 
-    let (data_new_fn_args, data_new_call_args) =
-        get_new_fn_args_for_record(fields, autoincrement, primary_key_ident);
+    let authorities: Vec<Ident> = fields
+        .iter()
+        .filter(|f| parse_field_attributes(f).is_authority)
+        .map(|f| f.ident.clone().unwrap())
+        .collect();
+    let payer_authorities: Vec<Ident> = fields
+        .iter()
+        .filter(|f| parse_field_attributes(f).is_authority_is_payer)
+        .map(|f| f.ident.clone().unwrap())
+        .collect();
+    if payer_authorities.len() > 1 {
+        panic!("Only one field can be marked `#[nautilus(authority_is_payer)]`!");
+    }
+    let payer_authority = payer_authorities.first();
+    let last_writers: Vec<Ident> = fields
+        .iter()
+        .filter(|f| parse_field_attributes(f).is_last_writer)
+        .map(|f| f.ident.clone().unwrap())
+        .collect();
+    if last_writers.len() > 1 {
+        panic!("Only one field can be marked `#[nautilus(last_writer)]`!");
+    }
+    let last_writer = last_writers.first();
+    if last_writer.map_or(false, |ident| authorities.contains(ident)) {
+        panic!("A field marked `#[nautilus(last_writer)]` can't be an authority, since every write restamps it!");
+    }
+
+    let (data_new_fn_args, data_new_call_args) = get_new_fn_args_for_record(
+        fields,
+        autoincrement,
+        primary_key_ident,
+        payer_authority,
+        last_writer,
+    );
+
+    // The fields stamped with the key of whoever pays for the record, if any.
+    let payer_authority_init = payer_authority.map(|ident| quote! { #ident: *fee_payer.key(), });
+    let last_writer_init = last_writer.map(|ident| quote! { #ident: *fee_payer.key(), });
+    let last_writer_stamp = last_writer.map(|ident| quote! { data.#ident = *authority.key(); });
+    let last_writer_impl = last_writer.map(|last_writer| {
+        quote! {
+            impl nautilus::LastWriter for #ident {
+                fn last_writer(&self) -> &Pubkey {
+                    &self.#last_writer
+                }
+
+                fn set_last_writer(&mut self, writer: Pubkey) {
+                    self.#last_writer = writer;
+                }
+            }
+        }
+    });
+
+    let authorities_count = authorities.len() as u8;
+    let accounts_arg = match authorities.is_empty() {
+        true => quote! { _accounts },
+        false => quote! { accounts },
+    };
+    let authority_checks = authorities.iter().map(|ident| {
+        quote! {
+            if !accounts
+                .iter()
+                .any(|account| account.is_signer && *account.key == self.#ident)
+            {
+                return Err(nautilus::error::NautilusError::MissingAuthority(
+                    self.#ident.to_string(),
+                )
+                .into());
+            }
+        }
+    });
+
+    let create_with_authority_fns = payer_authority.map(|payer_authority| {
+        (
+            quote! {
+                fn create_with_authority(&mut self, #(#data_new_fn_args,)* authority: impl NautilusSigner<'a>, payer: impl NautilusSigner<'a>) -> ProgramResult;
+            },
+            quote! {
+                fn create_with_authority(&mut self, #(#data_new_fn_args,)* authority: impl NautilusSigner<'a>, payer: impl NautilusSigner<'a>) -> ProgramResult {
+                    let mut data = #ident ::new(
+                        self.self_account.index.clone(),
+                        payer.clone(),
+                        #(#data_new_call_args,)*
+                    )?;
+                    data.#payer_authority = *authority.key();
+                    #last_writer_stamp
+                    self.self_account.data = data;
+                    self.create_record_with_payer(payer)
+                }
+            },
+        )
+    });
+    let (create_with_authority_decl, create_with_authority_impl) = match create_with_authority_fns {
+        Some((decl, imp)) => (decl, imp),
+        None => (quote! {}, quote! {}),
+    };
+
+    // Only integer primary keys can hold an id assigned by the index.
+    let with_id_fn = match quote!(#primary_key_ty).to_string().as_str() {
+        "u8" | "u16" | "u32" | "u64" => quote! {
+            fn with_id(mut self, id: u32) -> Option<Self> {
+                self.#primary_key_ident = ::core::convert::TryFrom::try_from(id).ok()?;
+                Some(self)
+            }
+        },
+        _ => quote! {},
+    };
+
+    // The fields the create path fills in itself are measured at their default
+    // if they have no value, since they're of a fixed size.
+    let field_names: Vec<String> = fields
+        .iter()
+        .map(|f| f.ident.as_ref().unwrap().to_string())
+        .collect();
+    let size_for_fields = fields.iter().map(|f| {
+        let field_ident = f.ident.as_ref().unwrap();
+        let field_name = field_ident.to_string();
+        let field_ty = &f.ty;
+        let stamped = (autoincrement && field_ident == primary_key_ident)
+            || Some(field_ident) == payer_authority
+            || Some(field_ident) == last_writer;
+        let missing = match stamped {
+            true => quote! {
+                nautilus::borsh::BorshSerialize::try_to_vec(
+                    &<#field_ty as ::core::default::Default>::default(),
+                )?
+                .len()
+            },
+            false => quote! {
+                return Err(nautilus::error::NautilusError::MissingFieldValue(
+                    Self::TABLE_NAME.to_string(),
+                    #field_name.to_string(),
+                )
+                .into())
+            },
+        };
+        quote! {
+            size += match fields.get(#field_name) {
+                Some(bytes) => {
+                    <#field_ty as nautilus::borsh::BorshDeserialize>::try_from_slice(bytes).map_err(|_| {
+                        nautilus::error::NautilusError::InvalidFieldValue(
+                            Self::TABLE_NAME.to_string(),
+                            #field_name.to_string(),
+                        )
+                    })?;
+                    bytes.len()
+                }
+                None => #missing,
+            };
+        }
+    });
+
+    let max_span_fields = fields.iter().map(|f| {
+        let field_ty = &f.ty;
+        quote! { span = span.checked_add(<#field_ty as nautilus::MaxSpan>::max_span()?)?; }
+    });
+
+    let schema = format!(
+        "{}({})",
+        table_name,
+        fields
+            .iter()
+            .map(|f| {
+                let field_ty = &f.ty;
+                let field_ty: String = quote! { #field_ty }
+                    .to_string()
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                format!("{}:{}", f.ident.as_ref().unwrap(), field_ty)
+            })
+            .collect::<Vec<String>>()
+            .join(","),
+    );
 
     let data_new_fn = match autoincrement {
         true => quote! {
@@ -117,11 +429,18 @@ pub fn impl_nautilus_record_data(
                 fee_payer: impl NautilusSigner<'a>,
                 #(#data_new_fn_args,)*
             ) -> Result<Box<Self>, ProgramError> {
-                let #primary_key_ident = nautilus_index.add_record(
-                    Self::TABLE_NAME,
-                    fee_payer,
-                )?.try_into().unwrap();
-                Ok(Box::new(Self{ #primary_key_ident, #(#data_new_call_args,)* }))
+                let mut data = Self{
+                    #primary_key_ident: ::core::default::Default::default(),
+                    #payer_authority_init
+                    #last_writer_init
+                    #(#data_new_call_args,)*
+                };
+                let table_name = data.variant_table_name();
+                data.#primary_key_ident = nautilus_index
+                    .add_record(&table_name, fee_payer)?
+                    .try_into()
+                    .map_err(|_| nautilus::error::NautilusError::IdSpaceExhausted(table_name))?;
+                Ok(Box::new(data))
             }
         },
         false => quote! {
@@ -130,7 +449,7 @@ pub fn impl_nautilus_record_data(
                 fee_payer: impl NautilusSigner<'a>,
                 #(#data_new_fn_args,)*
             ) -> Result<Box<Self>, ProgramError> {
-                Ok(Box::new(Self{ #(#data_new_call_args,)* }))
+                Ok(Box::new(Self{ #payer_authority_init #last_writer_init #(#data_new_call_args,)* }))
             }
         },
     };
@@ -149,18 +468,56 @@ pub fn impl_nautilus_record_data(
                 #tokens_primary_key_seed
             }
 
-            fn check_authorities(&self, accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
-                todo!()
+            #with_id_fn
+
+            fn max_span() -> Option<usize> {
+                let mut span: usize = match DISCRIMINATOR_PREFIXED {
+                    true => 8,
+                    false => 0,
+                };
+                #(#max_span_fields)*
+                Some(span)
+            }
+
+            fn schema() -> &'static str {
+                #schema
+            }
+
+            fn size_for_fields(fields: &nautilus::FieldValues) -> Result<usize, ProgramError> {
+                if let Some(field_name) = fields
+                    .field_names()
+                    .find(|field_name| ![#(#field_names),*].contains(field_name))
+                {
+                    return Err(nautilus::error::NautilusError::InvalidFieldValue(
+                        Self::TABLE_NAME.to_string(),
+                        field_name.to_string(),
+                    )
+                    .into());
+                }
+                let mut size: usize = match DISCRIMINATOR_PREFIXED {
+                    true => 8,
+                    false => 0,
+                };
+                #(#size_for_fields)*
+                Ok(size)
+            }
+
+            fn check_authorities(&self, #accounts_arg: Vec<AccountInfo>) -> Result<(), ProgramError> {
+                #(#authority_checks)*
+                Ok(())
             }
 
             fn count_authorities(&self) -> u8 {
-                todo!()
+                #authorities_count
             }
         }
 
+        #last_writer_impl
+
         pub trait #nautilus_create_obj_trait_ident<'a> {
             fn create(&mut self, #(#data_new_fn_args,)*) -> ProgramResult;
             fn create_with_payer(&mut self, #(#data_new_fn_args,)* payer: impl NautilusSigner<'a>) -> ProgramResult;
+            #create_with_authority_decl
         }
 
         impl<'a> #nautilus_create_obj_trait_ident<'a> for Create<'a, Record<'a, #ident>> {
@@ -185,6 +542,8 @@ pub fn impl_nautilus_record_data(
                 )?;
                 self.create_record_with_payer(payer)
             }
+
+            #create_with_authority_impl
         }
     }
 }
@@ -339,6 +698,7 @@ pub fn impl_nautilus_account_data(
                     payer.clone(),
                     #(#data_new_call_args,)*
                 )?;
+                assert_seed_count(&self.seeds(#seeds_caller)?)?;
                 let (pda, bump) = self.pda(#pda_caller_outer)?;
                 assert_eq!(
                     &pda,
@@ -363,6 +723,7 @@ pub fn impl_nautilus_account_data(
                     payer.clone(),
                     #(#data_new_call_args,)*
                 )?;
+                assert_seed_count(&self.seeds(#seeds_caller)?)?;
                 let (pda, bump) = self.pda(#pda_caller_outer)?;
                 assert_eq!(
                     &pda,
@@ -409,16 +770,25 @@ fn build_tokens_primary_key_seed(key: &syn::Ident, ty: &syn::Type) -> TokenStrea
 
 /// Helper function that parses the fields of a struct to determine the function
 /// signature for a `new(..) -> Self` function to create a record.
+///
+/// A field marked `#[nautilus(authority_is_payer)]` or
+/// `#[nautilus(last_writer)]` takes no argument, since it's stamped with the
+/// fee payer's key.
 fn get_new_fn_args_for_record(
     fields: &Fields,
     autoincrement: bool,
     primary_key_ident: &Ident,
+    payer_authority: Option<&Ident>,
+    last_writer: Option<&Ident>,
 ) -> (Vec<FnArg>, Vec<Ident>) {
     let mut data_new_fn_args: Vec<FnArg> = vec![];
     let mut data_new_call_args: Vec<Ident> = vec![];
     fields.iter().for_each(|f| match &f.ident {
         Some(ident) => {
-            if !(autoincrement && ident == primary_key_ident) {
+            if !(autoincrement && ident == primary_key_ident)
+                && Some(ident) != payer_authority
+                && Some(ident) != last_writer
+            {
                 data_new_call_args.push(ident.clone());
                 data_new_fn_args.push(FnArg::Typed(PatType {
                     attrs: vec![],
@@ -13,7 +13,8 @@ use crate::entry::required_account::RequiredAccount;
 
 use self::{
     data::{
-        impl_borsh, impl_clone, impl_default, impl_nautilus_account_data, impl_nautilus_record_data,
+        impl_audit_getters, impl_borsh, impl_clone, impl_default, impl_nautilus_account_data,
+        impl_nautilus_record_data, impl_view,
     },
     parser::{parse_item_struct, NautilusObjectConfig},
 };
@@ -140,7 +141,7 @@ impl From<&NautilusObject> for TokenStream {
                 let fields = &data_fields;
                 let impl_clone = impl_clone(ident, fields);
                 let impl_default = impl_default(ident, fields);
-                let impl_borsh = impl_borsh(ident, fields);
+                let impl_borsh = impl_borsh(ident, fields, quote! { Self::TABLE_NAME });
                 let impl_nautilus_record_data = impl_nautilus_record_data(
                     ident,
                     fields,
@@ -149,11 +150,16 @@ impl From<&NautilusObject> for TokenStream {
                     &primary_key_ident,
                     &primary_key_ty,
                 );
+                let impl_audit_getters =
+                    impl_audit_getters(ident, fields, quote! { Self::TABLE_NAME });
+                let impl_view = impl_view(ident, fields);
                 quote! {
                     #impl_clone
                     #impl_default
                     #impl_borsh
                     #impl_nautilus_record_data
+                    #impl_audit_getters
+                    #impl_view
                 }
                 .into()
             }
@@ -166,14 +172,17 @@ impl From<&NautilusObject> for TokenStream {
                 let fields = &data_fields;
                 let impl_clone = impl_clone(ident, fields);
                 let impl_default = impl_default(ident, fields);
-                let impl_borsh = impl_borsh(ident, fields);
+                let impl_borsh = impl_borsh(ident, fields, quote! { Self::DISCRIMINATOR_STR });
                 let impl_nautilus_account_data =
                     impl_nautilus_account_data(ident, fields, &discrminator_str, seeds);
+                let impl_audit_getters =
+                    impl_audit_getters(ident, fields, quote! { Self::DISCRIMINATOR_STR });
                 quote! {
                     #impl_clone
                     #impl_default
                     #impl_borsh
                     #impl_nautilus_account_data
+                    #impl_audit_getters
                 }
                 .into()
             }
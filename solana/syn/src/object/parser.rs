@@ -36,6 +36,10 @@ pub struct NautilusAccountFieldAttributes {
     pub is_primary_key: bool,
     pub autoincrement_enabled: bool,
     pub is_authority: bool,
+    pub is_authority_is_payer: bool,
+    pub is_last_writer: bool,
+    pub is_default_on_missing: bool,
+    pub is_audited: bool,
 }
 
 /// Parse out a `syn::ItemStruct` according to whichever type of Nautilus object
@@ -109,12 +113,17 @@ pub fn parse_item_struct(
     }
 }
 
-/// Parses the field attributes of the struct, such as `#[authority]` and
-/// `#[primary_key(..)]`.
+/// Parses the field attributes of the struct, such as `#[authority]`,
+/// `#[primary_key(..)]`, `#[default_on_missing]` and
+/// `#[nautilus(authority_is_payer)]` or `#[nautilus(last_writer)]`.
 pub fn parse_field_attributes(field: &syn::Field) -> NautilusAccountFieldAttributes {
     let mut is_primary_key = false;
     let mut autoincrement_enabled = true;
     let mut is_authority = false;
+    let mut is_authority_is_payer = false;
+    let mut is_last_writer = false;
+    let mut is_default_on_missing = false;
+    let mut is_audited = false;
     for attr in field.attrs.iter() {
         if let Ok(syn::Meta::List(meta_list)) = attr.parse_meta() {
             if meta_list.path.is_ident("primary_key") {
@@ -130,17 +139,36 @@ pub fn parse_field_attributes(field: &syn::Field) -> NautilusAccountFieldAttribu
                         }
                     }
                 }
+            } else if meta_list.path.is_ident("nautilus") {
+                for nested_meta in &meta_list.nested {
+                    if let syn::NestedMeta::Meta(syn::Meta::Path(path)) = nested_meta {
+                        if path.is_ident("audit") {
+                            is_audited = true;
+                        } else if path.is_ident("authority_is_payer") {
+                            is_authority = true;
+                            is_authority_is_payer = true;
+                        } else if path.is_ident("last_writer") {
+                            is_last_writer = true;
+                        }
+                    }
+                }
             }
         } else if attr.path.is_ident("primary_key") {
             is_primary_key = true;
         } else if attr.path.is_ident("authority") {
             is_authority = true;
+        } else if attr.path.is_ident("default_on_missing") {
+            is_default_on_missing = true;
         }
     }
     NautilusAccountFieldAttributes {
         is_primary_key,
         autoincrement_enabled,
         is_authority,
+        is_authority_is_payer,
+        is_last_writer,
+        is_default_on_missing,
+        is_audited,
     }
 }
 
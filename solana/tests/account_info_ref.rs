@@ -0,0 +1,67 @@
+mod common;
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use common::{account_info, leak_key, Hero};
+use nautilus::*;
+
+/// Counts every allocation made by this test binary.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Reads the record's account `READS` times, the way a read-heavy instruction
+/// would, and returns the number of allocations made doing so.
+fn allocations_for_reads(read: impl Fn() -> u64) -> usize {
+    const READS: u64 = 1_000;
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let mut total = 0;
+    for _ in 0..READS {
+        total += read();
+    }
+    assert_eq!(total, READS * 42);
+    ALLOCATIONS.load(Ordering::SeqCst) - before
+}
+
+#[test]
+fn account_info_ref_does_not_allocate() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let new_account = || {
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            42,
+            vec![],
+            false,
+            true,
+        ))
+    };
+    let record = Mut::new(Record::<Hero>::new(
+        program_id,
+        new_account(),
+        NautilusIndex::new(program_id, new_account()),
+    ))
+    .unwrap();
+
+    let owned = allocations_for_reads(|| record.account_info().lamports());
+    let borrowed = allocations_for_reads(|| record.account_info_ref().lamports());
+    assert!(owned >= 1_000, "{} allocations", owned);
+    assert_eq!(borrowed, 0);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::{account_info, leak_key, Hero};
+use nautilus::{error::NautilusError, *};
+
+/// A fixed-size "stats" aggregate over `Hero` records.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+struct HeroStats {
+    count: u32,
+    name_bytes: u64,
+}
+
+impl NautilusRecordData for HeroStats {
+    const TABLE_NAME: &'static str = "hero_stats";
+    const AUTO_INCREMENT: bool = false;
+
+    fn primary_key(&self) -> Vec<u8> {
+        vec![0]
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+impl NautilusAggregate<Hero> for HeroStats {
+    fn on_create(&mut self, record: &Hero) {
+        self.count += 1;
+        self.name_bytes += record.name.len() as u64;
+    }
+
+    fn on_delete(&mut self, record: &Hero) {
+        self.count -= 1;
+        self.name_bytes -= record.name.len() as u64;
+    }
+}
+
+fn stats_record(is_writable: bool) -> Record<'static, HeroStats> {
+    let program_id = leak_key(Pubkey::new_unique());
+    let index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    Record::load(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            HeroStats::default().try_to_vec().unwrap(),
+            false,
+            is_writable,
+        )),
+        index,
+    )
+    .unwrap()
+}
+
+fn hero(id: u32, name: &str) -> Hero {
+    Hero {
+        id,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn aggregate_hooks_update_and_write_the_aggregate() {
+    let mut stats = AggregateRecord::new(stats_record(true)).unwrap();
+    stats.on_create(&hero(1, "Joe")).unwrap();
+    stats.on_create(&hero(2, "Jane")).unwrap();
+    stats
+        .on_update(&hero(1, "Joe"), &hero(1, "Joseph"))
+        .unwrap();
+    stats.on_delete(&hero(2, "Jane")).unwrap();
+
+    let expected = HeroStats {
+        count: 1,
+        name_bytes: 6,
+    };
+    assert_eq!(stats.data(), &expected);
+    assert_eq!(
+        HeroStats::try_from_slice(&stats.record.self_account.account_info.data.borrow()).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn aggregate_account_must_be_writable() {
+    assert_eq!(
+        AggregateRecord::new(stats_record(false)).err(),
+        Some(NautilusError::AccountNotMutable(String::default()).into())
+    );
+}
@@ -0,0 +1,22 @@
+use nautilus::*;
+
+#[derive(Table)]
+pub struct Patient {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    pub name: String,
+    #[nautilus(audit)]
+    pub diagnosis: String,
+}
+
+#[test]
+fn audited_fields_have_logging_getters() {
+    let patient = Patient {
+        id: 1,
+        name: "Joe".to_string(),
+        diagnosis: "Healthy".to_string(),
+    };
+    let accessor = Pubkey::new_unique();
+    assert_eq!(patient.get_diagnosis(&accessor), "Healthy");
+    log_field_access(Patient::TABLE_NAME, "diagnosis", &accessor);
+}
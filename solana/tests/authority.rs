@@ -0,0 +1,519 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key, reallocatable_account_info, signer};
+use nautilus::{error::NautilusError, *};
+
+#[derive(Table)]
+pub struct Note {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    #[nautilus(authority_is_payer)]
+    pub owner: Pubkey,
+    pub body: String,
+}
+
+#[derive(Table)]
+pub struct Draft {
+    #[primary_key(autoincrement = false)]
+    pub id: u32,
+    #[authority]
+    pub owner: Pubkey,
+    pub body: String,
+    pub version: u64,
+}
+
+impl Versioned for Draft {
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+}
+
+#[derive(Table)]
+pub struct Tag {
+    #[primary_key(autoincrement = false)]
+    pub name: String,
+}
+
+#[test]
+fn payer_authorities_are_stamped_and_checked() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    let payer = signer(u64::MAX);
+    let note = Note::new(index, payer.clone(), "Hello".to_string()).unwrap();
+    assert_eq!(note.id, 1);
+    assert_eq!(note.owner, *payer.key());
+    assert_eq!(note.count_authorities(), 1);
+
+    let missing = Err(NautilusError::MissingAuthority(String::default()).into());
+    assert_eq!(note.check_authorities(vec![*payer.account_info()]), Ok(()));
+    assert_eq!(note.check_authorities(vec![]), missing);
+    let mut unsigned = *payer.account_info();
+    unsigned.is_signer = false;
+    assert_eq!(note.check_authorities(vec![unsigned]), missing);
+    let other = account_info(
+        leak_key(Pubkey::new_unique()),
+        &system_program::ID,
+        0,
+        vec![],
+        true,
+        false,
+    );
+    assert_eq!(note.check_authorities(vec![other]), missing);
+
+    let tag = Tag {
+        name: "rust".to_string(),
+    };
+    assert_eq!(tag.count_authorities(), 0);
+    assert_eq!(tag.check_authorities(vec![]), Ok(()));
+}
+
+#[test]
+fn close_and_burn_sends_rent_to_the_incinerator() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    let owner = signer(u64::MAX);
+    let note = Note::new(index.clone(), owner.clone(), "Burn me".to_string()).unwrap();
+    let bytes = note.try_to_vec().unwrap();
+    let address = note.pda(program_id).0;
+    let record = |data: Vec<u8>| {
+        Record::<Note>::load(
+            program_id,
+            Box::new(reallocatable_account_info(
+                address, program_id, 5_000, data, true,
+            )),
+            index.clone(),
+        )
+        .unwrap()
+    };
+    let incinerator = |is_writable| {
+        account_info(
+            leak_key(solana_program::incinerator::ID),
+            &system_program::ID,
+            1,
+            vec![],
+            false,
+            is_writable,
+        )
+    };
+
+    let missing = Err(NautilusError::MissingAuthority(String::default()).into());
+    assert_eq!(
+        record(bytes.clone()).close_and_burn(vec![], &incinerator(true)),
+        missing
+    );
+    let impostor = account_info(
+        leak_key(Pubkey::new_unique()),
+        &system_program::ID,
+        0,
+        vec![],
+        false,
+        true,
+    );
+    assert_eq!(
+        record(bytes.clone()).close_and_burn(vec![*owner.account_info()], &impostor),
+        Err(ProgramError::InvalidArgument)
+    );
+    assert_eq!(
+        record(bytes.clone()).close_and_burn(vec![*owner.account_info()], &incinerator(false)),
+        Err(NautilusError::AccountNotMutable(String::default()).into())
+    );
+
+    let burned = record(bytes);
+    let account = burned.account_info.clone();
+    let incinerator = incinerator(true);
+    assert_eq!(
+        burned.close_and_burn(vec![*owner.account_info()], &incinerator),
+        Ok(())
+    );
+    assert_eq!(incinerator.lamports(), 1 + 5_000);
+    assert_eq!(account.lamports(), 0);
+    assert_eq!(account.data_len(), 0);
+    assert_eq!(account.owner, &system_program::ID);
+
+    // Without an authority to guard it, anyone could burn the record.
+    let tag = Tag {
+        name: "rust".to_string(),
+    };
+    let tag = Record::<Tag>::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            tag.pda(program_id).0,
+            program_id,
+            5_000,
+            tag.try_to_vec().unwrap(),
+            true,
+        )),
+        index,
+    )
+    .unwrap();
+    assert_eq!(
+        tag.close_and_burn(vec![], &incinerator),
+        Err(NautilusError::BurnWithoutAuthority(String::default()).into())
+    );
+    assert_eq!(incinerator.lamports(), 1 + 5_000);
+}
+
+#[test]
+fn update_requires_the_records_authorities() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    let owner = signer(u64::MAX);
+    let note = Note::new(index.clone(), owner.clone(), "Hello".to_string()).unwrap();
+    let bytes = note.try_to_vec().unwrap();
+    let mut record = Record::<Note>::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            note.pda(program_id).0,
+            program_id,
+            minimum_balance(bytes.len()).unwrap(),
+            bytes.clone(),
+            true,
+        )),
+        index,
+    )
+    .unwrap();
+    let mut edited = (*note).clone();
+    edited.body = "Hijacked".to_string();
+
+    assert_eq!(
+        record.update(edited.clone(), vec![], owner.clone()),
+        Err(NautilusError::MissingAuthority(String::default()).into())
+    );
+    assert_eq!(
+        record.update(
+            edited.clone(),
+            vec![*signer(0).account_info()],
+            owner.clone()
+        ),
+        Err(NautilusError::MissingAuthority(String::default()).into())
+    );
+    assert_eq!(*record.account_info.data.borrow(), &bytes[..]);
+
+    assert_eq!(
+        record.update(edited, vec![*owner.account_info()], owner.clone()),
+        Ok(WriteOutcome::Written)
+    );
+    assert_eq!(record.data.body, "Hijacked");
+}
+
+/// Creates a note owned by `owner`, returning its record - holding `lamports`
+/// - along with its bytes.
+fn note_record(
+    program_id: &'static Pubkey,
+    owner: &Signer<Wallet<'static>>,
+    lamports: u64,
+) -> (Record<'static, Note>, Vec<u8>) {
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    let note = Note::new(index.clone(), owner.clone(), "Archive me".to_string()).unwrap();
+    let bytes = note.try_to_vec().unwrap();
+    let record = Record::<Note>::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            note.pda(program_id).0,
+            program_id,
+            lamports,
+            bytes.clone(),
+            true,
+        )),
+        index,
+    )
+    .unwrap();
+    (record, bytes)
+}
+
+#[test]
+fn close_with_archive_requires_the_records_authorities() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let owner = signer(u64::MAX);
+    let (record, bytes) = note_record(program_id, &owner, 1_000);
+    let account = record.account_info.clone();
+    let archive = reallocatable_account_info(Pubkey::new_unique(), program_id, 0, vec![], true);
+    let thief = signer(0);
+
+    let missing = Err(NautilusError::MissingAuthority(String::default()).into());
+    assert_eq!(
+        record
+            .clone()
+            .close_with_archive(vec![], &archive, thief.clone())
+            .map(|_| ()),
+        missing
+    );
+    assert_eq!(
+        record
+            .close_with_archive(vec![*thief.account_info()], &archive, thief.clone())
+            .map(|_| ()),
+        missing
+    );
+    assert_eq!(*account.data.borrow(), &bytes[..]);
+    assert_eq!(account.lamports(), 1_000);
+    assert_eq!(archive.data_len(), 0);
+    assert_eq!(thief.lamports(), 0);
+
+    // Without an authority to guard it, anyone could take the record's rent.
+    let tag = Tag {
+        name: "rust".to_string(),
+    };
+    let tag = Record::<Tag>::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            tag.pda(program_id).0,
+            program_id,
+            1_000,
+            tag.try_to_vec().unwrap(),
+            true,
+        )),
+        NautilusIndex::new(
+            program_id,
+            Box::new(account_info(
+                leak_key(Pubkey::new_unique()),
+                program_id,
+                0,
+                vec![],
+                false,
+                true,
+            )),
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        tag.close_with_archive(vec![], &archive, thief.clone())
+            .map(|_| ()),
+        Err(NautilusError::CloseWithoutAuthority(String::default()).into())
+    );
+    assert_eq!(thief.lamports(), 0);
+}
+
+#[test]
+fn close_with_archive_requires_a_writable_program_owned_archive() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let owner = signer(u64::MAX);
+    let (record, bytes) = note_record(program_id, &owner, 1_000);
+    let account = record.account_info.clone();
+    let refund_to = signer(0);
+    let archive = |owner: &Pubkey, is_writable| {
+        account_info(
+            leak_key(Pubkey::new_unique()),
+            owner,
+            0,
+            vec![],
+            false,
+            is_writable,
+        )
+    };
+
+    assert_eq!(
+        record
+            .clone()
+            .close_with_archive(
+                vec![*owner.account_info()],
+                &archive(program_id, false),
+                refund_to.clone()
+            )
+            .map(|_| ()),
+        Err(NautilusError::AccountNotMutable(String::default()).into())
+    );
+    assert_eq!(
+        record
+            .close_with_archive(
+                vec![*owner.account_info()],
+                &archive(&Pubkey::new_unique(), true),
+                refund_to.clone()
+            )
+            .map(|_| ()),
+        Err(ProgramError::IllegalOwner)
+    );
+    assert_eq!(*account.data.borrow(), &bytes[..]);
+    assert_eq!(account.lamports(), 1_000);
+    assert_eq!(account.owner, program_id);
+    assert_eq!(refund_to.lamports(), 0);
+}
+
+#[test]
+fn close_with_archive_reports_the_archive_rent_and_refund() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let owner = signer(u64::MAX);
+    let (record, bytes) = note_record(program_id, &owner, 10_000_000);
+    let archive = reallocatable_account_info(Pubkey::new_unique(), program_id, 0, vec![], true);
+    let refund_to = signer(0);
+
+    let receipt = record
+        .close_with_archive(vec![*owner.account_info()], &archive, refund_to.clone())
+        .unwrap();
+    let archive_rent = minimum_balance(4 + bytes.len()).unwrap();
+    assert_eq!(
+        receipt,
+        LamportReceipt {
+            rent_paid: archive_rent,
+            refunded: 10_000_000 - archive_rent,
+            ..LamportReceipt::default()
+        }
+    );
+    assert_eq!(archive.lamports(), receipt.rent_paid);
+    assert_eq!(refund_to.lamports(), receipt.refunded);
+    assert_eq!(archived_records(&archive.data.borrow()), Some(vec![bytes]));
+}
+
+#[test]
+fn write_if_version_requires_the_records_authorities() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let owner = signer(u64::MAX);
+    let draft = Draft {
+        id: 1,
+        owner: *owner.key(),
+        body: "Hello".to_string(),
+        version: 3,
+    };
+    let bytes = draft.try_to_vec().unwrap();
+    let (mut record, version) = Record::<Draft>::load_versioned(
+        program_id,
+        Box::new(reallocatable_account_info(
+            draft.pda(program_id).0,
+            program_id,
+            minimum_balance(bytes.len()).unwrap(),
+            bytes.clone(),
+            true,
+        )),
+        NautilusIndex::new(
+            program_id,
+            Box::new(account_info(
+                leak_key(Pubkey::new_unique()),
+                program_id,
+                0,
+                vec![],
+                false,
+                true,
+            )),
+        ),
+    )
+    .unwrap();
+    let mut edited = draft.clone();
+    edited.body = "Hijacked".to_string();
+
+    let missing = Err(NautilusError::MissingAuthority(String::default()).into());
+    assert_eq!(
+        record.write_if_version(edited.clone(), version, vec![], owner.clone()),
+        missing
+    );
+    assert_eq!(
+        record.write_if_version(
+            edited.clone(),
+            version,
+            vec![*signer(0).account_info()],
+            owner.clone()
+        ),
+        missing
+    );
+    assert_eq!(*record.account_info.data.borrow(), &bytes[..]);
+
+    assert_eq!(
+        record.write_if_version(edited, version, vec![*owner.account_info()], owner.clone()),
+        Ok(4)
+    );
+    assert_eq!(record.data.body, "Hijacked");
+}
+
+#[test]
+fn write_full_requires_the_records_authorities() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let owner = signer(u64::MAX);
+    let draft = Draft {
+        id: 1,
+        owner: *owner.key(),
+        body: "Hello".to_string(),
+        version: 0,
+    };
+    let bytes = draft.try_to_vec().unwrap();
+    let account = Box::new(reallocatable_account_info(
+        draft.pda(program_id).0,
+        program_id,
+        minimum_balance(bytes.len()).unwrap(),
+        bytes.clone(),
+        true,
+    ));
+    let mut record: Record<Draft> = Record::new(
+        program_id,
+        account.clone(),
+        NautilusIndex::new(
+            program_id,
+            Box::new(account_info(
+                leak_key(Pubkey::new_unique()),
+                program_id,
+                0,
+                vec![],
+                false,
+                true,
+            )),
+        ),
+    );
+    *record.data = draft;
+
+    let missing = Err(NautilusError::MissingAuthority(String::default()).into());
+    assert_eq!(record.write_full(&[], vec![], owner.clone()), missing);
+    assert_eq!(
+        record.write_full(&[], vec![*signer(0).account_info()], owner.clone()),
+        missing
+    );
+    assert_eq!(*account.data.borrow(), &bytes[..]);
+
+    assert_eq!(
+        record.write_full(&[], vec![*owner.account_info()], owner.clone()),
+        Ok(())
+    );
+    assert_eq!(read_chain(&account, &[]).unwrap(), bytes);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key, signer};
+use nautilus::{error::NautilusError, *};
+
+#[test]
+fn cascade_orders_children_before_parents() {
+    install_runtime();
+    let mut plan = CascadePlan::new();
+    plan.add("person", 1)
+        .add_dependency(("home", 4), ("person", 1))
+        .add_dependency(("car", 2), ("person", 1))
+        .add_dependency(("car", 2), ("home", 4));
+    assert_eq!(
+        plan.order().unwrap(),
+        vec![("car", 2), ("home", 4), ("person", 1)]
+    );
+
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut data = NautilusIndexData::default();
+    for (table_name, count) in [("person", 1), ("home", 4), ("car", 2)] {
+        for _ in 0..count {
+            data.add_record(table_name);
+        }
+    }
+    let mut index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            data.try_to_vec().unwrap(),
+            false,
+            true,
+        )),
+    );
+    index.data = data;
+
+    let mut deleted = vec![];
+    plan.execute(&mut index, signer(u64::MAX), |table_name, id| {
+        deleted.push((table_name.to_string(), id));
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(deleted.len(), 3);
+    assert_eq!(deleted[0], ("car".to_string(), 2));
+    assert_eq!(index.get_count("person"), Some(0));
+    assert_eq!(index.get_count("home"), Some(3));
+    assert_eq!(index.get_count("car"), Some(1));
+}
+
+#[test]
+fn cascade_errors_on_cycle() {
+    let mut plan = CascadePlan::new();
+    plan.add_dependency(("a", 1), ("b", 1))
+        .add_dependency(("b", 1), ("a", 1));
+    assert_eq!(
+        plan.order(),
+        Err(NautilusError::CascadeCycle(String::default(), 0).into())
+    );
+
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    let mut deleted = 0;
+    assert!(plan
+        .execute(&mut index, signer(u64::MAX), |_, _| {
+            deleted += 1;
+            Ok(())
+        })
+        .is_err());
+    assert_eq!(deleted, 0);
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{
+    account_info, install_runtime, leak_key, reallocatable_account_info, set_slot, signer,
+};
+use nautilus::{error::NautilusError, *};
+
+#[test]
+fn changelog_wraps_at_capacity() {
+    let mut data = IndexChangelogData::with_capacity(2);
+    assert!(data.entries().is_empty());
+    data.append(ChangelogEntry::new(1, "person", 0, 1));
+    data.append(ChangelogEntry::new(2, "person", 1, 2));
+    data.append(ChangelogEntry::new(3, "car", 0, 1));
+    let entries = data.entries();
+    assert_eq!(data.capacity(), 2);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].slot, 2);
+    assert_eq!(entries[1].slot, 3);
+    assert_eq!(entries[1].table_name(), "car");
+
+    let mut empty = IndexChangelogData::with_capacity(0);
+    empty.append(ChangelogEntry::new(1, "person", 0, 1));
+    assert!(empty.entries().is_empty());
+}
+
+#[test]
+fn index_appends_removals_to_changelog() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut index_data = NautilusIndexData::default();
+    index_data.add_record("person");
+    index_data.add_record("person");
+    let mut index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            Pubkey::new_unique(),
+            program_id,
+            0,
+            index_data.try_to_vec().unwrap(),
+            true,
+        )),
+    )
+    .unwrap();
+    let changelog = IndexChangelog::load(
+        program_id,
+        Box::new(account_info(
+            leak_key(IndexChangelogData::default().pda(program_id).0),
+            program_id,
+            0,
+            IndexChangelogData::with_capacity(4).try_to_vec().unwrap(),
+            false,
+            true,
+        )),
+    )
+    .unwrap();
+    let changelog_account = changelog.account_info.clone();
+    index = index.with_changelog(changelog);
+
+    set_slot(7);
+    assert_eq!(index.remove_record("person", signer(u64::MAX)).unwrap(), 1);
+    set_slot(8);
+    assert_eq!(index.remove_record("person", signer(u64::MAX)).unwrap(), 0);
+
+    let written = IndexChangelogData::try_from_slice(&changelog_account.data.borrow()).unwrap();
+    assert_eq!(
+        written.entries(),
+        vec![
+            &ChangelogEntry::new(7, "person", 2, 1),
+            &ChangelogEntry::new(8, "person", 1, 0),
+        ]
+    );
+}
+
+#[test]
+fn index_changelog_rejects_impostor_accounts() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let address = leak_key(IndexChangelogData::default().pda(program_id).0);
+    let changelog_account = |key: &'static Pubkey, data: Vec<u8>| {
+        Box::new(account_info(key, program_id, 0, data, false, true))
+    };
+
+    // Another program-owned account that would parse as a changelog.
+    assert_eq!(
+        IndexChangelog::load(
+            program_id,
+            changelog_account(
+                leak_key(Pubkey::new_unique()),
+                IndexChangelogData::with_capacity(1).try_to_vec().unwrap()
+            )
+        )
+        .err(),
+        Some(NautilusError::AddressMismatch(String::default(), String::default()).into())
+    );
+    // Data at the changelog's address without the changelog's discriminator.
+    let mut head_first = 0u32.to_le_bytes().to_vec();
+    head_first.extend(0u32.to_le_bytes());
+    head_first.extend(0u32.to_le_bytes());
+    assert_eq!(
+        IndexChangelog::load(program_id, changelog_account(address, head_first)).err(),
+        Some(NautilusError::DeserializeDataFailed(String::default(), String::default()).into())
+    );
+}
+
+#[test]
+fn index_changelog_copies_append_without_losing_entries() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let changelog = IndexChangelog::load(
+        program_id,
+        Box::new(account_info(
+            leak_key(IndexChangelogData::default().pda(program_id).0),
+            program_id,
+            0,
+            IndexChangelogData::with_capacity(4).try_to_vec().unwrap(),
+            false,
+            true,
+        )),
+    )
+    .unwrap();
+    let mut first = changelog.clone();
+    let mut second = changelog;
+
+    set_slot(3);
+    first.append("person", 0, 1).unwrap();
+    second.append("car", 0, 1).unwrap();
+
+    let written = IndexChangelogData::try_from_slice(&first.account_info.data.borrow()).unwrap();
+    assert_eq!(
+        written.entries(),
+        vec![
+            &ChangelogEntry::new(3, "person", 0, 1),
+            &ChangelogEntry::new(3, "car", 0, 1),
+        ]
+    );
+}
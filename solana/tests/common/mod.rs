@@ -0,0 +1,291 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Once,
+    },
+};
+
+use nautilus::solana_program::{
+    clock::Clock,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    program_utils::limited_deserialize,
+    rent::Rent,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+use nautilus::{
+    borsh, AccountInfo, BorshDeserialize, BorshSerialize, Create, NautilusAccountInfo,
+    NautilusRecordData, Pubkey, Signer, Wallet,
+};
+
+/// A minimal record type for exercising `Record<'_, T>`.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+pub struct Hero {
+    pub id: u32,
+    pub name: String,
+}
+
+impl NautilusRecordData for Hero {
+    const TABLE_NAME: &'static str = "hero";
+    const AUTO_INCREMENT: bool = true;
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.id.to_le_bytes().to_vec()
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), nautilus::ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+/// Leaks a pubkey so it can back an `AccountInfo<'static>`.
+pub fn leak_key(key: Pubkey) -> &'static Pubkey {
+    Box::leak(Box::new(key))
+}
+
+/// Builds an `AccountInfo<'static>` with leaked backing storage.
+///
+/// The owner is copied into storage of its own, so reassigning the account
+/// leaves the pubkey passed in untouched.
+pub fn account_info(
+    key: &'static Pubkey,
+    owner: &Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    is_writable: bool,
+) -> AccountInfo<'static> {
+    AccountInfo::new(
+        key,
+        is_signer,
+        is_writable,
+        Box::leak(Box::new(lamports)),
+        Box::leak(data.into_boxed_slice()),
+        leak_key(*owner),
+        false,
+        0,
+    )
+}
+
+/// An account's key as the runtime serializes it for a program: preceded by
+/// the length of the account's data when the instruction began.
+#[repr(C)]
+struct SerializedKey {
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+/// Builds an `AccountInfo<'static>` laid out the way the runtime serializes
+/// accounts for a program - its original data length ahead of its key, its
+/// data length ahead of its data, and room after the data to grow by the
+/// realloc limit - so `AccountInfo::realloc(..)` can resize it.
+pub fn reallocatable_account_info(
+    key: Pubkey,
+    owner: &Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_writable: bool,
+) -> AccountInfo<'static> {
+    let len = data.len();
+    let serialized_key = Box::leak(Box::new(SerializedKey {
+        original_data_len: len as u32,
+        key,
+    }));
+    let words =
+        Box::leak(vec![0u64; 1 + (len + MAX_PERMITTED_DATA_INCREASE + 7) / 8].into_boxed_slice());
+    words[0] = len as u64;
+    let data = unsafe {
+        let data_ptr = (words.as_mut_ptr() as *mut u8).add(8);
+        std::ptr::copy_nonoverlapping(data.as_ptr(), data_ptr, len);
+        std::slice::from_raw_parts_mut(data_ptr, len)
+    };
+    AccountInfo::new(
+        &serialized_key.key,
+        false,
+        is_writable,
+        Box::leak(Box::new(lamports)),
+        data,
+        leak_key(*owner),
+        false,
+        0,
+    )
+}
+
+/// Builds a funded wallet that has signed the transaction.
+pub fn signer(lamports: u64) -> Signer<Wallet<'static>> {
+    let system_program = leak_key(system_program::ID);
+    Signer::new(Wallet {
+        account_info: Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            system_program,
+            lamports,
+            vec![],
+            true,
+            true,
+        )),
+        system_program: Box::new(account_info(
+            system_program,
+            system_program,
+            0,
+            vec![],
+            false,
+            false,
+        )),
+    })
+    .unwrap()
+}
+
+/// Wraps `self_account` in `Create<'_, T>`, with a fee payer funded with
+/// `fee_payer_lamports`.
+pub fn create<T: NautilusAccountInfo<'static>>(
+    self_account: T,
+    fee_payer_lamports: u64,
+) -> Create<'static, T> {
+    let system_program = leak_key(system_program::ID);
+    Create::new(
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            system_program,
+            fee_payer_lamports,
+            vec![],
+            true,
+            true,
+        )),
+        Box::new(account_info(
+            system_program,
+            system_program,
+            0,
+            vec![],
+            false,
+            false,
+        )),
+        Box::new(account_info(
+            leak_key(nautilus::solana_program::sysvar::rent::ID),
+            system_program,
+            0,
+            vec![],
+            false,
+            false,
+        )),
+        self_account,
+    )
+    .unwrap()
+}
+
+static SLOT: AtomicU64 = AtomicU64::new(0);
+
+/// A callback run on the accounts of every invocation, standing in for a
+/// program reading them through a CPI.
+type InvocationObserver = Box<dyn FnMut(&[AccountInfo])>;
+
+thread_local! {
+    static OBSERVER: RefCell<Option<InvocationObserver>> = RefCell::new(None);
+}
+
+/// Stands in for the runtime's syscalls: default rent, a settable clock slot,
+/// and the system program's `Transfer`, `CreateAccount` and `Assign`
+/// instructions applied to the accounts passed to the invocation. Signatures
+/// and seeds aren't checked, and invocations of any other program do nothing
+/// - beyond running the observer of `observe_invocations(..)`, if any.
+struct TestRuntime;
+
+impl SyscallStubs for TestRuntime {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Clock) = Clock {
+                slot: SLOT.load(Ordering::SeqCst),
+                ..Clock::default()
+            }
+        };
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        OBSERVER.with(|observer| {
+            if let Some(observer) = observer.borrow_mut().as_mut() {
+                observer(account_infos);
+            }
+        });
+        if instruction.program_id != system_program::ID {
+            return Ok(());
+        }
+        let account = |i: usize| {
+            account_infos
+                .iter()
+                .find(|a| *a.key == instruction.accounts[i].pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let transfer = |from: &AccountInfo, to: &AccountInfo, lamports: u64| {
+            let remaining = from
+                .lamports()
+                .checked_sub(lamports)
+                .ok_or(ProgramError::InsufficientFunds)?;
+            **from.try_borrow_mut_lamports()? = remaining;
+            **to.try_borrow_mut_lamports()? += lamports;
+            Ok::<(), ProgramError>(())
+        };
+        match limited_deserialize(&instruction.data, 1_024)
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+        {
+            SystemInstruction::Transfer { lamports } => {
+                transfer(account(0)?, account(1)?, lamports)
+            }
+            SystemInstruction::CreateAccount {
+                lamports, owner, ..
+            } => {
+                transfer(account(0)?, account(1)?, lamports)?;
+                account(1)?.assign(&owner);
+                Ok(())
+            }
+            SystemInstruction::Assign { owner } => {
+                account(0)?.assign(&owner);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Runs `observer` on the accounts of every invocation made on this thread
+/// under `install_runtime()`, until `clear_observer()` is called.
+pub fn observe_invocations(observer: impl FnMut(&[AccountInfo]) + 'static) {
+    OBSERVER.with(|slot| *slot.borrow_mut() = Some(Box::new(observer)));
+}
+
+/// Stops running the observer set by `observe_invocations(..)`.
+pub fn clear_observer() {
+    OBSERVER.with(|slot| *slot.borrow_mut() = None);
+}
+
+/// Installs the `TestRuntime` syscall stubs for this test binary.
+pub fn install_runtime() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(TestRuntime));
+    });
+}
+
+/// Sets the slot reported by the `Clock` sysvar under `install_runtime()`.
+pub fn set_slot(slot: u64) {
+    SLOT.store(slot, Ordering::SeqCst);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::{install_runtime, leak_key, reallocatable_account_info, signer};
+use nautilus::{error::NautilusError, *};
+
+#[derive(Table)]
+pub struct Ticket {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    pub seat: String,
+}
+
+/// Builds an empty, system-owned account at `key` with room for `ticket`.
+fn ticket_account(key: Pubkey, ticket: &Ticket) -> Box<AccountInfo<'static>> {
+    Box::new(reallocatable_account_info(
+        key,
+        &system_program::ID,
+        0,
+        vec![0; ticket.span().unwrap()],
+        true,
+    ))
+}
+
+#[test]
+fn create_next_creates_the_record_or_leaves_the_index_unchanged() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let mut index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    let ticket = Ticket {
+        id: 0,
+        seat: "A1".to_string(),
+    };
+
+    let (address, _) = next_pda::<Ticket>(program_id, &index.data, Ticket::TABLE_NAME).unwrap();
+    let (id, record) = index
+        .create_next(
+            ticket.clone(),
+            ticket_account(address, &ticket),
+            signer(u64::MAX),
+        )
+        .unwrap();
+    assert_eq!(id, 1);
+    assert_eq!(record.data.id, 1);
+    assert_eq!(record.owner(), program_id);
+    let written = Ticket::try_from_slice(&record.account_info.try_borrow_data().unwrap()).unwrap();
+    assert_eq!((written.id, written.seat.as_str()), (1, "A1"));
+    assert_eq!(record.index.get_count(Ticket::TABLE_NAME), Some(1));
+    let generation = index.generation();
+
+    // A failed creation spends no id: the index is neither mutated in memory
+    // nor written.
+    let (address, _) = next_pda::<Ticket>(program_id, &index.data, Ticket::TABLE_NAME).unwrap();
+    assert_eq!(
+        index
+            .create_next(ticket.clone(), ticket_account(address, &ticket), signer(0))
+            .err(),
+        Some(ProgramError::InsufficientFunds)
+    );
+    assert_eq!(
+        index
+            .create_next(
+                ticket.clone(),
+                ticket_account(Pubkey::new_unique(), &ticket),
+                signer(u64::MAX)
+            )
+            .err(),
+        Some(NautilusError::AddressMismatch(String::default(), String::default()).into())
+    );
+    assert_eq!(index.get_count(Ticket::TABLE_NAME), Some(1));
+    assert_eq!(index.generation(), generation);
+    let reloaded = NautilusIndex::load(program_id, index.account_info.clone()).unwrap();
+    assert_eq!(reloaded.get_count(Ticket::TABLE_NAME), Some(1));
+    assert_eq!(reloaded.generation(), generation);
+
+    let (id, _) = index
+        .create_next(
+            ticket.clone(),
+            ticket_account(address, &ticket),
+            signer(u64::MAX),
+        )
+        .unwrap();
+    assert_eq!(id, 2);
+}
+
+#[test]
+fn create_next_receipt_sums_the_record_and_index_rent() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let mut index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    let ticket = Ticket {
+        id: 0,
+        seat: "A1".to_string(),
+    };
+    let payer = signer(u64::MAX);
+    let index_before = index.lamports();
+
+    let (address, _) = next_pda::<Ticket>(program_id, &index.data, Ticket::TABLE_NAME).unwrap();
+    let (_, record, receipt) = index
+        .create_next_with_receipt(
+            ticket.clone(),
+            ticket_account(address, &ticket),
+            payer.clone(),
+        )
+        .unwrap();
+    let record_rent = minimum_balance(ticket.span().unwrap()).unwrap();
+    assert_eq!(record.lamports(), record_rent);
+    assert_eq!(
+        receipt,
+        LamportReceipt {
+            rent_paid: record_rent + record.index.lamports() - index_before,
+            ..LamportReceipt::default()
+        }
+    );
+    assert_eq!(receipt.net_paid(), (u64::MAX - payer.lamports()) as i128);
+}
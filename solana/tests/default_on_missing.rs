@@ -0,0 +1,59 @@
+mod v1 {
+    use nautilus::*;
+
+    #[derive(Table)]
+    pub struct Person {
+        #[primary_key(autoincrement = true)]
+        pub id: u32,
+        pub name: String,
+    }
+}
+
+mod v2 {
+    use nautilus::*;
+
+    #[derive(Table)]
+    pub struct Person {
+        #[primary_key(autoincrement = true)]
+        pub id: u32,
+        pub name: String,
+        #[default_on_missing]
+        pub age: u8,
+        #[default_on_missing]
+        pub nickname: Option<String>,
+    }
+}
+
+use nautilus::{BorshDeserialize, BorshSerialize};
+
+#[test]
+fn missing_trailing_fields_are_defaulted() {
+    let legacy = v1::Person {
+        id: 1,
+        name: "Joe".to_string(),
+    }
+    .try_to_vec()
+    .unwrap();
+    let person = v2::Person::try_from_slice(&legacy).unwrap();
+    assert_eq!(person.id, 1);
+    assert_eq!(person.name, "Joe");
+    assert_eq!(person.age, 0);
+    assert_eq!(person.nickname, None);
+    let view = v2::PersonView::new(&legacy).unwrap();
+    assert_eq!(view.name().unwrap(), "Joe");
+    assert_eq!(view.age().unwrap(), 0);
+    assert_eq!(view.nickname().unwrap(), None);
+
+    let bytes = v2::Person {
+        id: 2,
+        name: "Jane".to_string(),
+        age: 30,
+        nickname: Some("J".to_string()),
+    }
+    .try_to_vec()
+    .unwrap();
+    let person = v2::Person::try_from_slice(&bytes).unwrap();
+    assert_eq!(person.age, 30);
+    assert_eq!(person.nickname, Some("J".to_string()));
+    assert!(v2::Person::try_from_slice(&bytes[..bytes.len() - 1]).is_err());
+}
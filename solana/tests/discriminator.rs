@@ -0,0 +1,134 @@
+mod common;
+
+use common::{account_info, leak_key};
+use nautilus::*;
+
+#[derive(Table)]
+pub struct Person {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Table)]
+pub struct Car {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    pub name: String,
+}
+
+#[test]
+fn derived_serialization_follows_the_discriminator_convention() {
+    let person = Person {
+        id: 1,
+        name: "Joe".to_string(),
+    };
+    let bytes = person.try_to_vec().unwrap();
+    let fields = (1u32, "Joe".to_string()).try_to_vec().unwrap();
+    assert_eq!(bytes, prefix_discriminator(person.discriminator(), &fields));
+    assert_eq!(&bytes[..8], &person.discriminator());
+
+    let loaded = Person::try_from_slice(&bytes).unwrap();
+    assert_eq!(loaded.id, 1);
+    assert_eq!(loaded.name, "Joe");
+    // A `Car` has the same fields, but not the same discriminator.
+    assert!(Car::try_from_slice(&bytes).is_err());
+    assert!(Person::try_from_slice(&fields).is_err());
+}
+
+/// A program that opts out of the discriminator prefix.
+mod unprefixed {
+    use nautilus::*;
+
+    pub const DISCRIMINATOR_PREFIXED: bool = false;
+
+    #[derive(Table)]
+    pub struct Person {
+        #[primary_key(autoincrement = true)]
+        pub id: u32,
+        pub name: String,
+    }
+}
+
+#[test]
+fn a_programs_own_const_overrides_the_discriminator_convention() {
+    let person = unprefixed::Person {
+        id: 1,
+        name: "Joe".to_string(),
+    };
+    let bytes = person.try_to_vec().unwrap();
+    assert_eq!(bytes, (1u32, "Joe".to_string()).try_to_vec().unwrap());
+    assert_eq!(
+        unprefixed::Person::max_span(),
+        Person::max_span().map(|span| span - 8)
+    );
+    let loaded = unprefixed::Person::try_from_slice(&bytes).unwrap();
+    assert_eq!(loaded.id, 1);
+    assert_eq!(loaded.name, "Joe");
+}
+
+#[test]
+fn index_follows_the_discriminator_convention_it_is_loaded_under() {
+    let mut data = NautilusIndexData::default();
+    data.add_record("person");
+    let prefixed = data.try_to_vec().unwrap();
+    assert_eq!(&prefixed[..8], &data.discriminator());
+    assert_eq!(prefixed.len(), data.span().unwrap());
+
+    let mut unprefixed_data = NautilusIndexData::try_from_slice(&prefixed).unwrap();
+    unprefixed_data.unprefixed_discriminator = true;
+    let unprefixed = unprefixed_data.try_to_vec().unwrap();
+    assert_eq!(unprefixed, prefixed[8..]);
+    assert_eq!(unprefixed.len(), unprefixed_data.span().unwrap());
+
+    let loaded =
+        NautilusIndexData::try_from_slice_with_discriminator_prefix(&unprefixed, false).unwrap();
+    assert_eq!(loaded.get_count("person"), Some(1));
+    assert_eq!(loaded.try_to_vec().unwrap(), unprefixed);
+    // Data without the index's discriminator isn't the index.
+    let mut other = prefixed.clone();
+    other[0] ^= 1;
+    assert!(NautilusIndexData::try_from_slice(&other).is_err());
+
+    // Every loader of the `NautilusIndex` reads its account under the same
+    // convention.
+    let program_id = leak_key(Pubkey::new_unique());
+    let account = Box::new(account_info(
+        leak_key(Pubkey::new_unique()),
+        program_id,
+        0,
+        unprefixed.clone(),
+        false,
+        true,
+    ));
+    assert!(NautilusIndex::load(program_id, account.clone()).is_err());
+    assert!(NautilusIndex::load_from_slice(program_id, account.clone(), &unprefixed).is_err());
+    let loaded =
+        NautilusIndex::load_with_discriminator_prefix(program_id, account.clone(), false).unwrap();
+    assert_eq!(loaded.get_count("person"), Some(1));
+    let loaded = NautilusIndex::load_from_slice_with_discriminator_prefix(
+        program_id,
+        account,
+        &unprefixed,
+        false,
+    )
+    .unwrap();
+    assert_eq!(loaded.get_count("person"), Some(1));
+    assert_eq!(loaded.span().unwrap(), unprefixed.len());
+}
+
+#[test]
+fn distinct_table_names_have_distinct_discriminators() {
+    assert_no_discriminator_collisions(&[
+        Person::TABLE_NAME,
+        Car::TABLE_NAME,
+        NautilusIndexData::TABLE_NAME,
+        IndexChangelogData::TABLE_NAME,
+    ]);
+}
+
+#[test]
+#[should_panic(expected = "Discriminator collision: `person` and `person`")]
+fn repeated_table_name_is_a_collision() {
+    assert_no_discriminator_collisions(&["person", "car", "person"]);
+}
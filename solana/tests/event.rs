@@ -0,0 +1,70 @@
+mod common;
+
+use common::Hero;
+use nautilus::*;
+
+#[test]
+fn record_events_encode_to_the_documented_layout() {
+    let address = Pubkey::new_unique();
+    let hero = Hero {
+        id: 7,
+        name: "Ada".to_string(),
+    };
+    let event = RecordEvent {
+        kind: RecordEventKind::Updated,
+        table_name: Hero::TABLE_NAME.to_string(),
+        id: hero.primary_key(),
+        address,
+        payload: hero.try_to_vec().unwrap(),
+    };
+    let bytes = event.encode();
+
+    let mut expected = b"NTLS".to_vec();
+    expected.extend([EVENT_VERSION, 1]);
+    expected.extend(4u32.to_le_bytes());
+    expected.extend(b"hero");
+    expected.extend(4u32.to_le_bytes());
+    expected.extend(7u32.to_le_bytes());
+    expected.extend(address.to_bytes());
+    expected.extend(hero.try_to_vec().unwrap());
+    assert_eq!(bytes, expected);
+    assert_eq!(RecordEvent::decode(&bytes), Some(event.clone()));
+
+    let deleted = RecordEvent {
+        kind: RecordEventKind::Deleted,
+        payload: vec![],
+        ..event
+    };
+    assert_eq!(RecordEvent::decode(&deleted.encode()), Some(deleted));
+    assert_eq!(
+        emit_record_event(RecordEventKind::Created, &address, &hero),
+        Ok(())
+    );
+}
+
+#[test]
+fn record_event_decoding_rejects_foreign_bytes() {
+    let event = RecordEvent {
+        kind: RecordEventKind::Created,
+        table_name: "hero".to_string(),
+        id: 1u32.to_le_bytes().to_vec(),
+        address: Pubkey::new_unique(),
+        payload: vec![],
+    };
+    let bytes = event.encode();
+    assert_eq!(RecordEvent::decode(&bytes[..bytes.len() - 1]), None);
+    assert_eq!(RecordEvent::decode(&[]), None);
+
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert_eq!(RecordEvent::decode(&wrong_magic), None);
+    let mut unknown_version = bytes.clone();
+    unknown_version[4] = EVENT_VERSION + 1;
+    assert_eq!(RecordEvent::decode(&unknown_version), None);
+    let mut unknown_kind = bytes.clone();
+    unknown_kind[5] = 3;
+    assert_eq!(RecordEvent::decode(&unknown_kind), None);
+    let mut overlong_name = bytes;
+    overlong_name[6..10].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(RecordEvent::decode(&overlong_name), None);
+}
@@ -0,0 +1,93 @@
+use nautilus::{error::NautilusError, *};
+
+#[derive(Table)]
+pub struct Listing {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    #[nautilus(authority_is_payer)]
+    pub seller: Pubkey,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub price: Decimal<2>,
+    pub expires: Option<u64>,
+}
+
+#[test]
+fn size_for_fields_matches_the_span_of_the_instance() {
+    let listing = Listing {
+        id: 7,
+        seller: Pubkey::new_unique(),
+        title: "Bicycle".to_string(),
+        tags: vec!["used".to_string(), "blue".to_string()],
+        price: Decimal::new(12_50),
+        expires: Some(100),
+    };
+    let mut fields = FieldValues::new();
+    fields
+        .set("title", &listing.title)
+        .unwrap()
+        .set("tags", &listing.tags)
+        .unwrap()
+        .set("price", &listing.price)
+        .unwrap()
+        .set("expires", &listing.expires)
+        .unwrap();
+    // The id and seller are filled in on create, so they're measured anyway.
+    assert_eq!(
+        Listing::size_for_fields(&fields),
+        Ok(listing.span().unwrap())
+    );
+    fields
+        .set("id", &listing.id)
+        .unwrap()
+        .set("seller", &listing.seller)
+        .unwrap();
+    assert_eq!(
+        Listing::size_for_fields(&fields),
+        Ok(listing.span().unwrap())
+    );
+
+    let empty = Listing {
+        expires: None,
+        ..Default::default()
+    };
+    fields
+        .set("title", &empty.title)
+        .unwrap()
+        .set("tags", &empty.tags)
+        .unwrap()
+        .set_serialized("expires", vec![0]);
+    assert_eq!(Listing::size_for_fields(&fields), Ok(empty.span().unwrap()));
+    assert_eq!(Listing::size_for_fields(&fields), Listing::min_span());
+}
+
+#[test]
+fn size_for_fields_rejects_missing_unknown_and_malformed_values() {
+    let mut fields = FieldValues::new();
+    fields
+        .set("title", &"Bicycle".to_string())
+        .unwrap()
+        .set("tags", &Vec::<String>::new())
+        .unwrap()
+        .set("price", &Decimal::<2>::new(1))
+        .unwrap();
+    assert_eq!(
+        Listing::size_for_fields(&fields),
+        Err(NautilusError::MissingFieldValue(String::default(), String::default()).into())
+    );
+
+    let invalid =
+        Err(NautilusError::InvalidFieldValue(String::default(), String::default()).into());
+    let mut unknown = fields.clone();
+    unknown.set("color", &1u8).unwrap();
+    assert_eq!(Listing::size_for_fields(&unknown), invalid);
+    fields.set("expires", &1u32).unwrap();
+    assert_eq!(Listing::size_for_fields(&fields), invalid);
+    fields.set("expires", &Some(1u64)).unwrap();
+    assert_eq!(
+        Listing::size_for_fields(&fields),
+        Ok(Listing::min_span().unwrap() + "Bicycle".len() + 8)
+    );
+    fields.set_serialized("title", vec![9, 0, 0, 0, b'x']);
+    assert_eq!(Listing::size_for_fields(&fields), invalid);
+}
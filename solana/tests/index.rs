@@ -0,0 +1,1409 @@
+mod common;
+
+use common::{
+    account_info, clear_observer, install_runtime, leak_key, observe_invocations,
+    reallocatable_account_info, signer,
+};
+use nautilus::solana_program::clock::Clock;
+use nautilus::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use nautilus::{error::NautilusError, *};
+
+#[test]
+fn index_txn_errors_before_exceeding_realloc_limit() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let mut index = NautilusIndex::new(&program_id, Box::new(account_info));
+
+    let mut tables = 0;
+    {
+        let mut txn = index.txn();
+        let err = loop {
+            match txn.add_record(&format!("table_{:04}", tables)) {
+                Ok(count) => assert_eq!(count, 1),
+                Err(err) => break err,
+            }
+            tables += 1;
+        };
+        assert_eq!(
+            err,
+            NautilusError::ReallocTooLarge(String::default(), 0).into()
+        );
+        assert!(txn.realloc_delta().unwrap() <= MAX_PERMITTED_DATA_INCREASE);
+        assert!(txn.realloc_remaining().unwrap() < 4 + "table_0000".len() + 4);
+    }
+    assert_eq!(index.data.index.len(), tables);
+    assert_eq!(index.get_count(&format!("table_{:04}", tables)), None);
+}
+
+#[test]
+fn index_rent_calculator_override() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let index = NautilusIndex::new(&program_id, Box::new(account_info))
+        .with_rent_calculator(|span| Ok(span as u64 * 1_000));
+    assert_eq!(
+        index.required_rent().unwrap(),
+        index.span().unwrap() as u64 * 1_000
+    );
+    assert_eq!(
+        Mut::new(index.clone()).unwrap().required_rent().unwrap(),
+        index.required_rent().unwrap()
+    );
+}
+
+#[test]
+fn index_data_metadata_is_backward_compatible() {
+    let mut legacy = NautilusIndexData::default().discriminator().to_vec();
+    let mut counts = std::collections::HashMap::new();
+    counts.insert("person".to_string(), 3u32);
+    counts.insert("car".to_string(), 1u32);
+    legacy.extend(counts.try_to_vec().unwrap());
+
+    let mut data = NautilusIndexData::try_from_slice(&legacy).unwrap();
+    assert_eq!(data.get_count("person"), Some(3));
+    assert_eq!(data.get_metadata("person"), Some(&[][..]));
+    assert_eq!(data.try_to_vec().unwrap(), legacy);
+
+    data.set_metadata("person", vec![2, 0]);
+    data.set_metadata("home", vec![7]);
+    let bytes = data.try_to_vec().unwrap();
+    assert!(bytes.len() > legacy.len());
+    let decoded = NautilusIndexData::try_from_slice(&bytes).unwrap();
+    assert_eq!(decoded.get_count("person"), Some(3));
+    assert_eq!(decoded.get_metadata("person"), Some(&[2, 0][..]));
+    assert_eq!(decoded.get_count("home"), Some(0));
+    assert_eq!(decoded.get_metadata("home"), Some(&[7][..]));
+
+    data.set_metadata("person", vec![]);
+    data.set_metadata("home", vec![]);
+    data.index.remove("home");
+    data.generation = 0;
+    assert_eq!(data.try_to_vec().unwrap(), legacy);
+}
+
+#[test]
+fn index_data_generation_is_bumped_by_mutations() {
+    let mut legacy = NautilusIndexData::default().discriminator().to_vec();
+    let mut counts = std::collections::HashMap::new();
+    counts.insert("person".to_string(), 3u32);
+    legacy.extend(counts.try_to_vec().unwrap());
+
+    let mut data = NautilusIndexData::try_from_slice(&legacy).unwrap();
+    assert_eq!(data.generation(), 0);
+    assert_eq!(data.add_record_growth("person"), 4 + 8);
+
+    data.add_record("person");
+    assert_eq!(data.generation(), 1);
+    assert_eq!(data.add_record_growth("person"), 0);
+    let bytes = data.try_to_vec().unwrap();
+    assert_eq!(bytes.len(), legacy.len() + 4 + 8);
+
+    assert_eq!(data.remove_record("car"), None);
+    assert_eq!(data.generation(), 1);
+    data.remove_record("person");
+    data.set_metadata("person", vec![1]);
+    assert_eq!(data.generation(), 3);
+
+    let decoded = NautilusIndexData::try_from_slice(&data.try_to_vec().unwrap()).unwrap();
+    assert_eq!(decoded.generation(), 3);
+    assert_eq!(decoded.get_count("person"), Some(3));
+    assert_eq!(decoded.get_metadata("person"), Some(&[1][..]));
+}
+
+#[test]
+fn index_remove_record_grows_an_index_in_the_baseline_layout() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut legacy = NautilusIndexData::default().discriminator().to_vec();
+    let mut counts = std::collections::HashMap::new();
+    counts.insert("person".to_string(), 3u32);
+    legacy.extend(counts.try_to_vec().unwrap());
+    let account = Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(legacy.len()).unwrap(),
+        legacy.clone(),
+        true,
+    ));
+    let mut index = NautilusIndex::load(program_id, account.clone()).unwrap();
+    assert_eq!(index.generation(), 0);
+
+    assert_eq!(index.remove_record("person", signer(u64::MAX)), Ok(2));
+    assert!(account.data_len() > legacy.len());
+    assert_eq!(
+        account.lamports(),
+        minimum_balance(account.data_len()).unwrap()
+    );
+    let written = NautilusIndex::load(program_id, account).unwrap();
+    assert_eq!(written.get_count("person"), Some(2));
+    assert_eq!(written.generation(), 1);
+}
+
+#[test]
+fn index_compaction_ratio_and_auto_compact_threshold() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut index_data = NautilusIndexData::default();
+    index_data.add_record("person");
+    let used = index_data.try_to_vec().unwrap();
+    let mut data = used.clone();
+    data.resize(used.len() * 4, 0);
+    let mut lamports = 0;
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let mut index = NautilusIndex::load(&program_id, Box::new(account_info)).unwrap();
+    assert_eq!(index.compaction_ratio(), 0.25);
+
+    let payer_key = Pubkey::new_unique();
+    let mut payer_lamports = 0;
+    let mut payer_data = vec![];
+    let payer = AccountInfo::new(
+        &payer_key,
+        true,
+        true,
+        &mut payer_lamports,
+        &mut payer_data,
+        &program_id,
+        false,
+        0,
+    );
+    let system_key = nautilus::solana_program::system_program::ID;
+    let mut system_lamports = 0;
+    let mut system_data = vec![];
+    let system_program = AccountInfo::new(
+        &system_key,
+        false,
+        false,
+        &mut system_lamports,
+        &mut system_data,
+        &system_key,
+        true,
+        0,
+    );
+    let fee_payer = Signer::new(Wallet {
+        account_info: Box::new(payer),
+        system_program: Box::new(system_program),
+    })
+    .unwrap();
+    index.auto_compact_if(0.25, fee_payer).unwrap();
+    assert_eq!(index.account_info.data_len(), used.len() * 4);
+    assert_eq!(index.compaction_ratio(), 0.25);
+}
+
+#[test]
+fn index_merkle_proofs_verify_table_counts() {
+    let mut data = NautilusIndexData::default();
+    assert_eq!(data.merkle_root(), [0; 32]);
+    assert_eq!(data.merkle_proof("person"), None);
+
+    for (table_name, count) in [
+        ("person", 3),
+        ("car", 1),
+        ("home", 2),
+        ("dog", 5),
+        ("cat", 4),
+    ] {
+        for _ in 0..count {
+            data.add_record(table_name);
+        }
+    }
+    let root = data.merkle_root();
+    for table_name in ["person", "car", "home", "dog", "cat"] {
+        let count = data.get_count(table_name).unwrap();
+        let proof = data.merkle_proof(table_name).unwrap();
+        assert!(verify_merkle_proof(&root, table_name, count, &proof));
+        assert!(!verify_merkle_proof(&root, table_name, count + 1, &proof));
+    }
+
+    let decoded = NautilusIndexData::try_from_slice(&data.try_to_vec().unwrap()).unwrap();
+    assert_eq!(decoded.merkle_root(), root);
+    data.add_record("car");
+    assert_ne!(data.merkle_root(), root);
+}
+
+#[test]
+fn index_export_chunks_stitch_into_full_export() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let mut index = NautilusIndex::new(&program_id, Box::new(account_info));
+    for table in 0..200 {
+        index.data.add_record(&format!("table_{:04}", table));
+    }
+    let export = index.export().unwrap();
+    assert_eq!(index.export_len().unwrap(), export.len());
+    assert!(export.len() > nautilus::solana_program::program::MAX_RETURN_DATA);
+
+    let mut stitched = vec![];
+    loop {
+        let chunk = index
+            .export_chunk(
+                stitched.len(),
+                nautilus::solana_program::program::MAX_RETURN_DATA,
+            )
+            .unwrap();
+        if chunk.is_empty() {
+            break;
+        }
+        stitched.extend(chunk);
+    }
+    assert_eq!(stitched, export);
+    let decoded = NautilusIndexData::try_from_slice(&stitched).unwrap();
+    assert_eq!(decoded.get_count("table_0199"), Some(1));
+    assert_eq!(decoded.generation(), index.generation());
+    assert!(index
+        .export_chunk(export.len() + 10, 10)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn index_data_case_insensitive_table_names() {
+    let mut data = NautilusIndexData::default();
+    assert_eq!(data.add_record("Users"), 1);
+    assert_eq!(data.add_record("users"), 1);
+    assert_eq!(data.index.len(), 2);
+
+    let mut data = NautilusIndexData {
+        case_insensitive_table_names: true,
+        ..Default::default()
+    };
+    assert_eq!(data.add_record("Users"), 1);
+    assert_eq!(data.add_record_growth("USERS"), 0);
+    assert_eq!(data.add_record("users"), 2);
+    data.set_metadata("uSeRs", vec![1]);
+    assert_eq!(data.index.len(), 1);
+    assert_eq!(data.get_count("users"), Some(2));
+    assert_eq!(data.get_metadata("Users"), Some(&[1][..]));
+    assert_eq!(data.remove_record("USERS"), Some(1));
+    assert_eq!(data.table_name("Users"), "users");
+    assert_eq!(data.merkle_proof("Users"), data.merkle_proof("users"),);
+    assert!(data.merkle_proof("Users").is_some());
+
+    // The setting isn't stored, so a reloaded index is case-sensitive again.
+    let reloaded = NautilusIndexData::try_from_slice(&data.try_to_vec().unwrap()).unwrap();
+    assert!(!reloaded.case_insensitive_table_names);
+    assert_eq!(reloaded.get_count("users"), Some(1));
+    assert_eq!(reloaded.get_count("Users"), None);
+}
+
+#[test]
+fn index_simulated_mutations_leave_index_untouched() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut data = NautilusIndexData::default();
+    data.add_record("person");
+    data.last_mutation_slot = 1;
+    let mut data = data.try_to_vec().unwrap();
+    let span = data.len();
+    let mut lamports = span as u64 * 1_000;
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let index = NautilusIndex::load(&program_id, Box::new(account_info))
+        .unwrap()
+        .with_rent_calculator(|span| Ok(span as u64 * 1_000));
+
+    let growth = index.data.add_record_growth("car");
+    assert_eq!(
+        index
+            .simulate_add_record("car", &Pubkey::default())
+            .unwrap(),
+        MutationPlan {
+            count: 1,
+            span: span + growth,
+            realloc_size: growth,
+            lamport_delta: growth as u64 * 1_000,
+        }
+    );
+    assert_eq!(
+        index
+            .simulate_add_record("person", &Pubkey::default())
+            .unwrap(),
+        MutationPlan {
+            count: 2,
+            span,
+            realloc_size: 0,
+            lamport_delta: 0,
+        }
+    );
+    assert_eq!(
+        index.simulate_remove_record("person").unwrap(),
+        MutationPlan {
+            count: 0,
+            span,
+            realloc_size: 0,
+            lamport_delta: 0,
+        }
+    );
+    assert_eq!(
+        index.simulate_remove_record("car"),
+        Err(NautilusError::TableNotFound(String::default()).into())
+    );
+    assert_eq!(index.get_count("person"), Some(1));
+    assert_eq!(index.get_count("car"), None);
+    assert_eq!(index.generation(), 1);
+    assert_eq!(index.lamports(), span as u64 * 1_000);
+}
+
+#[test]
+fn index_load_from_slice_while_account_data_is_borrowed() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut data = NautilusIndexData::default();
+    data.add_record("person");
+    let mut data = data.try_to_vec().unwrap();
+    let mut lamports = 0;
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+
+    let mut view = account_info.data.borrow_mut();
+    let snapshot = view.to_vec();
+    assert_eq!(
+        NautilusIndex::load(&program_id, Box::new(account_info.clone())).err(),
+        Some(NautilusError::LoadDataFailed(String::default(), String::default()).into())
+    );
+    let index =
+        NautilusIndex::load_from_slice(&program_id, Box::new(account_info.clone()), &snapshot)
+            .unwrap();
+    assert_eq!(index.get_count("person"), Some(1));
+    view[0] ^= 1;
+
+    assert_eq!(
+        NautilusIndex::load_from_slice(&program_id, Box::new(account_info.clone()), &[0; 4]).err(),
+        Some(NautilusError::DeserializeDataFailed(String::default(), String::default()).into())
+    );
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn index_starting_counts_make_ids_reproducible() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let mut index = NautilusIndex::new_with_starting_counts(
+        &program_id,
+        Box::new(account_info),
+        &[("person", 100)],
+    );
+    assert_eq!(index.get_next_count("person"), 101);
+    assert_eq!(index.get_next_count("car"), 1);
+    let mut txn = index.txn();
+    assert_eq!(txn.add_record("person").unwrap(), 101);
+    assert_eq!(txn.add_record("person").unwrap(), 102);
+}
+#[test]
+fn index_data_move_table_sums_into_dest() {
+    let mut source = NautilusIndexData::default();
+    source.add_record("person");
+    source.add_record("person");
+    source.add_record("car");
+    source.set_metadata("person", vec![1]);
+    source
+        .unique_values
+        .entry(("person".to_string(), "email".to_string()))
+        .or_default()
+        .insert(b"joe@example.com".to_vec(), vec![1]);
+    let mut dest = NautilusIndexData::default();
+    dest.add_record("person");
+
+    assert_eq!(source.move_table("person", &mut dest), Some(3));
+    assert_eq!(source.get_count("person"), None);
+    assert_eq!(source.get_count("car"), Some(1));
+    assert!(source.unique_values.is_empty());
+    assert_eq!(dest.get_count("person"), Some(3));
+    assert_eq!(dest.get_metadata("person"), Some(&[1][..]));
+    assert_eq!(
+        dest.get_unique_owner("person", "email", b"joe@example.com"),
+        Some(&[1][..])
+    );
+    assert_eq!(source.generation(), 5);
+    assert_eq!(dest.generation(), 2);
+
+    assert_eq!(source.move_table("person", &mut dest), None);
+    assert_eq!(source.generation(), 5);
+}
+
+#[test]
+fn index_move_missing_table_fails() {
+    let program_id = Pubkey::new_unique();
+    let (source_key, dest_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut source_lamports, mut dest_lamports) = (0, 0);
+    let (mut source_data, mut dest_data) = (vec![], vec![]);
+    let mut source = NautilusIndex::new(
+        &program_id,
+        Box::new(AccountInfo::new(
+            &source_key,
+            false,
+            true,
+            &mut source_lamports,
+            &mut source_data,
+            &program_id,
+            false,
+            0,
+        )),
+    );
+    let mut dest = NautilusIndex::new(
+        &program_id,
+        Box::new(AccountInfo::new(
+            &dest_key,
+            false,
+            true,
+            &mut dest_lamports,
+            &mut dest_data,
+            &program_id,
+            false,
+            0,
+        )),
+    );
+    let system_program = nautilus::system_program::ID;
+    let (payer_key, mut payer_lamports, mut payer_data) = (Pubkey::new_unique(), 0, vec![]);
+    let (mut system_lamports, mut system_data) = (0, vec![]);
+    let fee_payer = Signer::new(Wallet {
+        account_info: Box::new(AccountInfo::new(
+            &payer_key,
+            true,
+            true,
+            &mut payer_lamports,
+            &mut payer_data,
+            &system_program,
+            false,
+            0,
+        )),
+        system_program: Box::new(AccountInfo::new(
+            &system_program,
+            false,
+            false,
+            &mut system_lamports,
+            &mut system_data,
+            &system_program,
+            false,
+            0,
+        )),
+    })
+    .unwrap();
+
+    assert_eq!(
+        source.move_table("person", &mut dest, fee_payer),
+        Err(NautilusError::TableNotFound(String::default()).into())
+    );
+    assert_eq!(source.generation(), 0);
+    assert_eq!(dest.generation(), 0);
+}
+#[test]
+fn index_count_fallback_reads_account_data() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut on_chain = NautilusIndexData::default();
+    on_chain.add_record("person");
+    let mut data = on_chain.try_to_vec().unwrap();
+    let mut lamports = 0;
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let index = NautilusIndex::new(&program_id, Box::new(account_info));
+    assert_eq!(index.get_count("person"), None);
+
+    let index = index.with_count_fallback(true);
+    assert_eq!(index.get_count("person"), Some(1));
+    assert_eq!(index.get_count("car"), None);
+    assert_eq!(index.data.get_count("person"), None);
+}
+
+#[test]
+fn index_txn_reads_are_read_committed() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let mut index = NautilusIndex::new(&program_id, Box::new(account_info));
+    index.data.add_record("person");
+
+    let mut txn = index.txn();
+    txn.add_record("person").unwrap();
+    txn.add_record("person").unwrap();
+    txn.add_record("car").unwrap();
+    assert_eq!(txn.get_count("person"), Some(1));
+    assert_eq!(txn.get_count_pending("person"), Some(3));
+    assert_eq!(txn.get_count("car"), None);
+    assert_eq!(txn.get_count_pending("car"), Some(1));
+    assert_eq!(txn.get_count("truck"), None);
+}
+
+#[test]
+fn index_load_fresh_checks_last_mutation_slot() {
+    let mut legacy = NautilusIndexData::default();
+    legacy.add_record("person");
+    let legacy_bytes = legacy.try_to_vec().unwrap();
+    let mut stamped = legacy.clone();
+    assert_eq!(stamped.slot_growth(), 4 + 8);
+    stamped.last_mutation_slot = 100;
+    assert_eq!(stamped.slot_growth(), 0);
+    let mut data = stamped.try_to_vec().unwrap();
+    assert_eq!(data.len(), legacy_bytes.len() + 4 + 8);
+    assert_eq!(
+        NautilusIndexData::try_from_slice(&legacy_bytes)
+            .unwrap()
+            .last_mutation_slot(),
+        0
+    );
+
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let clock = Clock {
+        slot: 150,
+        ..Clock::default()
+    };
+    let index =
+        NautilusIndex::load_fresh(&program_id, Box::new(account_info.clone()), &clock, 50).unwrap();
+    assert_eq!(index.data.last_mutation_slot(), 100);
+    assert_eq!(index.get_count("person"), Some(1));
+    assert_eq!(
+        NautilusIndex::load_fresh(&program_id, Box::new(account_info), &clock, 49).err(),
+        Some(NautilusError::StaleIndex(String::default(), 0, 0).into())
+    );
+}
+
+#[test]
+fn index_deltas_since_a_generation() {
+    let mut data = NautilusIndexData::default();
+    data.add_record("person");
+    assert_eq!(data.changes_since(0), None);
+    let disabled_span = data.span().unwrap();
+
+    data.enable_deltas(2);
+    assert_eq!(data.changes_since(0), None);
+    let synced = data.generation();
+    assert_eq!(data.changes_since(synced), Some(vec![]));
+    data.add_record("person");
+    data.add_record("car");
+    assert_eq!(
+        data.changes_since(synced),
+        Some(vec![
+            CountDelta::new(synced + 1, "person", 2),
+            CountDelta::new(synced + 2, "car", 1),
+        ])
+    );
+    assert_eq!(
+        data.changes_since(synced + 1),
+        Some(vec![CountDelta::new(synced + 2, "car", 1)])
+    );
+
+    let span = data.span().unwrap();
+    data.remove_record("person");
+    assert_eq!(data.span().unwrap(), span);
+    assert_eq!(data.changes_since(synced), None);
+    assert_eq!(
+        data.changes_since(synced + 1).unwrap()[1].table_name(),
+        "person"
+    );
+
+    let reloaded = NautilusIndexData::try_from_slice(&data.try_to_vec().unwrap()).unwrap();
+    assert_eq!(reloaded.deltas, data.deltas);
+    assert!(span > disabled_span);
+}
+
+#[test]
+fn index_pda_matches_nautilus_index_derivation() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let index = NautilusIndex::new(&program_id, Box::new(account_info));
+    assert_eq!(nautilus_index_pda(&program_id), index.pda());
+    assert_eq!(
+        nautilus_index_pda(&program_id),
+        Pubkey::find_program_address(&[b"nautilus_index", &[0]], &program_id)
+    );
+    assert_ne!(
+        nautilus_index_pda(&program_id),
+        nautilus_index_pda(&Pubkey::new_unique())
+    );
+}
+
+#[test]
+fn index_init_tables_adds_empty_entries_once() {
+    let mut data = NautilusIndexData::default();
+    data.add_record("person");
+    assert_eq!(data.init_tables(&["person", "car", "home"]), 2);
+    assert_eq!(data.get_count("person"), Some(1));
+    assert_eq!(data.get_count("car"), Some(0));
+    assert_eq!(data.get_next_count("home"), 1);
+    assert_eq!(data.add_record_growth("car"), 0);
+    assert_eq!(data.generation(), 2);
+
+    assert_eq!(data.init_tables(&["car", "home"]), 0);
+    assert_eq!(data.generation(), 2);
+}
+
+#[test]
+fn index_list_tables_pages_in_name_order() {
+    let mut data = NautilusIndexData::default();
+    for table_name in ["person", "car", "home", "car", "boat"] {
+        data.add_record(table_name);
+    }
+    assert_eq!(
+        data.list_tables_page(0, 2),
+        (vec![("boat".to_string(), 1), ("car".to_string(), 2)], true)
+    );
+    assert_eq!(
+        data.list_tables_page(2, 2),
+        (
+            vec![("home".to_string(), 1), ("person".to_string(), 1)],
+            false
+        )
+    );
+    assert_eq!(data.list_tables_page(4, 2), (vec![], false));
+    assert_eq!(data.list_tables_page(1, 0), (vec![], true));
+    assert_eq!(
+        data.list_tables_page(usize::MAX, usize::MAX),
+        (vec![], false)
+    );
+}
+
+#[test]
+fn index_tables_where_filters_by_count_in_name_order() {
+    let mut data = NautilusIndexData::default();
+    for table_name in ["person", "car", "home", "car", "boat", "car", "home"] {
+        data.add_record(table_name);
+    }
+    data.init_tables(&["truck", "bike"]);
+    assert_eq!(
+        data.tables_where(|count| count > 1),
+        vec![("car".to_string(), 3), ("home".to_string(), 2)]
+    );
+    assert_eq!(
+        data.tables_where(|count| count == 0),
+        vec![("bike".to_string(), 0), ("truck".to_string(), 0)]
+    );
+    assert_eq!(data.tables_where(|count| count > 3), vec![]);
+    assert_eq!(
+        data.tables_where(|_| true),
+        data.list_tables_page(0, usize::MAX).0
+    );
+}
+
+#[test]
+fn index_assert_id_in_range_at_the_boundaries() {
+    let program_id = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    let mut index = NautilusIndex::new(&program_id, Box::new(account_info));
+    index.data.add_record("person");
+    index.data.add_record("person");
+    let out_of_range = Err(NautilusError::IdOutOfRange(String::default(), 0, 0).into());
+    assert_eq!(index.assert_id_in_range("person", 0), out_of_range);
+    assert_eq!(index.assert_id_in_range("person", 1), Ok(()));
+    assert_eq!(index.assert_id_in_range("person", 2), Ok(()));
+    assert_eq!(index.assert_id_in_range("person", 3), out_of_range);
+    assert_eq!(index.assert_id_in_range("car", 1), out_of_range);
+}
+
+#[test]
+fn index_close_record_only_decrements_for_the_tail() {
+    let mut data = NautilusIndexData::default();
+    for _ in 0..3 {
+        data.add_record("person");
+    }
+    assert!(data.is_tail_record("person", 3));
+    assert!(!data.is_tail_record("person", 2));
+    assert!(!data.is_tail_record("person", 0));
+    assert!(!data.is_tail_record("car", 0));
+
+    let generation = data.generation();
+    assert_eq!(data.close_record("person", 2), Some(3));
+    assert_eq!(data.generation(), generation);
+    assert_eq!(data.close_record("person", 3), Some(2));
+    assert_eq!(data.add_record("person"), 3);
+    assert_eq!(data.close_record("car", 1), None);
+
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    index.data = data;
+    assert!(index.is_tail_record("person", 3));
+    assert_eq!(index.close_record("person", 1, signer(0)), Ok(3));
+    assert_eq!(
+        index.close_record("car", 1, signer(0)),
+        Err(NautilusError::TableNotFound(String::default()).into())
+    );
+}
+
+#[test]
+fn index_freeze_blocks_mutations_and_requires_the_admin() {
+    let admin = signer(0);
+    let mut data = NautilusIndexData::default();
+    data.add_record("person");
+    data.admin = *admin.key();
+    data.frozen = true;
+    let bytes = data.try_to_vec().unwrap();
+    let loaded = NautilusIndexData::try_from_slice(&bytes).unwrap();
+    assert_eq!(loaded.admin, *admin.key());
+    assert!(loaded.is_frozen());
+
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            bytes,
+            false,
+            true,
+        )),
+    );
+    index.data = loaded;
+    let frozen = Err(NautilusError::IndexFrozen(String::default()).into());
+    assert!(index.is_frozen());
+    assert_eq!(index.add_record("person", signer(0)).map(|_| ()), frozen);
+    assert_eq!(index.remove_record("person", signer(0)).map(|_| ()), frozen);
+    assert_eq!(index.set_metadata("person", vec![1], signer(0)), frozen);
+    assert_eq!(
+        index
+            .simulate_add_record("person", &Pubkey::default())
+            .map(|_| ()),
+        frozen
+    );
+    assert_eq!(index.get_count("person"), Some(1));
+
+    let not_admin = Err(NautilusError::NotIndexAdmin(String::default(), String::default()).into());
+    assert_eq!(index.unfreeze(signer(0)), not_admin);
+    assert_eq!(index.set_admin(&Pubkey::new_unique(), signer(0)), not_admin);
+    index.data.admin = Pubkey::default();
+    assert_eq!(index.freeze(admin), not_admin);
+}
+
+#[test]
+fn index_projected_rent_matches_rent_after_inserts() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut data = NautilusIndexData::default();
+    data.add_record("person");
+    let index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            data.try_to_vec().unwrap(),
+            false,
+            true,
+        )),
+    )
+    .with_rent_calculator(|span| Ok(span as u64 * 1_000));
+    let mut index = NautilusIndex { data, ..index };
+    assert_eq!(
+        index.projected_rent_after("car", 0).unwrap(),
+        index.required_rent().unwrap()
+    );
+    let projected = [
+        index.projected_rent_after("person", 3).unwrap(),
+        index.projected_rent_after("car", 3).unwrap(),
+    ];
+    assert!(projected[1] > projected[0]);
+    assert!(index.projected_rent_after(&"t".repeat(33), 1).is_err());
+
+    let mut person = index.data.clone();
+    for (table_name, projected) in [("person", projected[0]), ("car", projected[1])] {
+        index.data = person.clone();
+        for _ in 0..3 {
+            index.data.add_record(table_name);
+        }
+        index.data.last_mutation_slot = 1;
+        assert_eq!(index.required_rent().unwrap(), projected);
+    }
+    person.last_mutation_slot = 1;
+    index.data = person;
+    assert_eq!(
+        index.projected_rent_after("person", 3).unwrap(),
+        projected[0]
+    );
+}
+
+#[test]
+fn index_copies_keep_each_others_increments() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let account = Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(data.len()).unwrap(),
+        data,
+        true,
+    ));
+    // Each handler loads its own copy of the index before any of them writes,
+    // and the first addition to each table creates it.
+    let mut first = NautilusIndex::load(program_id, account.clone()).unwrap();
+    let mut second = NautilusIndex::load(program_id, account.clone()).unwrap();
+    let mut third = NautilusIndex::load(program_id, account.clone()).unwrap();
+    assert_eq!(first.add_record("orders", signer(u64::MAX)), Ok(1));
+    assert_eq!(second.add_record("orders", signer(u64::MAX)), Ok(2));
+    assert_eq!(second.add_record("items", signer(u64::MAX)), Ok(1));
+    let mut txn = third.txn();
+    assert_eq!(txn.add_record("orders"), Ok(3));
+    assert_eq!(txn.add_record("items"), Ok(2));
+    txn.commit(signer(u64::MAX)).unwrap();
+    assert_eq!(first.close_record("orders", 1, signer(u64::MAX)), Ok(3));
+
+    let index = NautilusIndex::load(program_id, account).unwrap();
+    assert_eq!(index.get_count("orders"), Some(3));
+    assert_eq!(index.get_count("items"), Some(2));
+    assert_eq!(index.generation(), 5);
+}
+
+#[test]
+fn index_id_strides_space_out_assigned_ids() {
+    let mut data = NautilusIndexData::default();
+    assert_eq!(data.get_id_stride("person"), IdStride::default());
+    assert_eq!(data.get_next_count("person"), 1);
+    assert!(data.set_id_stride(
+        "person",
+        IdStride {
+            start: 3,
+            stride: 4
+        }
+    ));
+    assert_eq!(data.get_next_count("person"), 3);
+    assert_eq!(data.add_record("person"), 3);
+    assert_eq!(data.add_record("person"), 7);
+    assert_eq!(data.add_record("person"), 11);
+    assert_eq!(data.get_count("person"), Some(3));
+    assert_eq!(data.get_next_count("person"), 15);
+    assert_eq!(data.add_record("car"), 1);
+
+    // Strides can only be set on tables that haven't assigned ids yet.
+    assert!(!data.set_id_stride("person", IdStride::default()));
+    assert!(!data.set_id_stride(
+        "car",
+        IdStride {
+            start: 2,
+            stride: 2
+        }
+    ));
+    assert!(!data.set_id_stride(
+        "house",
+        IdStride {
+            start: 0,
+            stride: 2
+        }
+    ));
+    assert!(!data.set_id_stride(
+        "house",
+        IdStride {
+            start: 1,
+            stride: 0
+        }
+    ));
+
+    let data = NautilusIndexData::try_from_slice(&data.try_to_vec().unwrap()).unwrap();
+    assert_eq!(
+        data.get_id_stride("person"),
+        IdStride {
+            start: 3,
+            stride: 4
+        }
+    );
+    assert_eq!(data.get_id_stride("car"), IdStride::default());
+    let mut index = NautilusIndex::new(
+        leak_key(Pubkey::new_unique()),
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            leak_key(Pubkey::new_unique()),
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    index.data = data;
+    assert!(index.is_tail_record("person", 11));
+    assert!(!index.is_tail_record("person", 3));
+    assert!(!index.is_tail_record("person", 12));
+    assert_eq!(index.assert_id_in_range("person", 7), Ok(()));
+    for id in [0, 1, 8, 15] {
+        assert_eq!(
+            index.assert_id_in_range("person", id),
+            Err(NautilusError::IdOutOfRange(String::default(), 0, 0).into())
+        );
+    }
+}
+
+#[test]
+fn index_exhausted_id_space_rejects_additions() {
+    install_runtime();
+    let mut data = NautilusIndexData::default();
+    assert!(data.set_id_stride(
+        "person",
+        IdStride {
+            start: u32::MAX - 1,
+            stride: 10
+        }
+    ));
+    assert_eq!(data.add_record("person"), u32::MAX - 1);
+    assert_eq!(data.get_next_count("person"), 0);
+    assert_eq!(data.add_record("person"), 0);
+    assert_eq!(data.get_count("person"), Some(1));
+
+    let program_id = leak_key(Pubkey::new_unique());
+    let bytes = data.try_to_vec().unwrap();
+    let account = Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(bytes.len()).unwrap(),
+        bytes,
+        true,
+    ));
+    let mut index = NautilusIndex::load(program_id, account).unwrap();
+    assert_eq!(
+        index.add_record("person", signer(u64::MAX)),
+        Err(NautilusError::IdSpaceExhausted(String::default()).into())
+    );
+    assert_eq!(
+        index.txn().add_record("person"),
+        Err(NautilusError::IdSpaceExhausted(String::default()).into())
+    );
+    assert_eq!(index.add_record("car", signer(u64::MAX)), Ok(1));
+    assert_eq!(index.get_count("person"), Some(1));
+}
+
+#[test]
+fn index_data_span_matches_serialized_length() {
+    let assert_span = |data: &NautilusIndexData| {
+        assert_eq!(data.span().unwrap(), data.try_to_vec().unwrap().len());
+    };
+    let mut data = NautilusIndexData::default();
+    assert_span(&data);
+
+    let old_span = data.span().unwrap();
+    let growth = data.add_record_growth("person");
+    data.add_record("person");
+    assert_eq!(data.span().unwrap(), old_span + growth);
+    assert_eq!(growth, NautilusIndexData::entry_span("person") + 4 + 8);
+    assert_span(&data);
+    data.add_record("person");
+    assert_eq!(data.span().unwrap(), old_span + growth);
+
+    let old_span = data.span().unwrap();
+    let growth = data.add_record_growth("car");
+    data.add_record("car");
+    assert_eq!(data.span().unwrap(), old_span + growth);
+    assert_span(&data);
+
+    data.set_metadata("car", vec![1, 2, 3]);
+    assert_span(&data);
+    data.unique_values
+        .entry(("person".to_string(), "email".to_string()))
+        .or_default()
+        .insert(b"a@b.c".to_vec(), vec![1, 0, 0, 0]);
+    data.unique_values
+        .entry(("car".to_string(), "vin".to_string()))
+        .or_default();
+    assert_span(&data);
+    data.last_mutation_slot = 7;
+    assert_span(&data);
+    data.enable_deltas(3);
+    data.add_record("person");
+    assert_span(&data);
+    data.admin = Pubkey::new_unique();
+    assert_span(&data);
+    data.frozen = true;
+    assert_span(&data);
+    data.voucher_nonce = 4;
+    assert_span(&data);
+    data.set_id_stride(
+        "bus",
+        IdStride {
+            start: 2,
+            stride: 3,
+        },
+    );
+    assert_span(&data);
+    data.enable_rate_limits(2);
+    data.set_rate_limit("person", 10);
+    data.record_create("person", &Pubkey::new_unique(), 5);
+    assert_span(&data);
+
+    let old_span = data.span().unwrap();
+    let growth = data.add_record_growth("truck");
+    data.add_record("truck");
+    assert_eq!(data.span().unwrap(), old_span + growth);
+    assert_span(&data);
+
+    data.rate_limits = Default::default();
+    data.index.values_mut().for_each(|entry| {
+        entry.metadata.clear();
+        entry.id_stride = IdStride::default();
+    });
+    assert_span(&data);
+
+    let mut data = NautilusIndexData {
+        case_insensitive_table_names: true,
+        ..Default::default()
+    };
+    data.add_record("People");
+    data.add_record("PEOPLE");
+    assert_span(&data);
+}
+
+#[test]
+fn index_reads_during_a_write_see_the_old_index_in_full() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut old = NautilusIndexData::default();
+    old.add_record("person");
+    let bytes = old.try_to_vec().unwrap();
+    let key = nautilus_index_pda(program_id).0;
+    let mut index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            key,
+            program_id,
+            minimum_balance(bytes.len()).unwrap(),
+            bytes,
+            true,
+        )),
+    )
+    .unwrap();
+
+    // A program reading the index through the rent transfer's CPI.
+    let observed = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    let reads = observed.clone();
+    observe_invocations(move |accounts| {
+        if let Some(account) = accounts.iter().find(|account| *account.key == key) {
+            let data = account.data.borrow();
+            reads
+                .borrow_mut()
+                .push(NautilusIndexData::try_from_slice(&data).map(|data| data.get_count("car")));
+        }
+    });
+    let fee_payer = signer(1_000_000_000);
+    let written = index.add_record("car", fee_payer.clone());
+    let grown = index.add_record("car", fee_payer);
+    clear_observer();
+    assert_eq!(written, Ok(1));
+    assert_eq!(grown, Ok(2));
+
+    // Only adding the table grows the index, so only that write transfers.
+    let observed = observed.borrow();
+    assert_eq!(observed.len(), 1);
+    assert_eq!(observed[0].as_ref().ok(), Some(&None));
+    let data = NautilusIndexData::try_from_slice(&index.account_info.data.borrow()).unwrap();
+    assert_eq!(data.get_count("car"), Some(2));
+    assert_eq!(data.get_count("person"), Some(1));
+    assert_eq!(index.account_info.data_len(), data.span().unwrap());
+}
+
+#[test]
+fn index_receipts_report_rent_fees_and_refunds() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let account = Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(data.len()).unwrap(),
+        data,
+        true,
+    ));
+    let mut index = NautilusIndex::load(program_id, account.clone()).unwrap();
+
+    // Growing the index tops it up to the Rent Sysvar's minimum, and adding to
+    // an existing table moves no lamports.
+    let before = index.lamports();
+    let (id, receipt) = index
+        .add_record_with_receipt("orders", signer(u64::MAX))
+        .unwrap();
+    assert_eq!(id, 1);
+    assert_eq!(
+        receipt,
+        LamportReceipt {
+            rent_paid: index.lamports() - before,
+            ..LamportReceipt::default()
+        }
+    );
+    assert!(receipt.rent_paid > 0);
+    assert_eq!(
+        index.lamports(),
+        minimum_balance(index.span().unwrap()).unwrap()
+    );
+    let (_, receipt) = index
+        .add_record_with_receipt("orders", signer(u64::MAX))
+        .unwrap();
+    assert_eq!(receipt, LamportReceipt::default());
+
+    // A rent calculator's buffer above that minimum is reported as the fee.
+    let mut index = index.with_rent_calculator(|span| Ok(minimum_balance(span)? + 1_000));
+    let before = index.lamports();
+    let (_, receipt) = index
+        .add_record_with_receipt("items", signer(u64::MAX))
+        .unwrap();
+    assert_eq!(receipt.fee_paid, 1_000);
+    assert_eq!(
+        receipt.rent_paid,
+        minimum_balance(index.span().unwrap()).unwrap() - before
+    );
+    assert_eq!(receipt.net_paid(), (index.lamports() - before) as i128);
+
+    let before = index.lamports();
+    let mut txn = index.txn();
+    txn.add_record("users").unwrap();
+    let receipt = txn.commit(signer(u64::MAX)).unwrap();
+    assert_eq!(receipt.net_paid(), (index.lamports() - before) as i128);
+
+    // Without the calculator, shrinking refunds its buffer.
+    let mut index = NautilusIndex::load(program_id, account).unwrap();
+    let receipt = index.shrink_to_fit(signer(0)).unwrap();
+    assert_eq!(
+        receipt,
+        LamportReceipt {
+            refunded: 1_000,
+            ..LamportReceipt::default()
+        }
+    );
+    assert_eq!(receipt.net_paid(), -1_000);
+    assert_eq!(
+        index.shrink_to_fit(signer(0)),
+        Ok(LamportReceipt::default())
+    );
+}
+
+#[test]
+fn index_refresh_rejects_unwritten_mutations_on_a_stale_copy() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let account = Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(data.len()).unwrap(),
+        data,
+        true,
+    ));
+    let mut first = NautilusIndex::load(program_id, account.clone()).unwrap();
+    let mut second = NautilusIndex::load(program_id, account.clone()).unwrap();
+
+    // Each copy mutates the same table once, so both end up at the same
+    // generation with different contents - only the first written.
+    assert_eq!(first.add_record("orders", signer(u64::MAX)), Ok(1));
+    second.data.add_record("items");
+    assert_eq!(second.data.generation(), first.data.generation());
+    let conflict =
+        Err(
+            NautilusError::IndexWriteConflict(String::default(), u64::default(), u64::default())
+                .into(),
+        );
+    assert_eq!(second.refresh(), conflict);
+    assert_eq!(
+        second.set_metadata("orders", vec![1], signer(u64::MAX)),
+        conflict
+    );
+
+    // A batch begun before another copy wrote the account commits nothing.
+    let mut third = NautilusIndex::load(program_id, account.clone()).unwrap();
+    let mut txn = third.txn();
+    assert_eq!(txn.add_record("items"), Ok(1));
+    assert_eq!(first.add_record("orders", signer(u64::MAX)), Ok(2));
+    assert_eq!(txn.commit(signer(u64::MAX)).map(|_| ()), conflict);
+
+    let index = NautilusIndex::load(program_id, account).unwrap();
+    assert_eq!(index.get_count("orders"), Some(2));
+    assert_eq!(index.get_count("items"), None);
+}
+
+#[test]
+fn index_shrink_and_freeze_refresh_a_stale_copy_first() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let admin = signer(u64::MAX);
+    let data = NautilusIndexData {
+        admin: *admin.key(),
+        ..NautilusIndexData::default()
+    };
+    let mut bytes = data.try_to_vec().unwrap();
+    bytes.resize(bytes.len() * 4, 0);
+    let account = Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(bytes.len()).unwrap(),
+        bytes,
+        true,
+    ));
+    let mut stale = NautilusIndex::load(program_id, account.clone()).unwrap();
+    let mut live = NautilusIndex::load(program_id, account.clone()).unwrap();
+
+    // Shrinking the stale copy keeps the records the live copy added.
+    assert_eq!(live.add_record("person", signer(u64::MAX)), Ok(1));
+    assert_eq!(live.add_record("person", signer(u64::MAX)), Ok(2));
+    stale.shrink_to_fit(signer(0)).unwrap();
+    assert_eq!(account.data_len(), live.span().unwrap());
+    let index = NautilusIndex::load(program_id, account.clone()).unwrap();
+    assert_eq!(index.get_count("person"), Some(2));
+
+    // So does freezing it.
+    assert_eq!(live.add_record("person", signer(u64::MAX)), Ok(3));
+    stale.freeze(admin).unwrap();
+    let index = NautilusIndex::load(program_id, account).unwrap();
+    assert!(index.is_frozen());
+    assert_eq!(index.get_count("person"), Some(3));
+}
+
+#[test]
+fn index_stale_copy_cannot_add_a_record_after_a_freeze() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let admin = signer(u64::MAX);
+    let data = NautilusIndexData {
+        admin: *admin.key(),
+        ..NautilusIndexData::default()
+    }
+    .try_to_vec()
+    .unwrap();
+    let account = Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(data.len()).unwrap(),
+        data,
+        true,
+    ));
+    let mut stale = NautilusIndex::load(program_id, account.clone()).unwrap();
+    let mut live = NautilusIndex::load(program_id, account.clone()).unwrap();
+
+    let generation = live.generation();
+    live.freeze(admin).unwrap();
+    assert_eq!(live.generation(), generation + 1);
+    assert_eq!(
+        stale.add_record("person", signer(u64::MAX)),
+        Err(NautilusError::IndexFrozen(String::default()).into())
+    );
+    let index = NautilusIndex::load(program_id, account).unwrap();
+    assert!(index.is_frozen());
+    assert_eq!(index.get_count("person"), None);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{install_runtime, leak_key, reallocatable_account_info, signer};
+use nautilus::*;
+
+#[derive(Table)]
+pub struct Post {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    #[nautilus(authority_is_payer)]
+    pub author: Pubkey,
+    #[nautilus(last_writer)]
+    pub last_writer: Pubkey,
+    pub body: String,
+}
+
+#[derive(Table)]
+pub struct Log {
+    #[primary_key(autoincrement = false)]
+    pub name: String,
+    #[nautilus(last_writer)]
+    pub editor: Pubkey,
+}
+
+fn index(program_id: &'static Pubkey) -> NautilusIndex<'static> {
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap()
+}
+
+#[test]
+fn last_writer_is_stamped_on_create_and_update() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let index = index(program_id);
+    let payer = signer(u64::MAX);
+    let post = Post::new(index.clone(), payer.clone(), "Hello".to_string()).unwrap();
+    assert_eq!(post.last_writer(), payer.key());
+    assert_eq!(post.count_authorities(), 1);
+
+    let log = Log::new(index.clone(), payer.clone(), "audit".to_string()).unwrap();
+    assert_eq!(log.last_writer(), payer.key());
+    assert_eq!(log.count_authorities(), 0);
+
+    let bytes = post.try_to_vec().unwrap();
+    let mut record = Record::<Post>::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            post.pda(program_id).0,
+            program_id,
+            minimum_balance(bytes.len()).unwrap(),
+            bytes.clone(),
+            true,
+        )),
+        index,
+    )
+    .unwrap();
+    assert_eq!(record.last_writer(), payer.key());
+
+    // An update signed by someone else records them, not the fee payer.
+    let author = vec![*payer.account_info()];
+    let editor = signer(0);
+    let mut new_data = (*record.data).clone();
+    new_data.body = "Howdy".to_string();
+    assert_eq!(
+        record.update_as(
+            new_data.clone(),
+            author.clone(),
+            editor.clone(),
+            payer.clone()
+        ),
+        Ok(WriteOutcome::Written)
+    );
+    assert_eq!(record.last_writer(), editor.key());
+    assert_eq!(record.data.author, *payer.key());
+    let written = Post::try_from_slice(&record.account_info.data.borrow()).unwrap();
+    assert_eq!(written.last_writer, *editor.key());
+    assert_eq!(written.body, "Howdy");
+    assert_eq!(record.account_info.data_len(), bytes.len());
+
+    // The same change by the same writer is a no-op, but by another isn't.
+    assert_eq!(
+        record.update_as(
+            new_data.clone(),
+            author.clone(),
+            editor.clone(),
+            payer.clone()
+        ),
+        Ok(WriteOutcome::Unchanged)
+    );
+    assert_eq!(
+        record.update_as(new_data, author, payer.clone(), payer.clone()),
+        Ok(WriteOutcome::Written)
+    );
+    assert_eq!(record.last_writer(), payer.key());
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key, reallocatable_account_info, signer};
+use nautilus::{error::NautilusError, *};
+
+/// Builds an index account owned by `program_id` holding `data`.
+fn index_account(
+    program_id: &'static Pubkey,
+    data: &NautilusIndexData,
+) -> Box<AccountInfo<'static>> {
+    let data = data.try_to_vec().unwrap();
+    Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(data.len()).unwrap(),
+        data,
+        true,
+    ))
+}
+
+#[test]
+fn lazy_reads_match_the_loaded_index() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut data = NautilusIndexData::default();
+    let tables = ["person", "car", "house", "sharded", "missing"];
+    for table_name in tables {
+        assert_eq!(
+            LazyIndex::load(program_id, index_account(program_id, &data))
+                .unwrap()
+                .get_count(table_name),
+            Ok(None)
+        );
+    }
+
+    data.add_record("person");
+    data.add_record("person");
+    data.add_record("car");
+    assert!(data.set_id_stride(
+        "sharded",
+        IdStride {
+            start: 2,
+            stride: 3
+        }
+    ));
+    data.add_record("sharded");
+    let lazy = LazyIndex::load(program_id, index_account(program_id, &data)).unwrap();
+    assert_eq!(lazy.get_next_count("sharded"), Ok(5));
+
+    // Every trailing field ahead of the id strides is skipped over.
+    data.set_metadata("car", vec![1, 2, 3]);
+    data.enable_deltas(4);
+    data.add_record("house");
+    data.unique_values.insert(
+        ("person".to_string(), "email".to_string()),
+        [(b"joe@example.com".to_vec(), vec![1, 0, 0, 0])]
+            .into_iter()
+            .collect(),
+    );
+    data.admin = Pubkey::new_unique();
+    data.voucher_nonce = 7;
+    let lazy = LazyIndex::load(program_id, index_account(program_id, &data)).unwrap();
+    for table_name in tables {
+        assert_eq!(lazy.get_count(table_name), Ok(data.get_count(table_name)));
+        assert_eq!(
+            lazy.get_next_count(table_name),
+            Ok(data.get_next_count(table_name))
+        );
+        assert_eq!(
+            lazy.get_id_stride(table_name),
+            Ok(data.get_id_stride(table_name))
+        );
+    }
+    assert_eq!(lazy.get_count("Person"), Ok(None));
+    let lazy = lazy.with_case_insensitive_table_names(true);
+    assert_eq!(lazy.get_count("Person"), Ok(Some(2)));
+    assert_eq!(lazy.get_next_count("SHARDED"), Ok(5));
+
+    // Mutations write back the full index.
+    assert_eq!(lazy.add_record("sharded", signer(u64::MAX)), Ok(5));
+    assert_eq!(lazy.get_count("sharded"), Ok(Some(2)));
+    assert_eq!(lazy.get_next_count("sharded"), Ok(8));
+    let index = lazy.to_index().unwrap();
+    assert_eq!(index.get_count("person"), Some(2));
+    assert_eq!(index.get_metadata("car"), Some(&[1u8, 2, 3][..]));
+    assert_eq!(index.data.voucher_nonce, 7);
+}
+
+#[test]
+fn lazy_index_rejects_foreign_and_malformed_accounts() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let foreign = Box::new(account_info(
+        leak_key(Pubkey::new_unique()),
+        leak_key(Pubkey::new_unique()),
+        0,
+        data,
+        false,
+        true,
+    ));
+    assert_eq!(
+        LazyIndex::load(program_id, foreign).err(),
+        Some(
+            NautilusError::OwnerMismatch(String::default(), String::default(), String::default())
+                .into()
+        )
+    );
+
+    let mut truncated = NautilusIndexData::default();
+    truncated.add_record("person");
+    let mut truncated = truncated.try_to_vec().unwrap();
+    // Cut off partway through the table's count.
+    truncated.truncate(8 + 4 + (4 + 6) + 2);
+    let lazy = LazyIndex::load(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            truncated,
+            false,
+            true,
+        )),
+    )
+    .unwrap();
+    assert_eq!(
+        lazy.get_count("person"),
+        Err(NautilusError::DeserializeDataFailed(String::default(), String::default()).into())
+    );
+}
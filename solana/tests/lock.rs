@@ -0,0 +1,192 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key, set_slot, signer};
+use nautilus::{error::NautilusError, *};
+
+#[test]
+fn index_lock_blocks_other_sessions_until_expiry() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let lock_data = IndexLockData::default().try_to_vec().unwrap();
+    let mut lock = IndexLock::load(
+        program_id,
+        Box::new(account_info(
+            leak_key(IndexLockData::default().pda(program_id).0),
+            program_id,
+            0,
+            lock_data,
+            false,
+            true,
+        )),
+    )
+    .unwrap();
+    let holder = signer(0);
+    let other = signer(0);
+    let locked: ProgramError =
+        NautilusError::IndexLocked(String::default(), String::default(), 0).into();
+
+    set_slot(100);
+    lock.acquire_lock(holder.clone(), 10).unwrap();
+    assert_eq!(lock.data.holder, *holder.key());
+    assert_eq!(lock.data.expires_at_slot, 110);
+    assert_eq!(
+        IndexLock::load(program_id, lock.account_info.clone())
+            .unwrap()
+            .data,
+        lock.data
+    );
+
+    let index_account = Box::new(account_info(
+        leak_key(Pubkey::new_unique()),
+        program_id,
+        0,
+        vec![],
+        false,
+        true,
+    ));
+    let mut holder_index = NautilusIndex::new(program_id, index_account.clone())
+        .with_lock(lock.clone(), holder.account_info_ref())
+        .unwrap();
+    let mut other_index = NautilusIndex::new(program_id, index_account)
+        .with_lock(lock.clone(), other.account_info_ref())
+        .unwrap();
+    assert_eq!(holder_index.txn().add_record("hero"), Ok(1));
+    assert_eq!(other_index.txn().add_record("hero"), Err(locked.clone()));
+    assert_eq!(
+        other_index.remove_record("hero", signer(u64::MAX)),
+        Err(locked.clone())
+    );
+    assert_eq!(lock.acquire_lock(other.clone(), 10), Err(locked.clone()));
+    assert_eq!(lock.release_lock(other.clone()), Err(locked.clone()));
+
+    set_slot(110);
+    let mut other_index = other_index
+        .with_lock(lock.clone(), other.account_info_ref())
+        .unwrap();
+    assert_eq!(other_index.txn().add_record("hero"), Ok(1));
+    lock.acquire_lock(other.clone(), 5).unwrap();
+    assert_eq!(lock.release_lock(holder.clone()), Err(locked.clone()));
+    lock.release_lock(other).unwrap();
+    assert_eq!(lock.data, IndexLockData::default());
+}
+
+#[test]
+fn index_lock_rejects_impostor_accounts_and_unsigned_sessions() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let address = leak_key(IndexLockData::default().pda(program_id).0);
+    let lock_account = |key: &'static Pubkey, data: Vec<u8>| {
+        Box::new(account_info(key, program_id, 0, data, false, true))
+    };
+    let lock_data = IndexLockData::default().try_to_vec().unwrap();
+
+    // Another program-owned account that would parse as a free lock.
+    assert_eq!(
+        IndexLock::load(
+            program_id,
+            lock_account(leak_key(Pubkey::new_unique()), lock_data.clone())
+        )
+        .err(),
+        Some(NautilusError::AddressMismatch(String::default(), String::default()).into())
+    );
+    // Data at the lock's address without the lock's discriminator.
+    let mut holder_first = Pubkey::new_unique().to_bytes().to_vec();
+    holder_first.extend(0u64.to_le_bytes());
+    assert_eq!(
+        IndexLock::load(program_id, lock_account(address, holder_first)).err(),
+        Some(NautilusError::DeserializeDataFailed(String::default(), String::default()).into())
+    );
+
+    // Naming a session without its signature.
+    let lock = IndexLock::load(program_id, lock_account(address, lock_data)).unwrap();
+    let mut unsigned = *signer(0).account_info();
+    unsigned.is_signer = false;
+    let index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    assert_eq!(
+        index.with_lock(lock, &unsigned).err(),
+        Some(NautilusError::AccountNotSigner(String::default()).into())
+    );
+}
+
+#[test]
+fn index_lock_must_be_loaded_and_writable() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let address = leak_key(IndexLockData::default().pda(program_id).0);
+    let index = || {
+        NautilusIndex::new(
+            program_id,
+            Box::new(account_info(
+                leak_key(Pubkey::new_unique()),
+                program_id,
+                0,
+                vec![],
+                false,
+                true,
+            )),
+        )
+    };
+    let session = signer(0);
+
+    // A lock that was never loaded reads as free, so it's loaded on use.
+    let unloaded = IndexLock::new(
+        program_id,
+        Box::new(account_info(address, program_id, 0, vec![], false, true)),
+    );
+    assert_eq!(unloaded.data, IndexLockData::default());
+    assert_eq!(
+        index()
+            .with_lock(unloaded, session.account_info_ref())
+            .err(),
+        Some(NautilusError::DeserializeDataFailed(String::default(), String::default()).into())
+    );
+
+    // A held lock is loaded with its holder, however it was instantiated.
+    let held = IndexLockData {
+        holder: Pubkey::new_unique(),
+        expires_at_slot: u64::MAX,
+    };
+    let held_account = Box::new(account_info(
+        address,
+        program_id,
+        0,
+        held.try_to_vec().unwrap(),
+        false,
+        true,
+    ));
+    let mut locked_index = index()
+        .with_lock(
+            IndexLock::new(program_id, held_account),
+            session.account_info_ref(),
+        )
+        .unwrap();
+    assert_eq!(
+        locked_index.txn().add_record("hero"),
+        Err(NautilusError::IndexLocked(String::default(), String::default(), 0).into())
+    );
+
+    let mut read_only = IndexLock::load(
+        program_id,
+        Box::new(account_info(
+            address,
+            program_id,
+            0,
+            IndexLockData::default().try_to_vec().unwrap(),
+            false,
+            false,
+        )),
+    )
+    .unwrap();
+    let not_mutable = Err(NautilusError::AccountNotMutable(String::default()).into());
+    assert_eq!(read_only.acquire_lock(session.clone(), 10), not_mutable);
+    assert_eq!(read_only.release_lock(session), not_mutable);
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{account_info, leak_key, signer, Hero};
+use nautilus::{error::NautilusError, *};
+
+/// Builds the accounts of a chain holding `bytes`, owned by `owner`, with one
+/// spare overflow account.
+fn chain(
+    owner: &'static Pubkey,
+    bytes: &[u8],
+) -> (Box<AccountInfo<'static>>, Vec<AccountInfo<'static>>) {
+    let keys: Vec<Pubkey> = (0..=overflow_accounts_needed(bytes.len()))
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    let mut data = chain_account_data(bytes, &keys).unwrap().into_iter();
+    let head = account_info(
+        leak_key(Pubkey::new_unique()),
+        owner,
+        0,
+        data.next().unwrap(),
+        false,
+        true,
+    );
+    let overflow = keys
+        .into_iter()
+        .map(|key| {
+            account_info(
+                leak_key(key),
+                owner,
+                0,
+                data.next().unwrap_or_default(),
+                false,
+                true,
+            )
+        })
+        .collect();
+    (Box::new(head), overflow)
+}
+
+#[test]
+fn overflow_chains_split_data_across_accounts() {
+    let head_capacity = OVERFLOW_CHUNK_LEN - OverflowHeader::span(0);
+    assert_eq!(overflow_accounts_needed(0), 0);
+    assert_eq!(overflow_accounts_needed(head_capacity), 0);
+    assert_eq!(overflow_accounts_needed(head_capacity + 1), 1);
+    assert_eq!(
+        overflow_accounts_needed(2 * OVERFLOW_CHUNK_LEN - OverflowHeader::span(1)),
+        1
+    );
+
+    let bytes: Vec<u8> = (0..3 * OVERFLOW_CHUNK_LEN).map(|i| i as u8).collect();
+    let keys = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let data = chain_account_data(&bytes, &keys).unwrap();
+    assert_eq!(data.len(), 4);
+    assert!(data.iter().all(|data| data.len() <= OVERFLOW_CHUNK_LEN));
+    let header = OverflowHeader::try_from_slice(&data[0][..OverflowHeader::span(3)]).unwrap();
+    assert_eq!(header.len, bytes.len() as u64);
+    assert_eq!(header.next, keys);
+    assert_eq!(header.chain_len(), 4);
+    assert_eq!(
+        chain_account_data(&bytes, &keys[..2]),
+        Err(NautilusError::OverflowChainTooShort(0, 0).into())
+    );
+
+    let program_id = leak_key(Pubkey::new_unique());
+    let (head, overflow) = chain(program_id, &bytes);
+    assert_eq!(read_chain(&head, &overflow).unwrap(), bytes);
+
+    let mismatch = Err(NautilusError::OverflowChainMismatch(String::default()).into());
+    let mut reordered = overflow.clone();
+    reordered.swap(0, 1);
+    assert_eq!(read_chain(&head, &reordered), mismatch);
+    assert_eq!(read_chain(&head, &overflow[..2]), mismatch);
+
+    let other_program_id = leak_key(Pubkey::new_unique());
+    let mut foreign = overflow.clone();
+    foreign[1] = account_info(
+        leak_key(*overflow[1].key),
+        other_program_id,
+        0,
+        overflow[1].data.borrow().to_vec(),
+        false,
+        true,
+    );
+    assert_eq!(read_chain(&head, &foreign), Err(ProgramError::IllegalOwner));
+    assert_eq!(
+        write_chain(&head, &foreign, &bytes, signer(0)),
+        Err(ProgramError::IllegalOwner)
+    );
+    let mut read_only = overflow.clone();
+    read_only[2].is_writable = false;
+    assert_eq!(
+        write_chain(&head, &read_only, &bytes, signer(0)),
+        Err(NautilusError::AccountNotMutable(String::default()).into())
+    );
+}
+
+#[test]
+fn overflow_chains_back_records() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let hero = Hero {
+        id: 1,
+        name: "J".repeat(OVERFLOW_CHUNK_LEN),
+    };
+    let (head, overflow) = chain(program_id, &hero.try_to_vec().unwrap());
+    assert_eq!(overflow.len(), 2);
+    let index = NautilusIndex::new(program_id, head.clone());
+    let mut record: Record<Hero> = Record::new(program_id, head, index);
+    record.read_full(&overflow).unwrap();
+    assert_eq!(*record.data, hero);
+    assert_eq!(
+        record.read_full(&overflow[1..]),
+        Err(NautilusError::OverflowChainMismatch(String::default()).into())
+    );
+}
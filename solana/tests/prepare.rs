@@ -0,0 +1,113 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key, signer};
+use nautilus::{error::NautilusError, *};
+
+/// Derives a record's address from its table name and id.
+fn derive(program_id: &Pubkey) -> impl Fn(&str, u32) -> (Pubkey, u8) + '_ {
+    move |table_name, id| {
+        Pubkey::find_program_address(&[table_name.as_bytes(), &id.to_le_bytes()], program_id)
+    }
+}
+
+/// Builds a free account at `key`.
+fn free_account(key: Pubkey) -> AccountInfo<'static> {
+    account_info(leak_key(key), &system_program::ID, 0, vec![], false, true)
+}
+
+#[test]
+fn prepared_commits_reserve_ids_without_writing() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    index.data.add_record("person");
+    let mut plan = Prepare::new();
+    plan.add("person", 100).add("car", 50).add("person", 100);
+    let accounts: Vec<AccountInfo> = [("car", 1), ("person", 2), ("person", 3)]
+        .iter()
+        .map(|(table_name, id)| free_account(derive(program_id)(table_name, *id).0))
+        .collect();
+
+    let commit = index
+        .prepare(&plan, &accounts, signer(u64::MAX), derive(program_id))
+        .unwrap();
+    let ids: Vec<(&str, u32)> = commit
+        .records()
+        .iter()
+        .map(|record| (record.table_name.as_str(), record.id))
+        .collect();
+    assert_eq!(ids, [("person", 2), ("car", 1), ("person", 3)]);
+    let (address, bump) = derive(program_id)("car", 1);
+    assert_eq!(commit.records()[1].address, address);
+    assert_eq!(commit.records()[1].bump, bump);
+    assert_eq!(commit.records()[1].rent, minimum_balance(50).unwrap());
+    assert!(commit.total_rent() > 2 * minimum_balance(100).unwrap() + minimum_balance(50).unwrap());
+    assert_eq!(index.get_count("person"), Some(1));
+    assert_eq!(index.get_count("car"), None);
+
+    // Any failed check leaves nothing reserved.
+    let exact = signer(commit.total_rent());
+    assert!(index
+        .prepare(&plan, &accounts, exact, derive(program_id))
+        .is_ok());
+    assert_eq!(
+        index
+            .prepare(
+                &plan,
+                &accounts,
+                signer(commit.total_rent() - 1),
+                derive(program_id)
+            )
+            .err(),
+        Some(ProgramError::InsufficientFunds)
+    );
+    assert_eq!(
+        index
+            .prepare(&plan, &accounts[1..], signer(u64::MAX), derive(program_id))
+            .err(),
+        Some(ProgramError::NotEnoughAccountKeys)
+    );
+    let mut taken = accounts.clone();
+    taken[2] = account_info(
+        leak_key(*accounts[2].key),
+        program_id,
+        1,
+        vec![0; 100],
+        false,
+        true,
+    );
+    assert_eq!(
+        index
+            .prepare(&plan, &taken, signer(u64::MAX), derive(program_id))
+            .err(),
+        Some(NautilusError::AccountExists(String::default()).into())
+    );
+    index.data.frozen = true;
+    assert_eq!(
+        index
+            .prepare(&plan, &accounts, signer(u64::MAX), derive(program_id))
+            .err(),
+        Some(NautilusError::IndexFrozen(String::default()).into())
+    );
+    index.data.frozen = false;
+    assert_eq!(index.get_count("person"), Some(1));
+
+    // A commit is stale once the index moves on from where it was prepared.
+    index.data.add_record("person");
+    assert_eq!(
+        index.commit(commit, signer(u64::MAX), |_| Ok(())),
+        Err(NautilusError::StaleCommit(0, 0).into())
+    );
+    assert_eq!(index.get_count("person"), Some(2));
+    assert_eq!(index.get_count("car"), None);
+}
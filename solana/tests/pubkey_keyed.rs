@@ -0,0 +1,113 @@
+mod common;
+
+use common::{account_info, leak_key};
+use nautilus::{error::NautilusError, *};
+
+/// A record keyed by the wallet it belongs to.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+struct Profile {
+    wallet: Pubkey,
+    nickname: String,
+}
+
+impl NautilusRecordData for Profile {
+    const TABLE_NAME: &'static str = "profile";
+    const AUTO_INCREMENT: bool = false;
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.wallet.to_bytes().to_vec()
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+impl PubkeyKeyed for Profile {
+    fn key_pubkey(&self) -> &Pubkey {
+        &self.wallet
+    }
+}
+
+#[test]
+fn create_and_find_record_by_pubkey() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    let wallet = Pubkey::new_unique();
+    let profile = Profile {
+        wallet,
+        nickname: "Joe".to_string(),
+    };
+    let (pda, _) = Profile::pda_for_pubkey(program_id, &wallet);
+    assert_eq!(profile.pda(program_id).0, pda);
+    assert_eq!(
+        Profile::seeds_for_pubkey(&wallet),
+        vec![b"profile".to_vec(), wallet.to_bytes().to_vec()]
+    );
+
+    // The account as `create_record()` would leave it, at the address derived
+    // from the wallet.
+    let record_account = Box::new(account_info(
+        leak_key(pda),
+        program_id,
+        0,
+        profile.try_to_vec().unwrap(),
+        false,
+        false,
+    ));
+    let record = Record::<Profile>::find_by_pubkey(
+        program_id,
+        &wallet,
+        record_account.clone(),
+        index.clone(),
+    )
+    .unwrap();
+    assert_eq!(*record.data, profile);
+    assert_eq!(index.data.index.len(), 0);
+
+    let not_found: ProgramError =
+        NautilusError::RecordNotFound(String::default(), String::default(), String::default())
+            .into();
+    assert_eq!(
+        Record::<Profile>::find_by_pubkey(
+            program_id,
+            &Pubkey::new_unique(),
+            record_account,
+            index.clone()
+        )
+        .err(),
+        Some(not_found.clone())
+    );
+
+    // An account at the wallet's address holding another wallet's profile.
+    let other_profile = Profile {
+        wallet: Pubkey::new_unique(),
+        nickname: "Jane".to_string(),
+    };
+    let misplaced_account = Box::new(account_info(
+        leak_key(pda),
+        program_id,
+        0,
+        other_profile.try_to_vec().unwrap(),
+        false,
+        false,
+    ));
+    assert_eq!(
+        Record::<Profile>::find_by_pubkey(program_id, &wallet, misplaced_account, index).err(),
+        Some(not_found)
+    );
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{install_runtime, leak_key, reallocatable_account_info, set_slot, signer};
+use nautilus::{error::NautilusError, *};
+
+#[test]
+fn rate_limits_track_payers_and_evict_the_oldest() {
+    let mut data = NautilusIndexData::default();
+    let [a, b, c, d] = [(); 4].map(|_| Pubkey::new_unique());
+    data.set_rate_limit("person", 10);
+    data.record_create("person", &a, 5);
+    // Disabled rate limits track and limit no one.
+    assert_eq!(data.rate_limit_retry_after("person", &a, 6), None);
+
+    let span = data.try_to_vec().unwrap().len();
+    data.enable_rate_limits(2);
+    assert_eq!(data.try_to_vec().unwrap().len(), span + 2 * 72);
+    data.record_create("person", &a, 5);
+    assert_eq!(data.rate_limit_retry_after("person", &a, 6), Some(15));
+    assert_eq!(data.rate_limit_retry_after("person", &a, 15), None);
+    assert_eq!(data.rate_limit_retry_after("person", &b, 6), None);
+    data.record_create("car", &a, 5);
+    assert_eq!(data.rate_limit_retry_after("car", &a, 6), None);
+
+    // With every slot taken and none lapsed, the oldest create is evicted.
+    data.record_create("person", &b, 7);
+    data.record_create("person", &c, 9);
+    assert_eq!(data.rate_limit_retry_after("person", &a, 9), None);
+    assert_eq!(data.rate_limit_retry_after("person", &b, 9), Some(17));
+    assert_eq!(data.rate_limit_retry_after("person", &c, 9), Some(19));
+    // A lapsed entry is taken before any that still limits its payer.
+    data.record_create("person", &d, 18);
+    assert_eq!(data.rate_limit_retry_after("person", &c, 18), Some(19));
+    assert_eq!(data.rate_limit_retry_after("person", &d, 18), Some(28));
+
+    let data = NautilusIndexData::try_from_slice(&data.try_to_vec().unwrap()).unwrap();
+    assert_eq!(data.rate_limits.capacity(), 2);
+    assert_eq!(data.rate_limits.interval("person"), Some(10));
+    assert_eq!(data.rate_limit_retry_after("person", &d, 18), Some(28));
+
+    let mut data = data;
+    data.set_rate_limit("person", 0);
+    assert_eq!(data.rate_limit_retry_after("person", &d, 18), None);
+    data.enable_rate_limits(1);
+    assert_eq!(data.rate_limits.entries[0].payer, d);
+}
+
+#[test]
+fn add_record_rejects_creates_faster_than_the_rate_limit() {
+    install_runtime();
+    set_slot(100);
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let mut index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    index.enable_rate_limits(4, signer(u64::MAX)).unwrap();
+    index.set_rate_limit("person", 1, signer(u64::MAX)).unwrap();
+
+    let payer = signer(u64::MAX);
+    assert_eq!(index.add_record("person", payer.clone()), Ok(1));
+    assert_eq!(
+        index.add_record("person", payer.clone()),
+        Err(NautilusError::RateLimited(String::default(), 0).into())
+    );
+    assert_eq!(index.get_count("person"), Some(1));
+    // A dry run is rejected just the same, without being tracked itself.
+    assert_eq!(
+        index.simulate_add_record("person", payer.key()).map(|_| ()),
+        Err(NautilusError::RateLimited(String::default(), 0).into())
+    );
+    let other = signer(u64::MAX);
+    assert!(index.simulate_add_record("person", other.key()).is_ok());
+    assert_eq!(index.add_record("person", other), Ok(2));
+    assert_eq!(index.add_record("car", payer.clone()), Ok(1));
+    assert_eq!(index.add_record("car", payer.clone()), Ok(2));
+
+    set_slot(101);
+    let mut index = NautilusIndex::load(program_id, index.account_info.clone()).unwrap();
+    assert_eq!(index.add_record("person", payer), Ok(3));
+}
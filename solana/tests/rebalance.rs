@@ -0,0 +1,61 @@
+mod common;
+
+use common::{account_info, leak_key};
+use nautilus::{error::NautilusError, *};
+
+fn account(lamports: u64) -> Box<AccountInfo<'static>> {
+    let program_id = leak_key(Pubkey::new_unique());
+    Box::new(account_info(
+        leak_key(Pubkey::new_unique()),
+        program_id,
+        lamports,
+        vec![],
+        false,
+        true,
+    ))
+}
+
+#[test]
+fn rebalance_applies_zero_sum_moves() {
+    let record = account(0);
+    let escrow = account(500);
+    let payer = account(1_000);
+    rebalance(&[
+        (payer.clone(), -300),
+        (record.clone(), 200),
+        (escrow.clone(), 100),
+        (payer.clone(), -100),
+        (escrow.clone(), 100),
+    ])
+    .unwrap();
+    assert_eq!(payer.lamports(), 600);
+    assert_eq!(record.lamports(), 200);
+    assert_eq!(escrow.lamports(), 700);
+}
+
+#[test]
+fn rebalance_applies_nothing_on_failure() {
+    let from = account(100);
+    let to = account(u64::MAX - 50);
+
+    assert_eq!(
+        rebalance(&[(from.clone(), -100), (to.clone(), 99)]),
+        Err(NautilusError::RebalanceNotZeroSum(0).into())
+    );
+    assert_eq!(
+        rebalance(&[(from.clone(), -101), (account(0), 101)]),
+        Err(NautilusError::RebalanceOutOfRange(String::default(), 0).into())
+    );
+    assert_eq!(
+        rebalance(&[(from.clone(), -100), (to.clone(), 100)]),
+        Err(NautilusError::RebalanceOutOfRange(String::default(), 0).into())
+    );
+    let mut read_only = account(0);
+    read_only.is_writable = false;
+    assert_eq!(
+        rebalance(&[(from.clone(), -10), (read_only, 10)]),
+        Err(NautilusError::AccountNotMutable(String::default()).into())
+    );
+    assert_eq!(from.lamports(), 100);
+    assert_eq!(to.lamports(), u64::MAX - 50);
+}
@@ -0,0 +1,483 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key, Hero};
+use nautilus::{error::NautilusError, *};
+
+fn hero_record(program_id: &'static Pubkey, owner: &'static Pubkey) -> Record<'static, Hero> {
+    let index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    Record::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            owner,
+            0,
+            vec![],
+            false,
+            false,
+        )),
+        index,
+    )
+}
+
+#[test]
+fn owner_matches_deriver() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let record = hero_record(program_id, program_id);
+    assert_eq!(record.deriver_program(), program_id);
+    assert_eq!(record.owner_program(), program_id);
+    assert!(record.assert_owner_is_deriver().is_ok());
+}
+
+#[test]
+fn owner_differs_from_deriver() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let other_program_id = leak_key(Pubkey::new_unique());
+    let record = hero_record(program_id, other_program_id);
+    assert_eq!(record.deriver_program(), program_id);
+    assert_eq!(record.owner_program(), other_program_id);
+    assert_eq!(
+        record.assert_owner_is_deriver(),
+        Err(
+            NautilusError::OwnerMismatch(String::default(), String::default(), String::default())
+                .into()
+        )
+    );
+
+    let data = Hero {
+        id: 1,
+        name: "Joe".to_string(),
+    }
+    .try_to_vec()
+    .unwrap();
+    let key = leak_key(Pubkey::new_unique());
+    assert!(Record::<Hero>::load(
+        program_id,
+        Box::new(account_info(
+            key,
+            other_program_id,
+            0,
+            data.clone(),
+            false,
+            false
+        )),
+        record.index.clone(),
+    )
+    .is_err());
+    let loaded = Record::<Hero>::load(
+        program_id,
+        Box::new(account_info(key, program_id, 0, data, false, false)),
+        record.index,
+    )
+    .unwrap();
+    assert_eq!(loaded.data.name, "Joe");
+}
+
+#[test]
+fn create_record_owned_by_another_program() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let owner_program = Pubkey::new_unique();
+    let system_program = leak_key(system_program::ID);
+    let mut record = hero_record(program_id, system_program);
+    record.data.id = 7;
+    let (pda, _) = record.pda();
+    record.account_info = Box::new(account_info(
+        leak_key(pda),
+        system_program,
+        0,
+        vec![],
+        false,
+        true,
+    ));
+    let fee_payer = Box::new(account_info(
+        leak_key(Pubkey::new_unique()),
+        system_program,
+        1_000_000_000,
+        vec![],
+        true,
+        true,
+    ));
+    let mut create = Create::new(
+        fee_payer,
+        Box::new(account_info(
+            system_program,
+            system_program,
+            0,
+            vec![],
+            false,
+            false,
+        )),
+        Box::new(account_info(
+            leak_key(nautilus::solana_program::sysvar::rent::ID),
+            system_program,
+            0,
+            vec![],
+            false,
+            false,
+        )),
+        record,
+    )
+    .unwrap();
+    let rent = create.required_rent().unwrap();
+
+    create.create_owned_by(&owner_program).unwrap();
+    assert_eq!(create.owner(), &owner_program);
+    assert_eq!(create.lamports(), rent);
+    assert_eq!(create.fee_payer.lamports(), 1_000_000_000 - rent);
+    assert_eq!(create.deriver_program(), program_id);
+    assert!(create.assert_owner_is_deriver().is_err());
+}
+
+#[test]
+fn reinterpret_requires_the_current_data_type() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut record = hero_record(program_id, program_id);
+    // `Hero` serializes without its discriminator, so the account can't be
+    // recognized as holding `Hero` data.
+    *record.data = Hero {
+        id: 1,
+        name: "Joe".to_string(),
+    };
+    let data = record.data.try_to_vec().unwrap();
+    record.account_info = Box::new(account_info(
+        leak_key(record.pda().0),
+        program_id,
+        1_000,
+        data.clone(),
+        false,
+        true,
+    ));
+    let account = record.account_info.clone();
+    let fee_payer = common::signer(1_000_000_000);
+
+    assert_eq!(
+        record
+            .reinterpret(
+                Hero {
+                    id: 1,
+                    name: "Joseph".to_string(),
+                },
+                vec![],
+                fee_payer.clone(),
+            )
+            .err(),
+        Some(NautilusError::DiscriminatorMismatch(String::default(), String::default()).into())
+    );
+    assert_eq!(*account.data.borrow(), &data[..]);
+    assert_eq!(account.lamports(), 1_000);
+    assert_eq!(fee_payer.lamports(), 1_000_000_000);
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn client_rent_estimate_matches_required_rent() {
+    use nautilus::client::EstimateRent;
+
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut record = hero_record(program_id, program_id);
+    *record.data = Hero {
+        id: 3,
+        name: "Josephine".to_string(),
+    };
+    assert_eq!(record.data.estimate_rent(), record.required_rent().unwrap());
+    assert_eq!(record.data.span().unwrap(), record.span().unwrap());
+}
+
+#[test]
+fn no_op_update_skips_the_write() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut record = hero_record(program_id, program_id);
+    let hero = Hero {
+        id: 1,
+        name: "Joe".to_string(),
+    };
+    *record.data = hero.clone();
+    *record.account_info = account_info(
+        leak_key(record.pda().0),
+        program_id,
+        0,
+        hero.try_to_vec().unwrap(),
+        false,
+        true,
+    );
+    let account = record.account_info.clone();
+    let fee_payer = common::signer(1_000_000_000);
+
+    // Holding a borrow of the account's data makes any serialization into it
+    // panic, so the no-op update must not write.
+    let held = account.data.borrow();
+    assert_eq!(
+        record.update(hero.clone(), vec![], fee_payer.clone()),
+        Ok(WriteOutcome::Unchanged)
+    );
+    drop(held);
+    assert_eq!(*record.data, hero);
+    assert_eq!(account.lamports(), 0);
+    assert_eq!(fee_payer.lamports(), 1_000_000_000);
+}
+
+#[test]
+fn archived_records_reads_back_appended_records() {
+    let mut archive = vec![];
+    for record in [&b"joe"[..], &b"jane"[..]] {
+        archive.extend_from_slice(&(record.len() as u32).to_le_bytes());
+        archive.extend_from_slice(record);
+    }
+    assert_eq!(
+        archived_records(&archive),
+        Some(vec![b"joe".to_vec(), b"jane".to_vec()])
+    );
+    assert_eq!(archived_records(&archive[..archive.len() - 1]), None);
+}
+
+/// A record type with a version field.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+struct Counter {
+    id: u32,
+    value: u64,
+    version: u64,
+}
+
+impl NautilusRecordData for Counter {
+    const TABLE_NAME: &'static str = "counter";
+    const AUTO_INCREMENT: bool = true;
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.id.to_le_bytes().to_vec()
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+impl Versioned for Counter {
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+}
+
+#[test]
+fn write_if_version_rejects_a_changed_version() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let counter = Counter {
+        id: 1,
+        value: 10,
+        version: 3,
+    };
+    let account = Box::new(account_info(
+        leak_key(counter.pda(program_id).0),
+        program_id,
+        1_000,
+        counter.try_to_vec().unwrap(),
+        false,
+        true,
+    ));
+    let index = hero_record(program_id, program_id).index;
+    let (mut record, version) =
+        Record::<Counter>::load_versioned(program_id, account.clone(), index).unwrap();
+    assert_eq!(version, 3);
+    assert_eq!(record.data.value, 10);
+
+    // Another handle on the account writes a new version in the meantime.
+    let concurrent = Counter {
+        id: 1,
+        value: 11,
+        version: 4,
+    };
+    account
+        .data
+        .borrow_mut()
+        .copy_from_slice(&concurrent.try_to_vec().unwrap());
+
+    let fee_payer = common::signer(1_000_000_000);
+    let mut new_data = (*record.data).clone();
+    new_data.value += 5;
+    assert_eq!(
+        record.write_if_version(new_data, version, vec![], fee_payer.clone()),
+        Err(NautilusError::VersionConflict(String::default(), 0, 0).into())
+    );
+    assert_eq!(
+        Counter::try_from_slice(&account.data.borrow()).unwrap(),
+        concurrent
+    );
+    assert_eq!(record.data.value, 10);
+    assert_eq!(fee_payer.lamports(), 1_000_000_000);
+}
+
+#[test]
+fn assert_mutable_checks_writability_ownership_and_address() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let record_at = |key: &'static Pubkey, owner: &'static Pubkey, is_writable| {
+        let mut record = hero_record(program_id, owner);
+        record.account_info = Box::new(account_info(key, owner, 0, vec![], false, is_writable));
+        record
+    };
+    let (address, bump) = hero_record(program_id, program_id).pda();
+    let address = leak_key(address);
+    assert_eq!(
+        record_at(address, program_id, true).assert_mutable(program_id),
+        Ok(bump)
+    );
+
+    assert_eq!(
+        record_at(address, program_id, false).assert_mutable(program_id),
+        Err(NautilusError::AccountNotMutable(String::default()).into())
+    );
+    assert_eq!(
+        record_at(address, leak_key(Pubkey::new_unique()), true).assert_mutable(program_id),
+        Err(
+            NautilusError::OwnerMismatch(String::default(), String::default(), String::default())
+                .into()
+        )
+    );
+    assert_eq!(
+        record_at(leak_key(Pubkey::new_unique()), program_id, true).assert_mutable(program_id),
+        Err(NautilusError::AddressMismatch(String::default(), String::default()).into())
+    );
+    // The first failing check is the one reported.
+    assert_eq!(
+        record_at(leak_key(Pubkey::new_unique()), &system_program::ID, false)
+            .assert_mutable(program_id),
+        Err(NautilusError::AccountNotMutable(String::default()).into())
+    );
+}
+
+#[test]
+fn every_write_path_runs_assert_mutable() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut record = hero_record(program_id, program_id);
+    *record.data = Hero {
+        id: 1,
+        name: "Joe".to_string(),
+    };
+    let data = record.data.try_to_vec().unwrap();
+    *record.account_info = account_info(
+        leak_key(record.pda().0),
+        program_id,
+        1_000,
+        data.clone(),
+        false,
+        false,
+    );
+    let account = record.account_info.clone();
+    let fee_payer = common::signer(1_000_000_000);
+    let archive = account_info(
+        leak_key(Pubkey::new_unique()),
+        program_id,
+        0,
+        vec![],
+        false,
+        true,
+    );
+    let incinerator = account_info(
+        leak_key(nautilus::solana_program::incinerator::ID),
+        &system_program::ID,
+        0,
+        vec![],
+        false,
+        true,
+    );
+    let renamed = Hero {
+        id: 1,
+        name: "Joseph".to_string(),
+    };
+    let not_mutable: ProgramResult =
+        Err(NautilusError::AccountNotMutable(String::default()).into());
+
+    assert_eq!(
+        record
+            .clone()
+            .update(renamed.clone(), vec![], fee_payer.clone())
+            .map(|_| ()),
+        not_mutable
+    );
+    assert_eq!(
+        record
+            .clone()
+            .reinterpret(renamed.clone(), vec![], fee_payer.clone())
+            .map(|_| ()),
+        not_mutable
+    );
+    assert_eq!(
+        record.write_full(&[], vec![], fee_payer.clone()),
+        not_mutable
+    );
+    assert_eq!(
+        record
+            .clone()
+            .close_with_archive(vec![], &archive, fee_payer.clone())
+            .map(|_| ()),
+        not_mutable
+    );
+    assert_eq!(
+        record.clone().close_and_burn(vec![], &incinerator),
+        not_mutable
+    );
+
+    // A record at an address its seeds don't derive can't be written either.
+    let mut misplaced = record.clone();
+    *misplaced.account_info = account_info(
+        leak_key(Pubkey::new_unique()),
+        program_id,
+        1_000,
+        data.clone(),
+        false,
+        true,
+    );
+    assert_eq!(
+        misplaced.update(renamed, vec![], fee_payer.clone()),
+        Err(NautilusError::AddressMismatch(String::default(), String::default()).into())
+    );
+
+    let counter = Counter {
+        id: 1,
+        value: 10,
+        version: 3,
+    };
+    let (mut counter, version) = Record::<Counter>::load_versioned(
+        program_id,
+        Box::new(account_info(
+            leak_key(counter.pda(program_id).0),
+            program_id,
+            1_000,
+            counter.try_to_vec().unwrap(),
+            false,
+            false,
+        )),
+        record.index.clone(),
+    )
+    .unwrap();
+    let new_data = (*counter.data).clone();
+    assert_eq!(
+        counter.write_if_version(new_data, version, vec![], fee_payer.clone()),
+        Err(NautilusError::AccountNotMutable(String::default()).into())
+    );
+
+    assert_eq!(*account.data.borrow(), &data[..]);
+    assert_eq!(account.lamports(), 1_000);
+    assert_eq!(fee_payer.lamports(), 1_000_000_000);
+}
@@ -0,0 +1,80 @@
+use nautilus::{error::NautilusError, *};
+
+mod v1 {
+    use nautilus::*;
+
+    #[derive(Table)]
+    pub struct Person {
+        #[primary_key(autoincrement = true)]
+        pub id: u32,
+        pub name: String,
+    }
+}
+
+mod v2 {
+    use nautilus::*;
+
+    #[derive(Table)]
+    pub struct Person {
+        #[primary_key(autoincrement = true)]
+        pub id: u32,
+        pub name: String,
+        pub friends: Vec<Pubkey>,
+    }
+}
+
+#[test]
+fn schema_hashes_cover_field_names_and_types() {
+    assert_eq!(v1::Person::schema(), "person(id:u32,name:String)");
+    assert_eq!(
+        v2::Person::schema(),
+        "person(id:u32,name:String,friends:Vec<Pubkey>)"
+    );
+    assert_eq!(
+        v1::Person::schema_hash(),
+        solana_program::hash::hash(b"person(id:u32,name:String)").to_bytes()
+    );
+    assert_ne!(v1::Person::schema_hash(), v2::Person::schema_hash());
+}
+
+#[test]
+fn schema_registries_detect_drift() {
+    let mut registry = SchemaRegistry::new();
+    assert_eq!(
+        registry.check::<v1::Person>(),
+        Err(NautilusError::SchemaMismatch(String::default()).into())
+    );
+    registry.register::<v2::Person>();
+    assert_eq!(registry.get("person"), Some(&v2::Person::schema_hash()));
+    assert_eq!(registry.check::<v2::Person>(), Ok(()));
+    assert_eq!(
+        registry.check::<v1::Person>(),
+        Err(NautilusError::SchemaMismatch(String::default()).into())
+    );
+    registry.register::<v1::Person>();
+    assert_eq!(registry.entries.len(), 1);
+    assert_eq!(registry.check::<v1::Person>(), Ok(()));
+
+    let program_id = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = registry.try_to_vec().unwrap();
+    let (key, _) = schema_registry_pda(&program_id);
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &program_id,
+        false,
+        0,
+    );
+    assert_eq!(
+        read_schema_registry(&program_id, &account_info),
+        Ok(registry)
+    );
+    assert_eq!(
+        read_schema_registry(&Pubkey::new_unique(), &account_info),
+        Err(ProgramError::InvalidSeeds)
+    );
+}
@@ -0,0 +1,439 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key, reallocatable_account_info, signer, Hero};
+use nautilus::{error::NautilusError, *};
+
+#[test]
+fn seeds_for_matches_record_seeds() {
+    let hero = Hero {
+        id: 42,
+        name: "Joe".to_string(),
+    };
+    let seeds = Hero::seeds_for(Hero::TABLE_NAME, &42u32.to_le_bytes());
+    assert_eq!(seeds, hero.seeds());
+    assert_eq!(seeds, vec![b"hero".to_vec(), vec![42, 0, 0, 0]]);
+    assert_eq!(
+        seeds,
+        nautilus::seeds::record_seeds("hero", &hero.primary_key())
+    );
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn client_pda_matches_record_pda() {
+    let program_id = Pubkey::new_unique();
+    let hero = Hero {
+        id: 42,
+        name: "Joe".to_string(),
+    };
+    assert_eq!(
+        nautilus::client::record_pda(&program_id, Hero::TABLE_NAME, &hero.primary_key()),
+        hero.pda(&program_id)
+    );
+}
+
+#[test]
+fn normalized_table_name_seeds() {
+    assert_eq!(nautilus::seeds::normalize_table_name("Users"), "users");
+    assert_eq!(
+        nautilus::seeds::record_seeds(&nautilus::seeds::normalize_table_name("USERS"), &[1]),
+        nautilus::seeds::record_seeds("users", &[1])
+    );
+}
+
+/// A record with composite seeds: one per byte of its primary key.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+struct Composite {
+    key: Vec<u8>,
+}
+
+impl NautilusRecordData for Composite {
+    const TABLE_NAME: &'static str = "composite";
+    const AUTO_INCREMENT: bool = false;
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.key.clone()
+    }
+
+    fn seeds(&self) -> Vec<Vec<u8>> {
+        self.key.iter().map(|byte| vec![*byte]).collect()
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+#[test]
+fn seed_count_accounts_for_the_bump() {
+    assert!(assert_seed_count(&vec![vec![0]; 15]).is_ok());
+    assert_eq!(
+        assert_seed_count(&vec![vec![0]; 16]),
+        Err(NautilusError::TooManySeeds(17).into())
+    );
+}
+
+#[test]
+fn create_record_with_17_seeds_fails() {
+    common::install_runtime();
+    let program_id = common::leak_key(Pubkey::new_unique());
+    let index = NautilusIndex::new(
+        program_id,
+        Box::new(common::account_info(
+            common::leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    let mut record: Record<Composite> = Record::new(
+        program_id,
+        Box::new(common::account_info(
+            common::leak_key(Pubkey::new_unique()),
+            &nautilus::system_program::ID,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+        index,
+    );
+    record.data.key = (0..17).collect();
+    let mut create = common::create(record, 1_000_000_000);
+    assert_eq!(
+        create.create_record(),
+        Err(NautilusError::TooManySeeds(18).into())
+    );
+    assert_eq!(create.lamports(), 0);
+}
+
+#[test]
+fn composed_keys_are_unambiguous() {
+    use nautilus::seeds::{compose_key, decompose_key};
+
+    let a_bc = compose_key(&[b"a", b"bc"]);
+    let ab_c = compose_key(&[b"ab", b"c"]);
+    assert_ne!(a_bc, ab_c);
+    assert_ne!(
+        Pubkey::find_program_address(&[b"hero", &a_bc], &Pubkey::default()),
+        Pubkey::find_program_address(&[b"hero", &ab_c], &Pubkey::default())
+    );
+
+    assert_eq!(
+        decompose_key(&a_bc),
+        Some(vec![b"a".to_vec(), b"bc".to_vec()])
+    );
+    assert_eq!(decompose_key(&compose_key(&[])), Some(vec![]));
+    assert_eq!(decompose_key(&compose_key(&[b""])), Some(vec![vec![]]));
+    assert_eq!(decompose_key(&a_bc[..a_bc.len() - 1]), None);
+    assert_eq!(decompose_key(&[1, 0]), None);
+}
+
+/// A record derived under the address of the hero it belongs to.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+struct Item {
+    id: u32,
+    hero: Pubkey,
+}
+
+impl NautilusRecordData for Item {
+    const TABLE_NAME: &'static str = "item";
+    const AUTO_INCREMENT: bool = true;
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.id.to_le_bytes().to_vec()
+    }
+
+    fn parent(&self) -> Option<Pubkey> {
+        Some(self.hero)
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+#[test]
+fn child_records_are_derived_under_their_parent() {
+    let program_id = Pubkey::new_unique();
+    let hero = Hero {
+        id: 1,
+        name: "Joe".to_string(),
+    };
+    let (hero_pda, _) = hero.pda(&program_id);
+    let item = Item {
+        id: 7,
+        hero: hero_pda,
+    };
+    assert_eq!(
+        item.seeds(),
+        vec![
+            hero_pda.to_bytes().to_vec(),
+            b"item".to_vec(),
+            vec![7, 0, 0, 0]
+        ]
+    );
+    assert_eq!(
+        item.pda(&program_id),
+        find_child(
+            &program_id,
+            &hero_pda,
+            Item::TABLE_NAME,
+            &7u32.to_le_bytes()
+        )
+    );
+    assert_ne!(
+        item.pda(&program_id),
+        find_child(
+            &program_id,
+            &Pubkey::new_unique(),
+            Item::TABLE_NAME,
+            &item.primary_key()
+        )
+    );
+    // A child keyed by a narrower integer is derived from its own bytes.
+    let seeds_vec = nautilus::seeds::child_record_seeds(
+        hero_pda.as_ref(),
+        Item::TABLE_NAME,
+        &7u16.to_le_bytes(),
+    );
+    let seeds: Vec<&[u8]> = seeds_vec.iter().map(AsRef::as_ref).collect();
+    assert_eq!(
+        find_child(
+            &program_id,
+            &hero_pda,
+            Item::TABLE_NAME,
+            &7u16.to_le_bytes()
+        ),
+        Pubkey::find_program_address(&seeds, &program_id)
+    );
+    assert!(assert_seed_count(&item.seeds()).is_ok());
+    assert_eq!(hero.seeds(), vec![b"hero".to_vec(), vec![1, 0, 0, 0]]);
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Car,
+    Truck,
+}
+
+impl Default for Kind {
+    fn default() -> Self {
+        Kind::Car
+    }
+}
+
+/// A record type standing for one logical table per `Kind`.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+struct Vehicle {
+    id: u32,
+    kind: Kind,
+}
+
+impl NautilusRecordData for Vehicle {
+    const TABLE_NAME: &'static str = "vehicle";
+    const AUTO_INCREMENT: bool = true;
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.id.to_le_bytes().to_vec()
+    }
+
+    fn variant_table_name(&self) -> String {
+        let variant = match self.kind {
+            Kind::Car => "car",
+            Kind::Truck => "truck",
+        };
+        nautilus::seeds::variant_table_name(Self::TABLE_NAME, variant)
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+}
+
+#[test]
+fn variants_are_counted_as_separate_tables() {
+    let mut index = NautilusIndexData::default();
+    let mut car = Vehicle {
+        id: 0,
+        kind: Kind::Car,
+    };
+    let mut truck = Vehicle {
+        id: 0,
+        kind: Kind::Truck,
+    };
+    car.id = index.add_record(&car.variant_table_name());
+    index.add_record(&car.variant_table_name());
+    truck.id = index.add_record(&truck.variant_table_name());
+    assert_eq!(car.variant_table_name(), "vehicle:car");
+    assert_eq!(index.get_count("vehicle:car"), Some(2));
+    assert_eq!(index.get_count("vehicle:truck"), Some(1));
+    assert_eq!(index.get_count(Vehicle::TABLE_NAME), None);
+
+    assert_eq!(car.id, truck.id);
+    assert_eq!(car.seeds(), vec![b"vehicle:car".to_vec(), vec![1, 0, 0, 0]]);
+    assert_ne!(car.pda(&Pubkey::default()), truck.pda(&Pubkey::default()));
+    assert_eq!(car.discriminator(), truck.discriminator());
+    assert_eq!(Hero::default().variant_table_name(), Hero::TABLE_NAME);
+}
+
+#[derive(Table)]
+pub struct Ticket {
+    #[primary_key(autoincrement = true)]
+    pub id: u8,
+    pub seat: String,
+}
+
+#[test]
+fn next_pda_matches_the_created_record() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let mut index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    for _ in 0..2 {
+        let next = next_pda::<Ticket>(program_id, &index.data, Ticket::TABLE_NAME).unwrap();
+        let ticket = Ticket::new(index.clone(), signer(u64::MAX), "A1".to_string()).unwrap();
+        assert_eq!(ticket.pda(program_id), next);
+        index = NautilusIndex::load(program_id, index.account_info.clone()).unwrap();
+    }
+    assert_eq!(
+        next_pda::<Ticket>(program_id, &index.data, Ticket::TABLE_NAME).unwrap(),
+        Ticket {
+            id: 3,
+            ..Default::default()
+        }
+        .pda(program_id)
+    );
+
+    // Case-insensitive mode derives under the normalized table name, like the
+    // record's own seeds.
+    let index = index.with_case_insensitive_table_names(true);
+    let record = Record::<Ticket> {
+        data: Box::new(Ticket {
+            id: 3,
+            ..Default::default()
+        }),
+        ..Record::new(
+            program_id,
+            Box::new(account_info(
+                leak_key(Pubkey::new_unique()),
+                program_id,
+                0,
+                vec![],
+                false,
+                true,
+            )),
+            index.clone(),
+        )
+    };
+    assert_eq!(
+        next_pda::<Ticket>(program_id, &index.data, "TICKET").unwrap(),
+        record.pda()
+    );
+
+    // An id that doesn't fit the primary key's type can't be derived.
+    let mut data = NautilusIndexData::default();
+    data.set_id_stride(
+        Ticket::TABLE_NAME,
+        IdStride {
+            start: u8::MAX as u32 + 1,
+            stride: 1,
+        },
+    );
+    assert_eq!(
+        next_pda::<Ticket>(program_id, &data, Ticket::TABLE_NAME),
+        Err(NautilusError::IdSpaceExhausted(String::default()).into())
+    );
+}
+
+#[test]
+fn autoincrement_new_rejects_an_id_its_key_type_cannot_hold() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut data = NautilusIndexData::default();
+    data.set_id_stride(
+        Ticket::TABLE_NAME,
+        IdStride {
+            start: u8::MAX as u32 - 5,
+            stride: 10,
+        },
+    );
+    let data = data.try_to_vec().unwrap();
+    let index = NautilusIndex::load(
+        program_id,
+        Box::new(reallocatable_account_info(
+            nautilus_index_pda(program_id).0,
+            program_id,
+            minimum_balance(data.len()).unwrap(),
+            data,
+            true,
+        )),
+    )
+    .unwrap();
+    let ticket = Ticket::new(index.clone(), signer(u64::MAX), "A1".to_string()).unwrap();
+    assert_eq!(ticket.id, u8::MAX - 5);
+
+    // The next id is a valid u32 but overflows the u8 key.
+    let index = NautilusIndex::load(program_id, index.account_info.clone()).unwrap();
+    assert_eq!(
+        Ticket::new(index, signer(u64::MAX), "A2".to_string()).err(),
+        Some(NautilusError::IdSpaceExhausted(String::default()).into())
+    );
+}
+
+#[test]
+fn assert_mutable_with_too_many_seeds_fails() {
+    let program_id = common::leak_key(Pubkey::new_unique());
+    let index = NautilusIndex::new(
+        program_id,
+        Box::new(common::account_info(
+            common::leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    let mut record: Record<Composite> = Record::new(
+        program_id,
+        Box::new(common::account_info(
+            common::leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+        index,
+    );
+    record.data.key = (0..16).collect();
+    assert_eq!(
+        record.assert_mutable(program_id),
+        Err(NautilusError::TooManySeeds(17).into())
+    );
+}
@@ -0,0 +1,317 @@
+use nautilus::{error::NautilusError, *};
+
+#[derive(Table)]
+pub struct Badge {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    pub owner: Pubkey,
+    pub roles: InlineVec<u8, 4>,
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Table)]
+pub struct Profile {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    pub bio: String,
+}
+
+#[test]
+fn inline_vec_round_trip() {
+    let mut roles: InlineVec<u16, 8> = InlineVec::new();
+    let empty_span = roles.try_to_vec().unwrap().len();
+    roles.push(1).unwrap();
+    roles.push(2).unwrap();
+    roles.push(3).unwrap();
+    assert_eq!(roles.remove(1), Some(2));
+    assert_eq!(roles.remove(5), None);
+
+    let bytes = roles.try_to_vec().unwrap();
+    assert_eq!(bytes.len(), empty_span);
+    assert_eq!(bytes.len(), 4 + 8 * 2);
+    let decoded = InlineVec::<u16, 8>::try_from_slice(&bytes).unwrap();
+    assert_eq!(decoded, roles);
+    assert_eq!(decoded.as_slice(), &[1, 3]);
+}
+
+#[test]
+fn inline_vec_overflow() {
+    let mut roles: InlineVec<u8, 2> = InlineVec::new();
+    roles.push(1).unwrap();
+    roles.push(2).unwrap();
+    assert!(roles.is_full());
+    assert_eq!(roles.push(3), Err(NautilusError::InlineVecFull(2).into()));
+    assert_eq!(roles.len(), 2);
+    assert!(InlineVec::<u8, 2>::try_from(vec![1, 2, 3]).is_err());
+
+    let mut too_long = 3u32.try_to_vec().unwrap();
+    too_long.extend_from_slice(&[1, 2]);
+    assert!(InlineVec::<u8, 2>::try_from_slice(&too_long).is_err());
+}
+
+#[test]
+fn short_vec_round_trip() {
+    let mut names: ShortVec<String> = ShortVec::new();
+    names.push("Joe".to_string()).unwrap();
+    names.push("Jane".to_string()).unwrap();
+    let bytes = names.try_to_vec().unwrap();
+    let standard = vec!["Joe".to_string(), "Jane".to_string()]
+        .try_to_vec()
+        .unwrap();
+    assert_eq!(bytes.len(), standard.len() - 2);
+    assert_eq!(&bytes[..2], &[2, 0]);
+    assert_eq!(ShortVec::<String>::try_from_slice(&bytes).unwrap(), names);
+
+    let empty = ShortVec::<u8>::new().try_to_vec().unwrap();
+    assert_eq!(empty, vec![0, 0]);
+    assert!(ShortVec::<u8>::try_from_slice(&[3, 0, 1, 2]).is_err());
+}
+
+#[test]
+fn short_vec_overflow() {
+    let mut full = ShortVec::try_from(vec![0u8; ShortVec::<u8>::MAX_LEN]).unwrap();
+    assert_eq!(
+        full.push(0),
+        Err(NautilusError::LengthPrefixOverflow(0, 0).into())
+    );
+    assert_eq!(full.len(), ShortVec::<u8>::MAX_LEN);
+    assert!(full.try_to_vec().is_ok());
+    assert!(ShortVec::try_from(vec![0u8; ShortVec::<u8>::MAX_LEN + 1]).is_err());
+}
+
+#[test]
+fn sorted_map_serializes_deterministically() {
+    let mut forward: SortedMap<String, u32> = SortedMap::new();
+    let mut backward: SortedMap<String, u32> = SortedMap::new();
+    let names = ["person", "car", "home", "zebra", "apple"];
+    for (i, name) in names.iter().enumerate() {
+        forward.insert(name.to_string(), i as u32);
+    }
+    for (i, name) in names.iter().enumerate().rev() {
+        backward.insert(name.to_string(), i as u32);
+    }
+    let bytes = forward.try_to_vec().unwrap();
+    assert_eq!(bytes, backward.try_to_vec().unwrap());
+    assert_eq!(bytes, forward.as_map().try_to_vec().unwrap());
+    assert_eq!(
+        SortedMap::<String, u32>::try_from_slice(&bytes).unwrap(),
+        forward
+    );
+    assert_eq!(
+        forward
+            .sorted_entries()
+            .into_iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["apple", "car", "home", "person", "zebra"]
+    );
+
+    let mut helper = vec![];
+    sorted_map_serialize(backward.as_map().iter(), &mut helper).unwrap();
+    assert_eq!(helper, bytes);
+
+    let mut nested: SortedMap<u8, SortedMap<String, u32>> = SortedMap::new();
+    nested.insert(2, backward);
+    nested.insert(1, SortedMap::new());
+    let bytes = nested.try_to_vec().unwrap();
+    assert_eq!(
+        SortedMap::<u8, SortedMap<String, u32>>::try_from_slice(&bytes).unwrap(),
+        nested
+    );
+}
+
+#[test]
+fn sorted_map_rejects_non_canonical_bytes() {
+    let unsorted = vec![(2u8, 0u8), (1, 0)].try_to_vec().unwrap();
+    assert!(SortedMap::<u8, u8>::try_from_slice(&unsorted).is_err());
+    let duplicated = vec![(1u8, 0u8), (1, 1)].try_to_vec().unwrap();
+    assert!(SortedMap::<u8, u8>::try_from_slice(&duplicated).is_err());
+    let sorted = vec![(1u8, 0u8), (2, 1)].try_to_vec().unwrap();
+    assert_eq!(
+        SortedMap::<u8, u8>::try_from_slice(&sorted).unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn rent_contributions_refund_proportionally() {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut contributions = RentContributions::new();
+    assert!(contributions.refund_plan(1_000).is_empty());
+
+    contributions.record_contribution(alice, 100);
+    contributions.record_contribution(bob, 100);
+    contributions.record_contribution(alice, 100);
+    assert_eq!(contributions.contribution(&alice), 200);
+    assert_eq!(contributions.total(), 300);
+    assert_eq!(contributions.as_slice().len(), 2);
+
+    assert_eq!(
+        contributions.refund_plan(1_000),
+        vec![(alice, 667), (bob, 333)]
+    );
+    assert_eq!(contributions.try_to_vec().unwrap().len(), 4 + 2 * 40);
+    assert_eq!(
+        RentContributions::try_from_slice(&contributions.try_to_vec().unwrap()).unwrap(),
+        contributions
+    );
+}
+
+#[test]
+fn record_fingerprint_tracks_content() {
+    let mut before = NautilusIndexData::default();
+    before.add_record("person");
+    before.add_record("car");
+    let mut after = NautilusIndexData::default();
+    after.add_record("car");
+    after.add_record("person");
+    assert_eq!(before.fingerprint().unwrap(), after.fingerprint().unwrap());
+    assert_eq!(
+        before.fingerprint().unwrap(),
+        nautilus::solana_program::hash::hash(&before.try_to_vec().unwrap()).to_bytes()
+    );
+
+    after.add_record("car");
+    assert_ne!(before.fingerprint().unwrap(), after.fingerprint().unwrap());
+}
+
+const BETA: u64 = NautilusFlags::flag(0);
+const LEGACY: u64 = NautilusFlags::flag(63);
+
+#[test]
+fn flags_are_set_and_cleared_in_place() {
+    let mut flags = NautilusFlags::default();
+    assert!(!flags.has_flag(BETA));
+    assert_eq!(
+        flags.require_flag(BETA),
+        Err(NautilusError::FlagNotSet(0).into())
+    );
+    let span = flags.try_to_vec().unwrap().len();
+
+    flags.set_flag(BETA);
+    flags.set_flag(LEGACY);
+    assert!(flags.has_flag(BETA) && flags.has_flag(LEGACY));
+    assert!(flags.has_flag(BETA | LEGACY));
+    assert!(flags.require_flag(BETA).is_ok());
+    assert_eq!(flags.bits(), 1 | 1 << 63);
+    assert_eq!(flags.try_to_vec().unwrap().len(), span);
+
+    flags.clear_flag(BETA);
+    assert!(!flags.has_flag(BETA | LEGACY));
+    assert_eq!(
+        NautilusFlags::try_from_slice(&flags.try_to_vec().unwrap()).unwrap(),
+        NautilusFlags::new(LEGACY)
+    );
+}
+
+#[test]
+fn max_span_bounds_fixed_and_bounded_types() {
+    assert_eq!(u64::max_span(), Some(8));
+    assert_eq!(Pubkey::max_span(), Some(32));
+    assert_eq!(<[u16; 3]>::max_span(), Some(6));
+    assert_eq!(<Option<u32>>::max_span(), Some(5));
+    assert_eq!(<(bool, u8)>::max_span(), Some(2));
+    assert_eq!(<InlineVec<u32, 8>>::max_span(), Some(4 + 32));
+    assert_eq!(NautilusFlags::max_span(), Some(8));
+    assert_eq!(String::max_span(), None);
+    assert_eq!(<Vec<u8>>::max_span(), None);
+    assert_eq!(<Option<String>>::max_span(), None);
+    assert_eq!(<SortedMap<u8, u8>>::max_span(), None);
+
+    let full = Badge {
+        id: u32::MAX,
+        owner: Pubkey::new_unique(),
+        roles: InlineVec::try_from(vec![1, 2, 3, 4]).unwrap(),
+        expires_at: Some(u64::MAX),
+    };
+    assert_eq!(Badge::max_span(), Some(full.span().unwrap()));
+    assert_eq!(Badge::min_span().unwrap(), Badge::max_span().unwrap() - 8);
+    assert_eq!(Profile::max_span(), None);
+    assert_eq!(
+        Profile::min_span().unwrap(),
+        Profile::default().try_to_vec().unwrap().len()
+    );
+}
+
+type Usd = Decimal<2>;
+
+#[test]
+fn decimals_round_half_away_from_zero() {
+    let parse = |string: &str| string.parse::<Usd>();
+    assert_eq!(parse("12.34"), Ok(Usd::new(1234)));
+    assert_eq!(parse("-0.5"), Ok(Usd::new(-50)));
+    assert_eq!(parse("+.5"), Ok(Usd::new(50)));
+    assert_eq!(parse("7"), Ok(Usd::new(700)));
+    assert_eq!(parse("0.004"), Ok(Usd::new(0)));
+    assert_eq!(parse("0.005"), Ok(Usd::new(1)));
+    assert_eq!(parse("-0.005"), Ok(Usd::new(-1)));
+    assert_eq!(parse("1.999"), Ok(Usd::new(200)));
+    for invalid in ["", ".", "-", "1.2.3", "1,5", "1e3", "--1", " 1"] {
+        assert_eq!(
+            parse(invalid),
+            Err(NautilusError::InvalidDecimal(String::default()).into())
+        );
+    }
+
+    assert_eq!(Usd::new(1234).to_string(), "12.34");
+    assert_eq!(Usd::new(-50).to_string(), "-0.50");
+    assert_eq!(Usd::new(5).to_string(), "0.05");
+    assert_eq!(Usd::default().to_string(), "0.00");
+    assert_eq!(Decimal::<0>::new(-42).to_string(), "-42");
+    for mantissa in [i128::MIN, -1, 0, 1, i128::MAX] {
+        let decimal = Decimal::<6>::new(mantissa);
+        assert_eq!(decimal.to_string().parse(), Ok(decimal));
+    }
+
+    // 1.25 * 0.5 = 0.625, and -1.25 * 0.5 = -0.625.
+    assert_eq!(Usd::new(125).checked_mul(Usd::new(50)), Ok(Usd::new(63)));
+    assert_eq!(Usd::new(-125).checked_mul(Usd::new(50)), Ok(Usd::new(-63)));
+    assert_eq!(Usd::new(124).checked_mul(Usd::new(50)), Ok(Usd::new(62)));
+    assert_eq!(
+        Usd::from_integer(3)
+            .unwrap()
+            .checked_mul(parse("1.10").unwrap()),
+        Ok(Usd::new(330))
+    );
+
+    let price = Usd::new(1999);
+    assert_eq!(
+        Usd::try_from_slice(&price.try_to_vec().unwrap()).unwrap(),
+        price
+    );
+    assert_eq!(price.try_to_vec().unwrap(), 1999i128.to_le_bytes());
+    assert_eq!(Usd::max_span(), Some(16));
+}
+
+#[test]
+fn decimals_fail_on_overflow_at_the_scale_boundaries() {
+    let overflow = Err(NautilusError::ArithmeticOverflow.into());
+    let max = Usd::new(i128::MAX);
+    let min = Usd::new(i128::MIN);
+    assert_eq!(max.checked_add(Usd::new(1)), overflow);
+    assert_eq!(min.checked_sub(Usd::new(1)), overflow);
+    assert_eq!(max.checked_sub(Usd::new(1)), Ok(Usd::new(i128::MAX - 1)));
+    assert_eq!(max.checked_mul(Usd::from_integer(1).unwrap()), overflow);
+    assert_eq!(max.checked_mul(Usd::new(1)), Ok(Usd::new(i128::MAX / 100)));
+    assert_eq!(Usd::from_integer(i128::MAX / 100 + 1), overflow);
+    assert!(Usd::from_integer(i128::MAX / 100).is_ok());
+
+    assert_eq!(max.to_string().parse(), Ok(max));
+    assert_eq!(min.to_string().parse(), Ok(min));
+    // Rounding up the last digit can carry past the largest mantissa.
+    assert_eq!(format!("{}9", max).parse::<Usd>(), overflow);
+    assert_eq!(format!("{}1", max).parse::<Usd>(), Ok(max));
+    let past_max = "1701411834604692317316873037158841057.28";
+    assert_eq!(past_max.parse::<Usd>(), overflow);
+    assert_eq!(format!("-{}", past_max).parse(), Ok(min));
+    assert_eq!(
+        "-1701411834604692317316873037158841057.29".parse::<Usd>(),
+        overflow
+    );
+
+    // A scale too large for an `i128` can't hold a whole number.
+    let overflow = Err(NautilusError::ArithmeticOverflow.into());
+    assert_eq!(Decimal::<39>::from_integer(1), overflow);
+    assert_eq!("1".parse::<Decimal<39>>(), overflow);
+    assert!(Decimal::<38>::from_integer(1).is_ok());
+}
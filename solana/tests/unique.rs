@@ -0,0 +1,125 @@
+mod common;
+
+use common::{account_info, create, install_runtime, leak_key};
+use nautilus::{error::NautilusError, *};
+
+/// A record whose `username` must be unique.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+struct Member {
+    id: u32,
+    username: String,
+}
+
+impl NautilusRecordData for Member {
+    const TABLE_NAME: &'static str = "member";
+    const AUTO_INCREMENT: bool = true;
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.id.to_le_bytes().to_vec()
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+
+    fn unique_fields(&self) -> Vec<(&'static str, Vec<u8>)> {
+        vec![("username", self.username.as_bytes().to_vec())]
+    }
+}
+
+fn member(id: u32, username: &str) -> Member {
+    Member {
+        id,
+        username: username.to_string(),
+    }
+}
+
+fn violation() -> ProgramError {
+    NautilusError::UniqueConstraintViolation(String::default(), String::default()).into()
+}
+
+#[test]
+fn unique_field_collisions_are_rejected() {
+    let mut data = NautilusIndexData::default();
+    data.claim_unique_fields(&member(1, "joe")).unwrap();
+    assert_eq!(
+        data.claim_unique_fields(&member(2, "joe")),
+        Err(violation())
+    );
+    data.claim_unique_fields(&member(1, "joe")).unwrap();
+    assert_eq!(
+        data.get_unique_owner("member", "username", b"joe"),
+        Some(&1u32.to_le_bytes()[..])
+    );
+
+    // Renaming a member releases their old username.
+    data.claim_unique_fields(&member(1, "joseph")).unwrap();
+    assert_eq!(data.get_unique_owner("member", "username", b"joe"), None);
+    data.claim_unique_fields(&member(2, "joe")).unwrap();
+
+    let mut decoded = NautilusIndexData::try_from_slice(&data.try_to_vec().unwrap()).unwrap();
+    assert_eq!(
+        decoded.check_unique_fields(&member(3, "joseph")),
+        Err(violation())
+    );
+    assert_eq!(
+        decoded.get_unique_owner("member", "username", b"joe"),
+        Some(&2u32.to_le_bytes()[..])
+    );
+
+    decoded.release_unique_fields(&member(1, "joseph"));
+    assert!(decoded.check_unique_fields(&member(3, "joseph")).is_ok());
+}
+
+#[test]
+fn create_record_rejects_duplicate_unique_value() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let system_program = leak_key(system_program::ID);
+    let mut index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    index.data.claim_unique_fields(&member(1, "joe")).unwrap();
+    let generation = index.generation();
+
+    let mut record = Record::<Member>::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            system_program,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+        index,
+    );
+    record.data = Box::new(member(2, "joe"));
+    let (pda, _) = record.pda();
+    record.account_info = Box::new(account_info(
+        leak_key(pda),
+        system_program,
+        0,
+        vec![],
+        false,
+        true,
+    ));
+    let mut create = create(record, 1_000_000_000);
+
+    assert_eq!(create.create_record(), Err(violation()));
+    assert_eq!(create.self_account.index.generation(), generation);
+    assert_eq!(create.lamports(), 0);
+    assert_eq!(create.fee_payer.lamports(), 1_000_000_000);
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key};
+use nautilus::{error::NautilusError, *};
+
+/// A record whose `authority` must be set to a wallet.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Default, Debug, PartialEq)]
+struct Vault {
+    id: u32,
+    authority: Pubkey,
+}
+
+impl NautilusRecordData for Vault {
+    const TABLE_NAME: &'static str = "vault";
+    const AUTO_INCREMENT: bool = true;
+
+    fn primary_key(&self) -> Vec<u8> {
+        self.id.to_le_bytes().to_vec()
+    }
+
+    fn check_authorities(&self, _accounts: Vec<AccountInfo>) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    fn count_authorities(&self) -> u8 {
+        0
+    }
+
+    fn validate(&self) -> ProgramResult {
+        assert_non_default_key("authority", &self.authority)?;
+        assert_on_curve("authority", &self.authority)
+    }
+}
+
+/// The public key of the first RFC 8032 ed25519 test vector.
+fn wallet() -> Pubkey {
+    Pubkey::new_from_array([
+        0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64, 0x07,
+        0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68, 0xf7, 0x07,
+        0x51, 0x1a,
+    ])
+}
+
+#[test]
+fn default_key_is_rejected() {
+    assert_eq!(
+        assert_non_default_key("authority", &Pubkey::default()),
+        Err(NautilusError::DefaultKey(String::default()).into())
+    );
+    assert!(assert_non_default_key("authority", &Pubkey::new_unique()).is_ok());
+}
+
+#[test]
+fn off_curve_key_is_rejected() {
+    let (pda, _) = Pubkey::find_program_address(&[b"vault"], &Pubkey::new_unique());
+    assert_eq!(
+        assert_on_curve("authority", &pda),
+        Err(NautilusError::KeyNotOnCurve(String::default(), String::default()).into())
+    );
+    assert!(assert_on_curve("authority", &wallet()).is_ok());
+}
+
+#[test]
+fn create_record_validates_data_first() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let system_program = leak_key(system_program::ID);
+    let index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    let mut record = Record::<Vault>::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            system_program,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+        index,
+    );
+    record.data.id = 1;
+    let (pda, _) = record.pda();
+    record.account_info = Box::new(account_info(
+        leak_key(pda),
+        system_program,
+        0,
+        vec![],
+        false,
+        true,
+    ));
+    let mut create = Create::new(
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            system_program,
+            1_000_000_000,
+            vec![],
+            true,
+            true,
+        )),
+        Box::new(account_info(
+            system_program,
+            system_program,
+            0,
+            vec![],
+            false,
+            false,
+        )),
+        Box::new(account_info(
+            leak_key(sysvar::rent::ID),
+            system_program,
+            0,
+            vec![],
+            false,
+            false,
+        )),
+        record,
+    )
+    .unwrap();
+
+    assert_eq!(
+        create.create_record(),
+        Err(NautilusError::DefaultKey(String::default()).into())
+    );
+    let (off_curve, _) = Pubkey::find_program_address(&[b"vault"], program_id);
+    create.self_account.data.authority = off_curve;
+    assert_eq!(
+        create.create_record(),
+        Err(NautilusError::KeyNotOnCurve(String::default(), String::default()).into())
+    );
+    assert_eq!(create.lamports(), 0);
+    assert_eq!(create.fee_payer.lamports(), 1_000_000_000);
+}
+
+#[test]
+fn strings_are_bounded_and_free_of_control_characters() {
+    assert!(validate_string("name", "", 4).is_ok());
+    assert!(validate_string("name", "Joe!", 4).is_ok());
+    assert_eq!(
+        validate_string("name", "Joey!", 4),
+        Err(NautilusError::StringTooLong(String::default(), 0, 0).into())
+    );
+    // Multi-byte characters count by their bytes.
+    assert!(validate_string("name", "é", 2).is_ok());
+    assert_eq!(
+        validate_string("name", "é", 1),
+        Err(NautilusError::StringTooLong(String::default(), 0, 0).into())
+    );
+    for value in ["a\nb", "\0", "tab\t", "\u{7f}"] {
+        assert_eq!(
+            validate_string("name", value, 8),
+            Err(NautilusError::InvalidString(String::default()).into())
+        );
+    }
+}
+
+#[test]
+fn index_rejects_invalid_table_names() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let mut index = NautilusIndex::new(
+        program_id,
+        Box::new(account_info(
+            leak_key(Pubkey::new_unique()),
+            program_id,
+            0,
+            vec![],
+            false,
+            true,
+        )),
+    );
+    let mut txn = index.txn();
+    assert_eq!(
+        txn.add_record("hero\n"),
+        Err(NautilusError::InvalidString(String::default()).into())
+    );
+    assert_eq!(
+        txn.add_record(&"h".repeat(33)),
+        Err(NautilusError::StringTooLong(String::default(), 0, 0).into())
+    );
+    assert_eq!(txn.add_record(&"h".repeat(32)), Ok(1));
+    assert_eq!(
+        index.simulate_add_record("hero\0", &Pubkey::default()),
+        Err(NautilusError::InvalidString(String::default()).into())
+    );
+    assert_eq!(index.data.index.len(), 1);
+}
@@ -0,0 +1,54 @@
+use nautilus::*;
+
+#[derive(Table)]
+pub struct Profile {
+    #[primary_key(autoincrement = true)]
+    pub id: u32,
+    pub tags: Vec<String>,
+    pub bio: String,
+    pub owner: Pubkey,
+    pub score: Option<u64>,
+}
+
+fn profile() -> Profile {
+    Profile {
+        id: 7,
+        tags: vec!["rust".to_string(), "solana".to_string()],
+        bio: "Builds things.".to_string(),
+        owner: Pubkey::new_unique(),
+        score: Some(42),
+    }
+}
+
+#[test]
+fn views_read_fields_lazily_from_borrowed_data() {
+    let profile = profile();
+    let bytes = profile.try_to_vec().unwrap();
+    let view = ProfileView::new(&bytes).unwrap();
+    assert_eq!(view.id().unwrap(), profile.id);
+    assert_eq!(view.tags().unwrap(), profile.tags);
+    assert_eq!(view.owner().unwrap(), profile.owner);
+    assert_eq!(view.score().unwrap(), profile.score);
+
+    let bio = view.bio().unwrap();
+    assert_eq!(bio, profile.bio);
+    let data = bytes.as_ptr_range();
+    assert!(data.contains(&bio.as_ptr()));
+
+    let truncated = &bytes[..bytes.len() - 4];
+    let view = ProfileView::new(truncated).unwrap();
+    assert_eq!(view.bio().unwrap(), profile.bio);
+    assert!(view.score().is_err());
+}
+
+#[test]
+fn views_check_the_discriminator() {
+    let bytes = profile().try_to_vec().unwrap();
+    assert_eq!(
+        ProfileView::new(&bytes[8..]).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+    let mut other = bytes.clone();
+    other[0] ^= 1;
+    assert!(ProfileView::new(&other).is_err());
+}
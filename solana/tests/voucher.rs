@@ -0,0 +1,157 @@
+mod common;
+
+use common::{account_info, install_runtime, leak_key, reallocatable_account_info, signer};
+use nautilus::solana_program::{
+    ed25519_program,
+    sysvar::instructions::{construct_instructions_data, store_current_index, BorrowedInstruction},
+};
+use nautilus::{error::NautilusError, *};
+
+/// Builds the Instructions sysvar account for a transaction made of `data`'s
+/// instruction for `program`, followed by the current instruction.
+fn instructions_sysvar(program: &Pubkey, data: &[u8]) -> Box<AccountInfo<'static>> {
+    let current_program = Pubkey::new_unique();
+    let mut sysvar_data = construct_instructions_data(&[
+        BorrowedInstruction {
+            program_id: program,
+            accounts: vec![],
+            data,
+        },
+        BorrowedInstruction {
+            program_id: &current_program,
+            accounts: vec![],
+            data: &[],
+        },
+    ]);
+    store_current_index(&mut sysvar_data, 1);
+    let key = leak_key(sysvar::instructions::ID);
+    Box::new(account_info(key, key, 0, sysvar_data, false, false))
+}
+
+#[test]
+fn index_vouchers_are_verified_before_redemption() {
+    let program_id = leak_key(Pubkey::new_unique());
+    let authority = leak_key(Pubkey::new_unique());
+    let mut data = NautilusIndexData::default();
+    data.add_record("person");
+    data.voucher_nonce = 5;
+    let index_key = leak_key(Pubkey::new_unique());
+    let index_account = Box::new(account_info(
+        index_key,
+        program_id,
+        0,
+        data.try_to_vec().unwrap(),
+        false,
+        true,
+    ));
+    let voucher = IndexVoucher {
+        index: *index_key,
+        table_name: "person".to_string(),
+        max_count: 2,
+        nonce: 6,
+    };
+    let signed = |voucher: &IndexVoucher, signer: &Pubkey| {
+        ed25519_instruction_data(signer, &[7; 64], &voucher.message().unwrap())
+    };
+    let invalid: Result<u32, ProgramError> =
+        Err(NautilusError::InvalidVoucher(String::default()).into());
+    let index = |sysvar: Box<AccountInfo<'static>>| {
+        let mut index = NautilusIndex::new(program_id, index_account.clone())
+            .with_voucher_authority(authority, sysvar);
+        index.data = data.clone();
+        index
+    };
+
+    let mut unconfigured = NautilusIndex::new(program_id, index_account.clone());
+    assert_eq!(
+        unconfigured.add_record_with_voucher("person", &voucher, signer(0)),
+        invalid.clone()
+    );
+
+    let signed_by_other = signed(&voucher, &Pubkey::new_unique());
+    let not_ed25519 = signed(&voucher, authority);
+    let mut tampered = voucher.clone();
+    tampered.max_count = 100;
+    let signed_other_voucher = signed(&tampered, authority);
+    for sysvar in [
+        instructions_sysvar(&ed25519_program::ID, &signed_by_other),
+        instructions_sysvar(&Pubkey::new_unique(), &not_ed25519),
+        instructions_sysvar(&ed25519_program::ID, &signed_other_voucher),
+    ] {
+        assert_eq!(
+            index(sysvar).add_record_with_voucher("person", &voucher, signer(0)),
+            invalid.clone()
+        );
+    }
+
+    // Signed by the authority, but not redeemable here or any more.
+    for (mutate, err) in [
+        (
+            (|voucher: &mut IndexVoucher| voucher.index = Pubkey::new_unique()) as fn(&mut _),
+            invalid.clone(),
+        ),
+        (|voucher| voucher.max_count = 1, invalid.clone()),
+        (
+            |voucher| voucher.nonce = 5,
+            Err(NautilusError::VoucherReplayed(0, 0).into()),
+        ),
+    ] {
+        let mut voucher = voucher.clone();
+        mutate(&mut voucher);
+        let sysvar = instructions_sysvar(&ed25519_program::ID, &signed(&voucher, authority));
+        let mut index = index(sysvar);
+        assert_eq!(
+            index.add_record_with_voucher("person", &voucher, signer(0)),
+            err
+        );
+        assert_eq!(index.data.voucher_nonce, 5);
+    }
+    let sysvar = instructions_sysvar(&ed25519_program::ID, &signed(&voucher, authority));
+    assert_eq!(
+        index(sysvar).add_record_with_voucher("car", &voucher, signer(0)),
+        invalid
+    );
+}
+
+#[test]
+fn index_vouchers_name_their_table_as_the_index_resolves_it() {
+    install_runtime();
+    let program_id = leak_key(Pubkey::new_unique());
+    let authority = leak_key(Pubkey::new_unique());
+    let data = NautilusIndexData::default().try_to_vec().unwrap();
+    let index_account = Box::new(reallocatable_account_info(
+        nautilus_index_pda(program_id).0,
+        program_id,
+        minimum_balance(data.len()).unwrap(),
+        data,
+        true,
+    ));
+    let voucher = IndexVoucher {
+        index: *index_account.key,
+        table_name: "Person".to_string(),
+        max_count: 1,
+        nonce: 1,
+    };
+    let sysvar = || {
+        instructions_sysvar(
+            &ed25519_program::ID,
+            &ed25519_instruction_data(authority, &[7; 64], &voucher.message().unwrap()),
+        )
+    };
+
+    let mut case_sensitive = NautilusIndex::load(program_id, index_account.clone())
+        .unwrap()
+        .with_voucher_authority(authority, sysvar());
+    assert_eq!(
+        case_sensitive.add_record_with_voucher("person", &voucher, signer(u64::MAX)),
+        Err(NautilusError::InvalidVoucher(String::default()).into())
+    );
+    let mut case_insensitive = NautilusIndex::load(program_id, index_account)
+        .unwrap()
+        .with_case_insensitive_table_names(true)
+        .with_voucher_authority(authority, sysvar());
+    assert_eq!(
+        case_insensitive.add_record_with_voucher("person", &voucher, signer(u64::MAX)),
+        Ok(1)
+    );
+}